@@ -1,5 +1,4 @@
 use std::{
-    collections::HashSet,
     str::FromStr,
     sync::{
         atomic::{AtomicBool, Ordering},
@@ -11,7 +10,7 @@ use std::{
 
 use cached::{Cached, TimedCache};
 use dashmap::DashMap;
-use jito_core::ofac::is_tx_ofac_related;
+use jito_core::ofac::OfacFilter;
 use jito_protos::{
     auth::{
         auth_service_client::AuthServiceClient, GenerateAuthChallengeRequest,
@@ -114,7 +113,7 @@ impl BlockEngineRelayerHandler {
         aoi_cache_ttl_s: u64,
         address_lookup_table_cache: Arc<DashMap<Pubkey, AddressLookupTableAccount>>,
         is_connected_to_block_engine: &Arc<AtomicBool>,
-        ofac_addresses: HashSet<Pubkey>,
+        ofac_filter: OfacFilter,
     ) -> BlockEngineRelayerHandler {
         let is_connected_to_block_engine = is_connected_to_block_engine.clone();
         let block_engine_forwarder = block_engine_config.map(|config| {
@@ -133,7 +132,7 @@ impl BlockEngineRelayerHandler {
                                 aoi_cache_ttl_s,
                                 &address_lookup_table_cache,
                                 &is_connected_to_block_engine,
-                                &ofac_addresses,
+                                &ofac_filter,
                             )
                             .await;
                             is_connected_to_block_engine.store(false, Ordering::Relaxed);
@@ -226,7 +225,7 @@ impl BlockEngineRelayerHandler {
         aoi_cache_ttl_s: u64,
         address_lookup_table_cache: &Arc<DashMap<Pubkey, AddressLookupTableAccount>>,
         is_connected_to_block_engine: &Arc<AtomicBool>,
-        ofac_addresses: &HashSet<Pubkey>,
+        ofac_filter: &OfacFilter,
     ) -> BlockEngineResult<()> {
         let mut auth_endpoint = Endpoint::from_str(auth_service_url).expect("valid auth url");
         if auth_service_url.contains("https") {
@@ -291,7 +290,7 @@ impl BlockEngineRelayerHandler {
             aoi_cache_ttl_s,
             address_lookup_table_cache,
             is_connected_to_block_engine,
-            ofac_addresses,
+            ofac_filter,
         )
         .await
     }
@@ -313,7 +312,7 @@ impl BlockEngineRelayerHandler {
         aoi_cache_ttl_s: u64,
         address_lookup_table_cache: &Arc<DashMap<Pubkey, AddressLookupTableAccount>>,
         is_connected_to_block_engine: &Arc<AtomicBool>,
-        ofac_addresses: &HashSet<Pubkey>,
+        ofac_filter: &OfacFilter,
     ) -> BlockEngineResult<()> {
         let subscribe_aoi_stream = client
             .subscribe_accounts_of_interest(AccountsOfInterestRequest {})
@@ -345,7 +344,7 @@ impl BlockEngineRelayerHandler {
             aoi_cache_ttl_s,
             address_lookup_table_cache,
             is_connected_to_block_engine,
-            ofac_addresses,
+            ofac_filter,
         )
         .await
     }
@@ -364,7 +363,7 @@ impl BlockEngineRelayerHandler {
         aoi_cache_ttl_s: u64,
         address_lookup_table_cache: &Arc<DashMap<Pubkey, AddressLookupTableAccount>>,
         is_connected_to_block_engine: &Arc<AtomicBool>,
-        ofac_addresses: &HashSet<Pubkey>,
+        ofac_filter: &OfacFilter,
     ) -> BlockEngineResult<()> {
         let mut aoi_stream = subscribe_aoi_stream.into_inner();
         let mut poi_stream = subscribe_poi_stream.into_inner();
@@ -434,7 +433,7 @@ impl BlockEngineRelayerHandler {
                     let num_packets: u64 = block_engine_batches.banking_packet_batch.0.iter().map(|b|b.len() as u64).sum::<u64>();
                     block_engine_stats.increment_num_packets_received(num_packets);
 
-                    let filtered_packets = Self::filter_packets(block_engine_batches, num_packets, &mut accounts_of_interest, &mut programs_of_interest, address_lookup_table_cache, ofac_addresses);
+                    let filtered_packets = Self::filter_packets(block_engine_batches, num_packets, &mut accounts_of_interest, &mut programs_of_interest, address_lookup_table_cache, ofac_filter, &mut block_engine_stats);
                     block_engine_stats.increment_packet_filter_elapsed_us(now.elapsed().as_micros() as u64);
 
                     if let Some(filtered_packets) = filtered_packets {
@@ -630,16 +629,18 @@ impl BlockEngineRelayerHandler {
         }
     }
 
-    /// Filters out packets that aren't on list of interest
+    /// Filters out packets that aren't on list of interest or involve OFAC-sanctioned addresses
     fn filter_packets(
         block_engine_batches: BlockEnginePackets,
         num_packets: u64,
         accounts_of_interest: &mut TimedCache<Pubkey, u8>,
         programs_of_interest: &mut TimedCache<Pubkey, u8>,
         address_lookup_table_cache: &DashMap<Pubkey, AddressLookupTableAccount>,
-        ofac_addresses: &HashSet<Pubkey>,
+        ofac_filter: &OfacFilter,
+        block_engine_stats: &mut BlockEngineStats,
     ) -> Option<ExpiringPacketBatch> {
         let mut filtered_packets = Vec::with_capacity(num_packets as usize);
+        let ofac_addresses = ofac_filter.addresses().load();
 
         for batch in &block_engine_batches.banking_packet_batch.0 {
             for packet in batch {
@@ -648,27 +649,26 @@ impl BlockEngineRelayerHandler {
                 }
 
                 if let Ok(tx) = packet.deserialize_slice::<VersionedTransaction, _>(..) {
-                    let is_forwardable = if ofac_addresses.is_empty() {
+                    let mut is_forwardable =
                         is_aoi_in_static_keys(&tx, accounts_of_interest, programs_of_interest)
                             || is_aoi_in_lookup_table(
                                 &tx,
                                 accounts_of_interest,
                                 programs_of_interest,
                                 address_lookup_table_cache,
-                            )
-                    } else {
-                        !is_tx_ofac_related(&tx, ofac_addresses, address_lookup_table_cache)
-                            && (is_aoi_in_static_keys(
-                                &tx,
-                                accounts_of_interest,
-                                programs_of_interest,
-                            ) || is_aoi_in_lookup_table(
-                                &tx,
-                                accounts_of_interest,
-                                programs_of_interest,
-                                address_lookup_table_cache,
-                            ))
-                    };
+                            );
+
+                    // only check OFAC for packets that would otherwise be forwarded so the drop
+                    // counts reflect packets actually withheld from the block engine
+                    if is_forwardable && !ofac_addresses.is_empty() {
+                        let verdict =
+                            ofac_filter.verdict(&tx, &ofac_addresses, address_lookup_table_cache);
+                        if ofac_filter.should_drop(&verdict) {
+                            block_engine_stats.increment_ofac_dropped(&verdict);
+                            ofac_filter.audit("block_engine", &tx, &verdict);
+                            is_forwardable = false;
+                        }
+                    }
 
                     if is_forwardable {
                         if let Some(packet) = packet_to_proto_packet(packet) {
@@ -1,3 +1,4 @@
+use jito_core::ofac::{OfacDropCounts, OfacVerdict};
 use solana_metrics::datapoint_info;
 
 #[derive(Default)]
@@ -15,6 +16,8 @@ pub struct BlockEngineStats {
 
     num_packets_received: u64,
 
+    ofac_drops: OfacDropCounts,
+
     packet_filter_elapsed_us: u64,
     packet_forward_elapsed_us: u64,
 
@@ -70,6 +73,10 @@ impl BlockEngineStats {
         self.num_packets_received = self.num_packets_received.saturating_add(num)
     }
 
+    pub fn increment_ofac_dropped(&mut self, verdict: &OfacVerdict) {
+        self.ofac_drops.increment(verdict)
+    }
+
     pub fn increment_packet_filter_elapsed_us(&mut self, num: u64) {
         self.packet_filter_elapsed_us = self.packet_filter_elapsed_us.saturating_add(num)
     }
@@ -123,6 +130,27 @@ impl BlockEngineStats {
             ("poi_update_elapsed_us", self.poi_update_elapsed_us, i64),
             ("poi_accounts_received", self.poi_accounts_received, i64),
             ("num_packets_received", self.num_packets_received, i64),
+            ("num_ofac_dropped", self.ofac_drops.total(), i64),
+            (
+                "num_ofac_dropped_static_key",
+                self.ofac_drops.static_key,
+                i64
+            ),
+            (
+                "num_ofac_dropped_lookup_table_writable",
+                self.ofac_drops.lookup_table_writable,
+                i64
+            ),
+            (
+                "num_ofac_dropped_lookup_table_readonly",
+                self.ofac_drops.lookup_table_readonly,
+                i64
+            ),
+            (
+                "num_ofac_dropped_unresolvable_lookup_table",
+                self.ofac_drops.unresolvable_lookup_table,
+                i64
+            ),
             (
                 "packet_filter_elapsed_us",
                 self.packet_filter_elapsed_us,
@@ -8,7 +8,11 @@
 //! - **Fetch Stage**: Handles transaction forwarding between validators with loop prevention
 //! - **Staked Nodes Updater**: Maintains real-time validator stake information for
 //!   resource allocation and prioritization
-//! - **OFAC Compliance**: Filters transactions involving sanctioned addresses
+//! - **Lookup Table Cache**: Keeps address lookup tables current with a program subscription
+//! - **OFAC Compliance**: Filters transactions involving sanctioned addresses, with a
+//!   hot-reloadable sanctions list and background fetching of missing lookup tables
+//! - **Pubkey Lists**: Watched pubkey list files, shared by the sanctions list and the
+//!   relayer's validator allow and deny lists
 //! - **Graceful Shutdown**: Coordinated shutdown system for multi-threaded operations
 //! 
 //! The core crate is designed to be validator-agnostic and provides clean abstractions
@@ -32,7 +36,11 @@ mod fetch_stage;
 mod staked_nodes_updater_service;
 
 // Public modules
+pub mod lookup_table_cache;
+pub mod lookup_table_fetcher;
 pub mod ofac;
+pub mod ofac_updater;
+pub mod pubkey_list;
 pub mod tpu;
 
 /// Sets up a graceful panic handler that coordinates shutdown across all threads.
@@ -0,0 +1,789 @@
+//! Incrementally maintained cache of address lookup tables.
+//!
+//! Fetching every lookup table with `getProgramAccounts` is a huge, slow RPC call, and
+//! tables created between refreshes are invisible to the OFAC and accounts-of-interest checks.
+//! Instead, the cache is seeded with a single full scan and then kept current with a
+//! `programSubscribe` stream on the address lookup table program:
+//! - **Created/extended** tables are upserted with their latest addresses
+//! - **Deactivated** tables keep their addresses, since they remain usable until closed
+//! - **Closed** tables are removed
+//!
+//! A full scan is repeated whenever the subscription reconnects (updates may have been missed
+//! while disconnected) and on a slow reconciliation interval as a safety net. Some RPC providers
+//! disable or limit `programSubscribe`, so while the subscription is down the cache falls back
+//! to full scans on a shorter interval, starting with one as soon as the first subscription
+//! attempt fails. The staleness of the cache is reported as a metric and exposed through
+//! [`LookupTableCacheHandle`].
+//!
+//! Optionally, the cache is periodically snapshotted to a compact file versioned by slot.
+//! On boot the snapshot is loaded before anything else, so a restarted relayer has usable
+//! lookup table data within milliseconds while the first full scan reconciles it in the
+//! background.
+
+use std::{
+    collections::HashSet,
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    thread::{self, sleep, Builder, JoinHandle},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crossbeam_channel::RecvTimeoutError;
+use dashmap::DashMap;
+use jito_rpc::load_balancer::LoadBalancer;
+use log::{debug, error, info, warn};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    client_error,
+    pubsub_client::PubsubClient,
+    rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+};
+use solana_metrics::{datapoint_error, datapoint_info};
+use solana_sdk::{
+    account::Account,
+    address_lookup_table::{state::AddressLookupTable, AddressLookupTableAccount},
+    clock::Slot,
+    commitment_config::CommitmentConfig,
+    pubkey,
+    pubkey::Pubkey,
+};
+use thiserror::Error;
+
+pub const ADDRESS_LOOKUP_TABLE_PROGRAM_ID: Pubkey =
+    pubkey!("AddressLookupTab1e1111111111111111111111111");
+
+/// Bumped whenever the snapshot file layout changes; snapshots with another version are ignored
+const SNAPSHOT_VERSION: u32 = 1;
+
+#[derive(Error, Debug)]
+pub enum LookupTableSnapshotError {
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("serialization error: {0}")]
+    Serialization(#[from] bincode::Error),
+
+    #[error("unsupported snapshot version: {0}")]
+    UnsupportedVersion(u32),
+}
+
+pub type LookupTableSnapshotResult<T> = Result<T, LookupTableSnapshotError>;
+
+/// Where and how often the lookup table cache is snapshotted to disk
+#[derive(Clone, Debug)]
+pub struct LookupTableSnapshotConfig {
+    pub path: PathBuf,
+    pub interval: Duration,
+}
+
+/// Contents of a lookup table cache snapshot
+pub struct LookupTableSnapshot {
+    /// Highest slot reflected in the snapshot
+    pub slot: Slot,
+    /// Unix time in milliseconds when the snapshot was taken
+    pub saved_at_ms: u64,
+    pub lookup_tables: Vec<AddressLookupTableAccount>,
+}
+
+/// Result of applying a single account update to the cache
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LookupTableUpdate {
+    /// A table that wasn't in the cache was added
+    Created,
+    /// An existing table was replaced with its latest addresses
+    Extended,
+    /// The table was deactivated; its addresses are kept until it's closed
+    Deactivated,
+    /// The table was closed and removed from the cache
+    Closed,
+    /// The account couldn't be deserialized as a lookup table
+    Invalid,
+}
+
+/// Freshness information shared between the cache updater and its readers
+#[derive(Default)]
+struct LookupTableCacheStatus {
+    /// Unix time in milliseconds of the last full scan or subscription update
+    last_update_ms: AtomicU64,
+    /// Highest slot reflected in the cache
+    last_update_slot: AtomicU64,
+    /// True while the program subscription is connected
+    is_subscribed: AtomicBool,
+}
+
+impl LookupTableCacheStatus {
+    fn mark_updated(&self, slot: Slot) {
+        self.last_update_ms.store(unix_time_ms(), Ordering::Relaxed);
+        self.last_update_slot.fetch_max(slot, Ordering::Relaxed);
+    }
+}
+
+/// Read access to the lookup table cache's freshness
+#[derive(Clone)]
+pub struct LookupTableCacheHandle {
+    status: Arc<LookupTableCacheStatus>,
+}
+
+impl LookupTableCacheHandle {
+    /// Time since the cache was last updated, or None if it was never seeded
+    pub fn staleness(&self) -> Option<Duration> {
+        match self.status.last_update_ms.load(Ordering::Relaxed) {
+            0 => None,
+            last_update_ms => Some(Duration::from_millis(
+                unix_time_ms().saturating_sub(last_update_ms),
+            )),
+        }
+    }
+
+    /// Highest slot reflected in the cache
+    pub fn last_update_slot(&self) -> Slot {
+        self.status.last_update_slot.load(Ordering::Relaxed)
+    }
+
+    /// True while the cache is receiving incremental updates
+    pub fn is_subscribed(&self) -> bool {
+        self.status.is_subscribed.load(Ordering::Relaxed)
+    }
+}
+
+/// Keeps a shared address lookup table cache current with a program subscription.
+pub struct LookupTableCacheUpdater {
+    updater_thread: JoinHandle<()>,
+    status: Arc<LookupTableCacheStatus>,
+}
+
+impl LookupTableCacheUpdater {
+    const METRICS_INTERVAL: Duration = Duration::from_secs(1);
+    /// How often to run a full scan while the program subscription is down
+    const UNSUBSCRIBED_SCAN_INTERVAL: Duration = Duration::from_secs(60);
+
+    /// Starts the updater thread.
+    ///
+    /// # Arguments
+    /// * `rpc_load_balancer` - Source of the RPC and websocket servers used for scans and the
+    ///   subscription
+    /// * `lookup_table_cache` - Shared cache kept current by this updater
+    /// * `reconcile_interval` - How often to run a full scan while the subscription is connected;
+    ///   scans run at least every minute while it's down
+    /// * `snapshot_config` - Snapshot file to warm-start from and periodically save to
+    /// * `exit` - Shutdown signal for graceful termination
+    pub fn new(
+        rpc_load_balancer: &Arc<LoadBalancer>,
+        lookup_table_cache: &Arc<DashMap<Pubkey, AddressLookupTableAccount>>,
+        reconcile_interval: Duration,
+        snapshot_config: Option<LookupTableSnapshotConfig>,
+        exit: &Arc<AtomicBool>,
+    ) -> LookupTableCacheUpdater {
+        let status = Arc::new(LookupTableCacheStatus::default());
+
+        // warm-start synchronously so the cache is usable before the first scan completes
+        if let Some(snapshot_config) = &snapshot_config {
+            Self::warm_start(&snapshot_config.path, lookup_table_cache, &status);
+        }
+
+        let rpc_load_balancer = rpc_load_balancer.clone();
+        let lookup_table_cache = lookup_table_cache.clone();
+        let exit = exit.clone();
+        let updater_thread = {
+            let status = status.clone();
+            Builder::new()
+                .name("lookup_table_cache_updater".to_string())
+                .spawn(move || {
+                    Self::run(
+                        &rpc_load_balancer,
+                        &lookup_table_cache,
+                        &status,
+                        reconcile_interval,
+                        snapshot_config.as_ref(),
+                        &exit,
+                    )
+                })
+                .unwrap()
+        };
+
+        LookupTableCacheUpdater {
+            updater_thread,
+            status,
+        }
+    }
+
+    pub fn handle(&self) -> LookupTableCacheHandle {
+        LookupTableCacheHandle {
+            status: self.status.clone(),
+        }
+    }
+
+    pub fn join(self) -> thread::Result<()> {
+        self.updater_thread.join()
+    }
+
+    fn warm_start(
+        path: &Path,
+        lookup_table_cache: &DashMap<Pubkey, AddressLookupTableAccount>,
+        status: &LookupTableCacheStatus,
+    ) {
+        let start = Instant::now();
+        match load_snapshot(path) {
+            Ok(snapshot) => {
+                let num_lookup_tables = snapshot.lookup_tables.len();
+                for lookup_table in snapshot.lookup_tables {
+                    lookup_table_cache.insert(lookup_table.key, lookup_table);
+                }
+                status
+                    .last_update_ms
+                    .store(snapshot.saved_at_ms, Ordering::Relaxed);
+                status
+                    .last_update_slot
+                    .store(snapshot.slot, Ordering::Relaxed);
+                info!(
+                    "loaded {num_lookup_tables} lookup tables at slot {} from snapshot {path:?} in {:?}",
+                    snapshot.slot,
+                    start.elapsed()
+                );
+                datapoint_info!(
+                    "lookup_table_cache-warm_start",
+                    ("lookup_table_size", num_lookup_tables, i64),
+                    ("slot", snapshot.slot, i64),
+                    ("elapsed_us", start.elapsed().as_micros() as i64, i64),
+                );
+            }
+            Err(LookupTableSnapshotError::Io(e)) if e.kind() == io::ErrorKind::NotFound => {
+                info!("no lookup table snapshot at {path:?}, starting cold");
+            }
+            Err(e) => {
+                warn!("error loading lookup table snapshot from {path:?}, starting cold: {e}");
+                datapoint_error!(
+                    "lookup_table_cache-warm_start_error",
+                    ("error", e.to_string(), String),
+                );
+            }
+        }
+    }
+
+    /// Saves a snapshot if the cache advanced since the last one, returning the slot saved
+    fn maybe_save_snapshot(
+        path: &Path,
+        lookup_table_cache: &DashMap<Pubkey, AddressLookupTableAccount>,
+        status: &LookupTableCacheStatus,
+        last_snapshot_slot: Slot,
+    ) -> Slot {
+        let slot = status.last_update_slot.load(Ordering::Relaxed);
+        if slot <= last_snapshot_slot {
+            return last_snapshot_slot;
+        }
+
+        let start = Instant::now();
+        match save_snapshot(path, lookup_table_cache, slot) {
+            Ok(()) => {
+                datapoint_info!(
+                    "lookup_table_cache-snapshot",
+                    ("lookup_table_size", lookup_table_cache.len(), i64),
+                    ("slot", slot, i64),
+                    ("elapsed_us", start.elapsed().as_micros() as i64, i64),
+                );
+                slot
+            }
+            Err(e) => {
+                error!("error saving lookup table snapshot to {path:?}: {e}");
+                datapoint_error!(
+                    "lookup_table_cache-snapshot_error",
+                    ("error", e.to_string(), String),
+                );
+                last_snapshot_slot
+            }
+        }
+    }
+
+    fn run(
+        rpc_load_balancer: &Arc<LoadBalancer>,
+        lookup_table_cache: &DashMap<Pubkey, AddressLookupTableAccount>,
+        status: &LookupTableCacheStatus,
+        reconcile_interval: Duration,
+        snapshot_config: Option<&LookupTableSnapshotConfig>,
+        exit: &Arc<AtomicBool>,
+    ) {
+        let mut last_metrics = Instant::now();
+        let mut update_counts = UpdateCounts::default();
+        let mut last_snapshot = Instant::now();
+        let mut last_snapshot_slot = status.last_update_slot.load(Ordering::Relaxed);
+        // unset until the first scan, which runs right after the first subscription attempt
+        let mut last_scan: Option<Instant> = None;
+        let mut subscription_failing = false;
+
+        while !exit.load(Ordering::Relaxed) {
+            let (websocket_url, _) = rpc_load_balancer.get_highest_slot();
+            let config = RpcProgramAccountsConfig {
+                account_config: RpcAccountInfoConfig {
+                    encoding: Some(UiAccountEncoding::Base64),
+                    commitment: Some(CommitmentConfig::processed()),
+                    ..RpcAccountInfoConfig::default()
+                },
+                with_context: Some(true),
+                ..RpcProgramAccountsConfig::default()
+            };
+
+            // subscribe before scanning so no update lands between the scan and the subscription
+            match PubsubClient::program_subscribe(
+                &websocket_url,
+                &ADDRESS_LOOKUP_TABLE_PROGRAM_ID,
+                Some(config),
+            ) {
+                Ok((_subscription, receiver)) => {
+                    status.is_subscribed.store(true, Ordering::Relaxed);
+                    subscription_failing = false;
+
+                    let mut scan_slot = Self::scan(rpc_load_balancer, lookup_table_cache, status);
+                    last_scan = Some(Instant::now());
+
+                    while !exit.load(Ordering::Relaxed) {
+                        match receiver.recv_timeout(Duration::from_millis(100)) {
+                            Ok(response) => {
+                                // notifications buffered during the scan may be older than it
+                                if scan_slot.is_some_and(|slot| response.context.slot < slot) {
+                                    continue;
+                                }
+                                let Ok(pubkey) = response.value.pubkey.parse::<Pubkey>() else {
+                                    continue;
+                                };
+                                let Some(account) = response.value.account.decode::<Account>()
+                                else {
+                                    continue;
+                                };
+                                let update =
+                                    apply_lookup_table_update(lookup_table_cache, pubkey, &account);
+                                debug!("lookup table {pubkey} update: {update:?}");
+                                update_counts.increment(update);
+                                status.mark_updated(response.context.slot);
+                            }
+                            Err(RecvTimeoutError::Timeout) => {}
+                            Err(RecvTimeoutError::Disconnected) => {
+                                info!("lookup table subscription disconnected");
+                                break;
+                            }
+                        }
+
+                        if last_scan
+                            .is_some_and(|last_scan| last_scan.elapsed() >= reconcile_interval)
+                        {
+                            scan_slot = Self::scan(rpc_load_balancer, lookup_table_cache, status);
+                            last_scan = Some(Instant::now());
+                        }
+                        if last_metrics.elapsed() >= Self::METRICS_INTERVAL {
+                            Self::report(lookup_table_cache, status, &update_counts);
+                            update_counts = UpdateCounts::default();
+                            last_metrics = Instant::now();
+                        }
+                        if let Some(snapshot_config) = snapshot_config {
+                            if last_snapshot.elapsed() >= snapshot_config.interval {
+                                last_snapshot_slot = Self::maybe_save_snapshot(
+                                    &snapshot_config.path,
+                                    lookup_table_cache,
+                                    status,
+                                    last_snapshot_slot,
+                                );
+                                last_snapshot = Instant::now();
+                            }
+                        }
+                    }
+                    status.is_subscribed.store(false, Ordering::Relaxed);
+                }
+                Err(e) => {
+                    // providers without programSubscribe fail every attempt, only log the first
+                    if !subscription_failing {
+                        error!("lookup table subscription error, falling back to scans: {e:?}");
+                    }
+                    subscription_failing = true;
+                    datapoint_error!(
+                        "lookup_table_cache-subscription_error",
+                        ("error", e.to_string(), String),
+                    );
+                }
+            }
+
+            // keep the cache current with full scans while the subscription is down
+            let scan_interval = Self::UNSUBSCRIBED_SCAN_INTERVAL.min(reconcile_interval);
+            if last_scan.map_or(true, |last_scan| last_scan.elapsed() >= scan_interval) {
+                Self::scan(rpc_load_balancer, lookup_table_cache, status);
+                last_scan = Some(Instant::now());
+            }
+            if let Some(snapshot_config) = snapshot_config {
+                if last_snapshot.elapsed() >= snapshot_config.interval {
+                    last_snapshot_slot = Self::maybe_save_snapshot(
+                        &snapshot_config.path,
+                        lookup_table_cache,
+                        status,
+                        last_snapshot_slot,
+                    );
+                    last_snapshot = Instant::now();
+                }
+            }
+
+            Self::report(lookup_table_cache, status, &update_counts);
+            update_counts = UpdateCounts::default();
+            last_metrics = Instant::now();
+
+            // brief pause before reconnecting to avoid tight retry loops
+            sleep(Duration::from_secs(1));
+        }
+
+        // save the latest state on shutdown so the next boot starts as warm as possible
+        if let Some(snapshot_config) = snapshot_config {
+            Self::maybe_save_snapshot(
+                &snapshot_config.path,
+                lookup_table_cache,
+                status,
+                last_snapshot_slot,
+            );
+        }
+    }
+
+    /// Runs a full scan, returning the slot it reflects on success
+    fn scan(
+        rpc_load_balancer: &LoadBalancer,
+        lookup_table_cache: &DashMap<Pubkey, AddressLookupTableAccount>,
+        status: &LookupTableCacheStatus,
+    ) -> Option<Slot> {
+        let now = Instant::now();
+        let result = full_scan(&rpc_load_balancer.rpc_client(), lookup_table_cache);
+        let elapsed_us = now.elapsed().as_micros() as i64;
+        match result {
+            Ok(slot) => {
+                status.mark_updated(slot);
+                datapoint_info!(
+                    "lookup_table_cache-scan",
+                    ("lookup_table_size", lookup_table_cache.len(), i64),
+                    ("slot", slot, i64),
+                    ("elapsed_us", elapsed_us, i64),
+                );
+                Some(slot)
+            }
+            Err(e) => {
+                error!("error scanning address lookup tables: {e:?}");
+                datapoint_error!(
+                    "lookup_table_cache-scan_error",
+                    ("lookup_table_size", lookup_table_cache.len(), i64),
+                    ("elapsed_us", elapsed_us, i64),
+                    ("error", e.to_string(), String),
+                );
+                None
+            }
+        }
+    }
+
+    fn report(
+        lookup_table_cache: &DashMap<Pubkey, AddressLookupTableAccount>,
+        status: &LookupTableCacheStatus,
+        update_counts: &UpdateCounts,
+    ) {
+        let staleness_ms = match status.last_update_ms.load(Ordering::Relaxed) {
+            0 => -1,
+            last_update_ms => unix_time_ms().saturating_sub(last_update_ms) as i64,
+        };
+        datapoint_info!(
+            "lookup_table_cache-stats",
+            ("lookup_table_size", lookup_table_cache.len(), i64),
+            ("staleness_ms", staleness_ms, i64),
+            (
+                "last_update_slot",
+                status.last_update_slot.load(Ordering::Relaxed),
+                i64
+            ),
+            (
+                "is_subscribed",
+                status.is_subscribed.load(Ordering::Relaxed),
+                bool
+            ),
+            ("num_created", update_counts.created, i64),
+            ("num_extended", update_counts.extended, i64),
+            ("num_deactivated", update_counts.deactivated, i64),
+            ("num_closed", update_counts.closed, i64),
+            ("num_invalid", update_counts.invalid, i64),
+        );
+    }
+}
+
+#[derive(Default)]
+struct UpdateCounts {
+    created: u64,
+    extended: u64,
+    deactivated: u64,
+    closed: u64,
+    invalid: u64,
+}
+
+impl UpdateCounts {
+    fn increment(&mut self, update: LookupTableUpdate) {
+        let counter = match update {
+            LookupTableUpdate::Created => &mut self.created,
+            LookupTableUpdate::Extended => &mut self.extended,
+            LookupTableUpdate::Deactivated => &mut self.deactivated,
+            LookupTableUpdate::Closed => &mut self.closed,
+            LookupTableUpdate::Invalid => &mut self.invalid,
+        };
+        *counter = counter.saturating_add(1);
+    }
+}
+
+/// Applies the latest state of a lookup table account to the cache.
+pub fn apply_lookup_table_update(
+    lookup_table_cache: &DashMap<Pubkey, AddressLookupTableAccount>,
+    pubkey: Pubkey,
+    account: &Account,
+) -> LookupTableUpdate {
+    if account.lamports == 0
+        || account.data.is_empty()
+        || account.owner != ADDRESS_LOOKUP_TABLE_PROGRAM_ID
+    {
+        lookup_table_cache.remove(&pubkey);
+        return LookupTableUpdate::Closed;
+    }
+
+    let table = match AddressLookupTable::deserialize(&account.data) {
+        Ok(table) => table,
+        Err(e) => {
+            warn!("error deserializing AddressLookupTable pubkey: {pubkey}, error: {e}");
+            return LookupTableUpdate::Invalid;
+        }
+    };
+
+    let previous = lookup_table_cache.insert(
+        pubkey,
+        AddressLookupTableAccount {
+            key: pubkey,
+            addresses: table.addresses.to_vec(),
+        },
+    );
+
+    if table.meta.deactivation_slot != Slot::MAX {
+        LookupTableUpdate::Deactivated
+    } else if previous.is_some() {
+        LookupTableUpdate::Extended
+    } else {
+        LookupTableUpdate::Created
+    }
+}
+
+/// Updates the cache with every lookup table on chain and evicts the closed ones, returning the
+/// slot the scan reflects.
+pub fn full_scan(
+    rpc_client: &RpcClient,
+    lookup_table_cache: &DashMap<Pubkey, AddressLookupTableAccount>,
+) -> client_error::Result<Slot> {
+    let cached_before_scan: HashSet<Pubkey> = lookup_table_cache
+        .iter()
+        .map(|entry| *entry.key())
+        .collect();
+    // the scan reflects at least this slot, since it's taken after
+    let slot = rpc_client.get_slot()?;
+
+    let start = Instant::now();
+    let accounts = rpc_client.get_program_accounts(&ADDRESS_LOOKUP_TABLE_PROGRAM_ID)?;
+    info!(
+        "Fetched {} lookup tables from RPC in {:?}",
+        accounts.len(),
+        start.elapsed()
+    );
+
+    apply_full_scan(lookup_table_cache, &cached_before_scan, accounts);
+    Ok(slot)
+}
+
+/// Applies the lookup tables returned by a full scan, evicting the tables that were cached
+/// before the scan started but are missing from it. Tables added while the scan ran, by the
+/// subscription or the lookup table fetcher, are newer than the scan and are kept.
+fn apply_full_scan(
+    lookup_table_cache: &DashMap<Pubkey, AddressLookupTableAccount>,
+    cached_before_scan: &HashSet<Pubkey>,
+    accounts: Vec<(Pubkey, Account)>,
+) {
+    let mut new_pubkeys = HashSet::new();
+    for (pubkey, account) in accounts {
+        match apply_lookup_table_update(lookup_table_cache, pubkey, &account) {
+            LookupTableUpdate::Closed | LookupTableUpdate::Invalid => {}
+            _ => {
+                new_pubkeys.insert(pubkey);
+            }
+        }
+    }
+
+    // remove the lookup tables closed since they were cached
+    lookup_table_cache
+        .retain(|pubkey, _| new_pubkeys.contains(pubkey) || !cached_before_scan.contains(pubkey));
+}
+
+/// Writes the cache to a snapshot file at `slot`. The file is written next to `path` and
+/// renamed into place so a crash mid-write never leaves a truncated snapshot.
+pub fn save_snapshot(
+    path: &Path,
+    lookup_table_cache: &DashMap<Pubkey, AddressLookupTableAccount>,
+    slot: Slot,
+) -> LookupTableSnapshotResult<()> {
+    let lookup_tables: Vec<(Pubkey, Vec<Pubkey>)> = lookup_table_cache
+        .iter()
+        .map(|entry| (*entry.key(), entry.value().addresses.clone()))
+        .collect();
+
+    let tmp_path = path.with_extension("tmp");
+    {
+        let mut writer = BufWriter::new(File::create(&tmp_path)?);
+        bincode::serialize_into(&mut writer, &SNAPSHOT_VERSION)?;
+        bincode::serialize_into(&mut writer, &(slot, unix_time_ms(), lookup_tables))?;
+        writer.flush()?;
+    }
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+/// Reads a snapshot file written by [`save_snapshot`]
+pub fn load_snapshot(path: &Path) -> LookupTableSnapshotResult<LookupTableSnapshot> {
+    let mut reader = BufReader::new(File::open(path)?);
+
+    let version: u32 = bincode::deserialize_from(&mut reader)?;
+    if version != SNAPSHOT_VERSION {
+        return Err(LookupTableSnapshotError::UnsupportedVersion(version));
+    }
+
+    let (slot, saved_at_ms, lookup_tables): (Slot, u64, Vec<(Pubkey, Vec<Pubkey>)>) =
+        bincode::deserialize_from(&mut reader)?;
+    Ok(LookupTableSnapshot {
+        slot,
+        saved_at_ms,
+        lookup_tables: lookup_tables
+            .into_iter()
+            .map(|(key, addresses)| AddressLookupTableAccount { key, addresses })
+            .collect(),
+    })
+}
+
+fn unix_time_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{borrow::Cow, collections::HashSet};
+
+    use dashmap::DashMap;
+    use solana_sdk::{
+        account::Account,
+        address_lookup_table::state::{AddressLookupTable, LookupTableMeta},
+        clock::Slot,
+        pubkey::Pubkey,
+    };
+
+    use crate::lookup_table_cache::{
+        apply_full_scan, apply_lookup_table_update, load_snapshot, save_snapshot,
+        LookupTableUpdate, ADDRESS_LOOKUP_TABLE_PROGRAM_ID,
+    };
+
+    fn lookup_table_account(addresses: &[Pubkey], deactivation_slot: Slot) -> Account {
+        let table = AddressLookupTable {
+            meta: LookupTableMeta {
+                deactivation_slot,
+                ..LookupTableMeta::default()
+            },
+            addresses: Cow::Borrowed(addresses),
+        };
+        Account {
+            lamports: 1,
+            data: table.serialize_for_tests().unwrap(),
+            owner: ADDRESS_LOOKUP_TABLE_PROGRAM_ID,
+            executable: false,
+            rent_epoch: 0,
+        }
+    }
+
+    #[test]
+    fn test_apply_lookup_table_update() {
+        let cache = DashMap::new();
+        let table_pubkey = Pubkey::new_unique();
+        let a = Pubkey::new_unique();
+        let b = Pubkey::new_unique();
+
+        let account = lookup_table_account(&[a], Slot::MAX);
+        assert_eq!(
+            apply_lookup_table_update(&cache, table_pubkey, &account),
+            LookupTableUpdate::Created
+        );
+        assert_eq!(cache.get(&table_pubkey).unwrap().addresses, vec![a]);
+
+        let account = lookup_table_account(&[a, b], Slot::MAX);
+        assert_eq!(
+            apply_lookup_table_update(&cache, table_pubkey, &account),
+            LookupTableUpdate::Extended
+        );
+        assert_eq!(cache.get(&table_pubkey).unwrap().addresses, vec![a, b]);
+
+        // deactivated tables stay usable until they're closed
+        let account = lookup_table_account(&[a, b], 100);
+        assert_eq!(
+            apply_lookup_table_update(&cache, table_pubkey, &account),
+            LookupTableUpdate::Deactivated
+        );
+        assert!(cache.contains_key(&table_pubkey));
+
+        let closed = Account {
+            lamports: 0,
+            data: vec![],
+            owner: Pubkey::default(),
+            executable: false,
+            rent_epoch: 0,
+        };
+        assert_eq!(
+            apply_lookup_table_update(&cache, table_pubkey, &closed),
+            LookupTableUpdate::Closed
+        );
+        assert!(!cache.contains_key(&table_pubkey));
+    }
+
+    #[test]
+    fn test_apply_full_scan() {
+        let cache = DashMap::new();
+        let (scanned, closed, added_during_scan) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let account = lookup_table_account(&[Pubkey::new_unique()], Slot::MAX);
+        for pubkey in [scanned, closed] {
+            apply_lookup_table_update(&cache, pubkey, &account);
+        }
+        let cached_before_scan = HashSet::from_iter([scanned, closed]);
+
+        // the subscription or fetcher adds a table while the scan is running
+        apply_lookup_table_update(&cache, added_during_scan, &account);
+
+        apply_full_scan(&cache, &cached_before_scan, vec![(scanned, account)]);
+        assert!(cache.contains_key(&scanned));
+        assert!(!cache.contains_key(&closed));
+        assert!(cache.contains_key(&added_during_scan));
+    }
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let cache = DashMap::new();
+        let table_pubkey = Pubkey::new_unique();
+        let addresses = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+        let account = lookup_table_account(&addresses, Slot::MAX);
+        apply_lookup_table_update(&cache, table_pubkey, &account);
+
+        let path = std::env::temp_dir().join(format!("lookup_tables-{table_pubkey}.bin"));
+        save_snapshot(&path, &cache, 1234).unwrap();
+        let snapshot = load_snapshot(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(snapshot.slot, 1234);
+        assert_eq!(snapshot.lookup_tables.len(), 1);
+        assert_eq!(snapshot.lookup_tables[0].key, table_pubkey);
+        assert_eq!(snapshot.lookup_tables[0].addresses, addresses);
+    }
+}
@@ -0,0 +1,314 @@
+//! Background fetching of address lookup tables missing from the shared cache.
+//!
+//! The lookup table cache is refreshed in the background, so a transaction can reference
+//! a table the relayer hasn't seen yet. When the OFAC filter runs in drop-and-fetch mode,
+//! it drops the transaction and asks the [`LookupTableFetcher`] for the missing table, which
+//! pulls it from RPC on its own thread and adds it to the cache so later transactions using
+//! the table can be checked. Packet forwarding never waits on RPC.
+//!
+//! Fetches are bounded in three ways so that a flood of transactions referencing unknown
+//! tables can't hammer the RPC servers or grow memory:
+//! - A bounded request queue; requests for tables already queued are ignored and requests
+//!   beyond its capacity are dropped
+//! - A per-second fetch budget
+//! - A negative cache; tables that recently failed to fetch aren't retried until it expires
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, sleep, Builder, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use crossbeam_channel::{bounded, Receiver, RecvTimeoutError, Sender};
+use dashmap::DashMap;
+use jito_rpc::load_balancer::LoadBalancer;
+use log::{debug, warn};
+use solana_client::rpc_client::RpcClient;
+use solana_metrics::datapoint_info;
+use solana_sdk::{
+    address_lookup_table::{state::AddressLookupTable, AddressLookupTableAccount},
+    pubkey::Pubkey,
+};
+
+/// Lookup tables waiting to be fetched before new requests are dropped
+const FETCH_QUEUE_CAPACITY: usize = 1_000;
+
+/// How long a single fetch can take, much shorter than the load balancer's RPC timeout so a
+/// slow RPC node doesn't hold up the tables queued behind it
+const FETCH_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Bound on the negative cache so unique garbage keys can't grow it without limit
+const MAX_RECENT_MISSES: usize = 100_000;
+
+/// Requests lookup table fetches from the [`LookupTableFetcher`] thread without blocking.
+#[derive(Clone)]
+pub struct LookupTableFetchHandle {
+    sender: Sender<Pubkey>,
+    state: Arc<Mutex<FetchState>>,
+    negative_cache_ttl: Duration,
+    num_dropped_requests: Arc<AtomicU64>,
+}
+
+#[derive(Default)]
+struct FetchState {
+    /// Tables queued or being fetched
+    pending: HashSet<Pubkey>,
+    /// Tables that recently failed to fetch, with the time of the failure
+    recent_misses: HashMap<Pubkey, Instant>,
+}
+
+impl LookupTableFetchHandle {
+    /// Creates a handle queueing up to `queue_capacity` fetches, returning the receiving end
+    /// of the queue
+    pub(crate) fn new(
+        queue_capacity: usize,
+        negative_cache_ttl: Duration,
+    ) -> (LookupTableFetchHandle, Receiver<Pubkey>) {
+        let (sender, receiver) = bounded(queue_capacity);
+        let handle = LookupTableFetchHandle {
+            sender,
+            state: Arc::default(),
+            negative_cache_ttl,
+            num_dropped_requests: Arc::default(),
+        };
+        (handle, receiver)
+    }
+
+    /// Queues a fetch of the lookup table unless it's already queued, recently failed, or the
+    /// queue is full. Returns true if the fetch was queued.
+    pub fn request(&self, lookup_table: &Pubkey) -> bool {
+        let mut state = self.state.lock().unwrap();
+        if state.pending.contains(lookup_table) {
+            return false;
+        }
+        if let Some(missed_at) = state.recent_misses.get(lookup_table) {
+            if missed_at.elapsed() < self.negative_cache_ttl {
+                return false;
+            }
+            state.recent_misses.remove(lookup_table);
+        }
+
+        if self.sender.try_send(*lookup_table).is_err() {
+            self.num_dropped_requests.fetch_add(1, Ordering::Relaxed);
+            return false;
+        }
+        state.pending.insert(*lookup_table);
+        true
+    }
+}
+
+/// Fetches requested lookup tables from RPC into the lookup table cache on its own thread.
+pub struct LookupTableFetcher {
+    handle: LookupTableFetchHandle,
+    fetch_thread: JoinHandle<()>,
+}
+
+impl LookupTableFetcher {
+    /// Starts the fetch thread.
+    ///
+    /// # Arguments
+    /// * `rpc_load_balancer` - Picks the RPC server to fetch from
+    /// * `address_lookup_table_cache` - Cache the fetched tables are added to
+    /// * `max_fetches_per_sec` - Maximum number of RPC fetches per second
+    /// * `negative_cache_ttl` - How long a table that failed to fetch is remembered before
+    ///   it's retried
+    /// * `exit` - Shutdown signal for graceful termination
+    pub fn new(
+        rpc_load_balancer: &Arc<LoadBalancer>,
+        address_lookup_table_cache: &Arc<DashMap<Pubkey, AddressLookupTableAccount>>,
+        max_fetches_per_sec: u64,
+        negative_cache_ttl: Duration,
+        exit: &Arc<AtomicBool>,
+    ) -> LookupTableFetcher {
+        let (handle, receiver) =
+            LookupTableFetchHandle::new(FETCH_QUEUE_CAPACITY, negative_cache_ttl);
+
+        let rpc_load_balancer = rpc_load_balancer.clone();
+        let address_lookup_table_cache = address_lookup_table_cache.clone();
+        let fetch_handle = handle.clone();
+        let exit = exit.clone();
+        let fetch_thread = Builder::new()
+            .name("lookup_table_fetcher".to_string())
+            .spawn(move || {
+                Self::run(
+                    receiver,
+                    &fetch_handle,
+                    &rpc_load_balancer,
+                    &address_lookup_table_cache,
+                    max_fetches_per_sec,
+                    &exit,
+                )
+            })
+            .unwrap();
+
+        LookupTableFetcher {
+            handle,
+            fetch_thread,
+        }
+    }
+
+    pub fn handle(&self) -> LookupTableFetchHandle {
+        self.handle.clone()
+    }
+
+    pub fn join(self) -> thread::Result<()> {
+        self.fetch_thread.join()
+    }
+
+    fn run(
+        receiver: Receiver<Pubkey>,
+        handle: &LookupTableFetchHandle,
+        rpc_load_balancer: &LoadBalancer,
+        address_lookup_table_cache: &DashMap<Pubkey, AddressLookupTableAccount>,
+        max_fetches_per_sec: u64,
+        exit: &AtomicBool,
+    ) {
+        // the load balancer's clients have a long timeout, so fetch through clients of our own
+        let mut rpc_clients: HashMap<String, RpcClient> = HashMap::new();
+        let mut window_start = Instant::now();
+        let mut window_fetches = 0;
+        let mut last_report = Instant::now();
+        let (mut num_fetched, mut num_failed) = (0u64, 0u64);
+
+        while !exit.load(Ordering::Relaxed) {
+            if last_report.elapsed() >= Duration::from_secs(1) {
+                datapoint_info!(
+                    "lookup_table_fetcher-stats",
+                    ("num_fetched", num_fetched, i64),
+                    ("num_failed", num_failed, i64),
+                    ("queue_len", receiver.len(), i64),
+                    (
+                        "num_dropped_requests",
+                        handle.num_dropped_requests.swap(0, Ordering::Relaxed),
+                        i64
+                    ),
+                );
+                (num_fetched, num_failed) = (0, 0);
+                last_report = Instant::now();
+            }
+
+            let lookup_table = match receiver.recv_timeout(Duration::from_millis(100)) {
+                Ok(lookup_table) => lookup_table,
+                Err(RecvTimeoutError::Timeout) => continue,
+                Err(RecvTimeoutError::Disconnected) => break,
+            };
+
+            if window_start.elapsed() >= Duration::from_secs(1) {
+                window_start = Instant::now();
+                window_fetches = 0;
+            }
+            if window_fetches >= max_fetches_per_sec {
+                // wait out the rest of the window rather than spinning
+                sleep(Duration::from_secs(1).saturating_sub(window_start.elapsed()));
+                window_start = Instant::now();
+                window_fetches = 0;
+            }
+            window_fetches += 1;
+
+            let load_balancer_client = rpc_load_balancer.rpc_client();
+            let rpc_client = rpc_clients
+                .entry(load_balancer_client.url())
+                .or_insert_with(|| {
+                    RpcClient::new_with_timeout_and_commitment(
+                        load_balancer_client.url(),
+                        FETCH_TIMEOUT,
+                        load_balancer_client.commitment(),
+                    )
+                });
+            let fetched = Self::fetch(rpc_client, &lookup_table);
+
+            let mut state = handle.state.lock().unwrap();
+            state.pending.remove(&lookup_table);
+            match fetched {
+                Some(table) => {
+                    num_fetched += 1;
+                    address_lookup_table_cache.insert(lookup_table, table);
+                }
+                None => {
+                    num_failed += 1;
+                    if state.recent_misses.len() >= MAX_RECENT_MISSES {
+                        let ttl = handle.negative_cache_ttl;
+                        state.recent_misses.retain(|_, at| at.elapsed() < ttl);
+                    }
+                    if state.recent_misses.len() < MAX_RECENT_MISSES {
+                        state.recent_misses.insert(lookup_table, Instant::now());
+                    }
+                }
+            }
+        }
+    }
+
+    /// Fetches the lookup table from RPC, returning None if it doesn't exist or can't be
+    /// deserialized
+    fn fetch(rpc_client: &RpcClient, lookup_table: &Pubkey) -> Option<AddressLookupTableAccount> {
+        let start = Instant::now();
+        let result = rpc_client
+            .get_account(lookup_table)
+            .map_err(|e| e.to_string())
+            .and_then(|account| {
+                AddressLookupTable::deserialize(&account.data)
+                    .map(|table| AddressLookupTableAccount {
+                        key: *lookup_table,
+                        addresses: table.addresses.to_vec(),
+                    })
+                    .map_err(|e| e.to_string())
+            });
+
+        datapoint_info!(
+            "lookup_table_fetcher-fetch",
+            ("success", result.is_ok(), bool),
+            ("elapsed_us", start.elapsed().as_micros() as i64, i64),
+        );
+
+        match result {
+            Ok(table) => {
+                debug!("fetched missing lookup table {lookup_table}");
+                Some(table)
+            }
+            Err(e) => {
+                warn!("error fetching lookup table {lookup_table}: {e}");
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use solana_sdk::pubkey::Pubkey;
+
+    use crate::lookup_table_fetcher::LookupTableFetchHandle;
+
+    #[test]
+    fn test_lookup_table_fetch_requests() {
+        let (handle, receiver) = LookupTableFetchHandle::new(2, Duration::from_secs(60));
+        let (a, b, c) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+
+        assert!(handle.request(&a));
+        // a table already queued isn't queued again
+        assert!(!handle.request(&a));
+        assert!(handle.request(&b));
+        // requests beyond the queue's capacity are dropped instead of blocking
+        assert!(!handle.request(&c));
+        assert_eq!(receiver.try_iter().collect::<Vec<_>>(), vec![a, b]);
+
+        // tables that recently failed aren't retried
+        {
+            let mut state = handle.state.lock().unwrap();
+            state.pending.clear();
+            state.recent_misses.insert(c, Instant::now());
+        }
+        assert!(!handle.request(&c));
+        assert!(handle.request(&a));
+    }
+}
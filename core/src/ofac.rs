@@ -10,118 +10,384 @@
 //! 
 //! When a transaction is identified as OFAC-related, it should be dropped before
 //! processing to ensure compliance with financial regulations.
+//!
+//! Transactions that reference a lookup table missing from the cache can't be fully checked.
+//! [`LookupTablePolicy`] controls whether those are forwarded or dropped, and whether the
+//! missing table is fetched so later transactions using it can be checked.
 
-use std::collections::HashSet;
+use std::{collections::HashSet, fmt, str::FromStr};
 
 use dashmap::DashMap;
+use log::info;
 use solana_sdk::{
     address_lookup_table::AddressLookupTableAccount, pubkey::Pubkey,
     transaction::VersionedTransaction,
 };
 
+use crate::{lookup_table_fetcher::LookupTableFetchHandle, ofac_updater::OfacAddressesHandle};
+
+/// How the OFAC filter treats transactions that reference lookup tables missing from the cache.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LookupTablePolicy {
+    /// Forward the transaction; only the addresses that could be resolved are checked
+    #[default]
+    FailOpen,
+    /// Drop the transaction
+    FailClosed,
+    /// Drop the transaction and fetch the missing tables in the background, so later
+    /// transactions using them can be checked. The first transactions through a table the
+    /// cache hasn't seen are always dropped; forwarding never waits on RPC.
+    DropAndFetch,
+}
+
+impl FromStr for LookupTablePolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fail-open" => Ok(LookupTablePolicy::FailOpen),
+            "fail-closed" => Ok(LookupTablePolicy::FailClosed),
+            "drop-and-fetch" => Ok(LookupTablePolicy::DropAndFetch),
+            _ => Err(format!(
+                "invalid lookup table policy: {s}, expected one of fail-open, fail-closed, drop-and-fetch"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for LookupTablePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LookupTablePolicy::FailOpen => write!(f, "fail-open"),
+            LookupTablePolicy::FailClosed => write!(f, "fail-closed"),
+            LookupTablePolicy::DropAndFetch => write!(f, "drop-and-fetch"),
+        }
+    }
+}
+
+/// Outcome of checking a transaction against the OFAC sanctions list, including why it matched.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OfacVerdict {
+    /// No sanctioned address is referenced by the transaction
+    Clean,
+    /// A sanctioned address appears in the transaction's static account keys
+    StaticKey(Pubkey),
+    /// A sanctioned address is loaded as writable through an address lookup table
+    LookupTableWritable {
+        lookup_table: Pubkey,
+        address: Pubkey,
+    },
+    /// A sanctioned address is loaded as readonly through an address lookup table
+    LookupTableReadonly {
+        lookup_table: Pubkey,
+        address: Pubkey,
+    },
+    /// No sanctioned address was found, but the transaction uses a lookup table that
+    /// isn't in the cache, so its loaded addresses couldn't be checked
+    UnresolvableLookupTable(Pubkey),
+}
+
+impl OfacVerdict {
+    /// Returns true if a sanctioned address was positively identified in the transaction
+    pub fn is_sanctioned(&self) -> bool {
+        matches!(
+            self,
+            OfacVerdict::StaticKey(_)
+                | OfacVerdict::LookupTableWritable { .. }
+                | OfacVerdict::LookupTableReadonly { .. }
+        )
+    }
+
+    /// The sanctioned address that caused the match, if any
+    pub fn sanctioned_address(&self) -> Option<&Pubkey> {
+        match self {
+            OfacVerdict::StaticKey(address)
+            | OfacVerdict::LookupTableWritable { address, .. }
+            | OfacVerdict::LookupTableReadonly { address, .. } => Some(address),
+            OfacVerdict::Clean | OfacVerdict::UnresolvableLookupTable(_) => None,
+        }
+    }
+
+    /// The lookup table involved in the match, if any
+    pub fn lookup_table(&self) -> Option<&Pubkey> {
+        match self {
+            OfacVerdict::LookupTableWritable { lookup_table, .. }
+            | OfacVerdict::LookupTableReadonly { lookup_table, .. }
+            | OfacVerdict::UnresolvableLookupTable(lookup_table) => Some(lookup_table),
+            OfacVerdict::Clean | OfacVerdict::StaticKey(_) => None,
+        }
+    }
+
+    /// Short, stable name for the verdict, used in metrics and audit logs
+    pub fn reason(&self) -> &'static str {
+        match self {
+            OfacVerdict::Clean => "clean",
+            OfacVerdict::StaticKey(_) => "static_key",
+            OfacVerdict::LookupTableWritable { .. } => "lookup_table_writable",
+            OfacVerdict::LookupTableReadonly { .. } => "lookup_table_readonly",
+            OfacVerdict::UnresolvableLookupTable(_) => "unresolvable_lookup_table",
+        }
+    }
+}
+
+/// Number of transactions dropped by the OFAC filter, broken down by [`OfacVerdict`] reason.
+#[derive(Default, Debug)]
+pub struct OfacDropCounts {
+    pub static_key: u64,
+    pub lookup_table_writable: u64,
+    pub lookup_table_readonly: u64,
+    pub unresolvable_lookup_table: u64,
+}
+
+impl OfacDropCounts {
+    pub fn increment(&mut self, verdict: &OfacVerdict) {
+        let counter = match verdict {
+            OfacVerdict::Clean => return,
+            OfacVerdict::StaticKey(_) => &mut self.static_key,
+            OfacVerdict::LookupTableWritable { .. } => &mut self.lookup_table_writable,
+            OfacVerdict::LookupTableReadonly { .. } => &mut self.lookup_table_readonly,
+            OfacVerdict::UnresolvableLookupTable(_) => &mut self.unresolvable_lookup_table,
+        };
+        *counter = counter.saturating_add(1);
+    }
+
+    pub fn total(&self) -> u64 {
+        self.static_key
+            .saturating_add(self.lookup_table_writable)
+            .saturating_add(self.lookup_table_readonly)
+            .saturating_add(self.unresolvable_lookup_table)
+    }
+}
+
+/// OFAC filtering configuration shared by the validator and block engine forwarding paths.
+#[derive(Clone, Default)]
+pub struct OfacFilter {
+    /// Current sanctions list
+    addresses: OfacAddressesHandle,
+    /// Log every dropped transaction with its signature and the sanctioned key
+    audit_log: bool,
+    /// Treatment of transactions referencing lookup tables missing from the cache
+    lookup_table_policy: LookupTablePolicy,
+    /// Used to fetch missing lookup tables, only set for [`LookupTablePolicy::DropAndFetch`]
+    lookup_table_fetcher: Option<LookupTableFetchHandle>,
+}
+
+impl OfacFilter {
+    /// # Panics
+    /// If `lookup_table_policy` is [`LookupTablePolicy::DropAndFetch`] and no fetcher is provided
+    pub fn new(
+        addresses: OfacAddressesHandle,
+        audit_log: bool,
+        lookup_table_policy: LookupTablePolicy,
+        lookup_table_fetcher: Option<LookupTableFetchHandle>,
+    ) -> OfacFilter {
+        assert!(
+            lookup_table_policy != LookupTablePolicy::DropAndFetch
+                || lookup_table_fetcher.is_some(),
+            "drop-and-fetch lookup table policy requires a lookup table fetcher"
+        );
+        OfacFilter {
+            addresses,
+            audit_log,
+            lookup_table_policy,
+            lookup_table_fetcher,
+        }
+    }
+
+    pub fn addresses(&self) -> &OfacAddressesHandle {
+        &self.addresses
+    }
+
+    /// Checks a transaction against a snapshot of the sanctions list. When the policy is
+    /// [`LookupTablePolicy::DropAndFetch`], lookup tables missing from
+    /// `address_lookup_table_cache` are queued to be fetched into it in the background; this
+    /// never waits on RPC.
+    pub fn verdict(
+        &self,
+        tx: &VersionedTransaction,
+        ofac_addresses: &HashSet<Pubkey>,
+        address_lookup_table_cache: &DashMap<Pubkey, AddressLookupTableAccount>,
+    ) -> OfacVerdict {
+        let verdict = ofac_verdict(tx, ofac_addresses, address_lookup_table_cache);
+        if let (OfacVerdict::UnresolvableLookupTable(_), Some(fetcher)) =
+            (&verdict, &self.lookup_table_fetcher)
+        {
+            for table in tx.message.address_table_lookups().unwrap_or_default() {
+                if !address_lookup_table_cache.contains_key(&table.account_key) {
+                    fetcher.request(&table.account_key);
+                }
+            }
+        }
+        verdict
+    }
+
+    /// Returns true if a transaction with this verdict should be dropped under the
+    /// configured lookup table policy
+    pub fn should_drop(&self, verdict: &OfacVerdict) -> bool {
+        match verdict {
+            OfacVerdict::Clean => false,
+            OfacVerdict::UnresolvableLookupTable(_) => {
+                self.lookup_table_policy != LookupTablePolicy::FailOpen
+            }
+            OfacVerdict::StaticKey(_)
+            | OfacVerdict::LookupTableWritable { .. }
+            | OfacVerdict::LookupTableReadonly { .. } => true,
+        }
+    }
+
+    /// Emits an audit log line for a dropped transaction if audit logging is enabled.
+    /// `source` identifies the forwarding path that dropped it.
+    pub fn audit(&self, source: &str, tx: &VersionedTransaction, verdict: &OfacVerdict) {
+        if !self.audit_log {
+            return;
+        }
+        info!(
+            target: "ofac_audit",
+            "dropped ofac transaction source={} signature={} reason={} sanctioned_key={} lookup_table={}",
+            source,
+            tx.signatures.first().map(|s| s.to_string()).unwrap_or_default(),
+            verdict.reason(),
+            verdict.sanctioned_address().map(|k| k.to_string()).unwrap_or_default(),
+            verdict.lookup_table().map(|k| k.to_string()).unwrap_or_default(),
+        );
+    }
+}
+
 /// Determines if a transaction involves any OFAC-sanctioned addresses.
-/// 
+///
+/// This is a convenience wrapper around [`ofac_verdict`] for callers that don't need to know
+/// why the transaction matched.
+///
+/// # Returns
+/// `true` if the transaction involves any sanctioned addresses, `false` otherwise
+///
+/// # Compliance Note
+/// Operators in regulated jurisdictions should drop transactions that return `true`
+/// to maintain compliance with OFAC sanctions programs.
+pub fn is_tx_ofac_related(
+    tx: &VersionedTransaction,
+    ofac_addresses: &HashSet<Pubkey>,
+    address_lookup_table_cache: &DashMap<Pubkey, AddressLookupTableAccount>,
+) -> bool {
+    ofac_verdict(tx, ofac_addresses, address_lookup_table_cache).is_sanctioned()
+}
+
+/// Checks a transaction against the OFAC sanctions list and explains the result.
+///
 /// This function performs comprehensive scanning of both static account keys
 /// and dynamic addresses referenced through lookup tables. A transaction is
 /// considered OFAC-related if it involves a sanctioned address in any capacity:
 /// - As a signer, writable account, or readonly account
 /// - Referenced through address lookup tables
 /// - As a program ID or fee payer
-/// 
+///
+/// Static keys are checked first. A sanctioned lookup table address takes precedence over
+/// an unresolvable lookup table, so [`OfacVerdict::UnresolvableLookupTable`] is only returned
+/// when nothing sanctioned was found.
+///
 /// # Arguments
 /// * `tx` - The versioned transaction to analyze
 /// * `ofac_addresses` - Set of known OFAC-sanctioned public keys
 /// * `address_lookup_table_cache` - Cache of address lookup tables for dynamic address resolution
-/// 
-/// # Returns
-/// `true` if the transaction involves any sanctioned addresses, `false` otherwise
-/// 
-/// # Compliance Note
-/// Operators in regulated jurisdictions should drop transactions that return `true`
-/// to maintain compliance with OFAC sanctions programs.
-pub fn is_tx_ofac_related(
+pub fn ofac_verdict(
     tx: &VersionedTransaction,
     ofac_addresses: &HashSet<Pubkey>,
     address_lookup_table_cache: &DashMap<Pubkey, AddressLookupTableAccount>,
-) -> bool {
-    is_ofac_address_in_static_keys(tx, ofac_addresses)
-        || is_ofac_address_in_lookup_table(tx, ofac_addresses, address_lookup_table_cache)
+) -> OfacVerdict {
+    match static_keys_verdict(tx, ofac_addresses) {
+        OfacVerdict::Clean => lookup_table_verdict(tx, ofac_addresses, address_lookup_table_cache),
+        verdict => verdict,
+    }
 }
 
 /// Checks if any OFAC-sanctioned addresses appear in the transaction's static account keys.
-/// 
+///
 /// Static account keys include:
 /// - Fee payer (always index 0)
 /// - All signers
 /// - All writable accounts
 /// - All readonly accounts
 /// - Program IDs
-/// 
+///
 /// # Arguments
 /// * `tx` - The versioned transaction to check
 /// * `ofac_addresses` - Set of known OFAC-sanctioned public keys
-/// 
+///
 /// # Returns
-/// `true` if any static account key matches a sanctioned address
-fn is_ofac_address_in_static_keys(
-    tx: &VersionedTransaction,
-    ofac_addresses: &HashSet<Pubkey>,
-) -> bool {
+/// [`OfacVerdict::StaticKey`] with the first static account key that matches a sanctioned
+/// address, otherwise [`OfacVerdict::Clean`]
+fn static_keys_verdict(tx: &VersionedTransaction, ofac_addresses: &HashSet<Pubkey>) -> OfacVerdict {
     tx.message
         .static_account_keys()
         .iter()
-        .any(|acc| ofac_addresses.contains(acc))
+        .find(|acc| ofac_addresses.contains(acc))
+        .map_or(OfacVerdict::Clean, |acc| OfacVerdict::StaticKey(*acc))
 }
 
 /// Checks if any OFAC-sanctioned addresses are referenced through address lookup tables.
-/// 
+///
 /// Solana's address lookup tables allow transactions to reference accounts indirectly
 /// to reduce transaction size. This function resolves those references and checks
 /// if any resolved addresses are sanctioned.
-/// 
+///
 /// Only addresses that are actually referenced by the transaction (through writable_indexes
 /// or readonly_indexes) are checked - addresses that exist in the lookup table but
 /// aren't used by the transaction are ignored.
-/// 
+///
 /// # Arguments
 /// * `tx` - The versioned transaction to check
-/// * `ofac_addresses` - Set of known OFAC-sanctioned public keys  
+/// * `ofac_addresses` - Set of known OFAC-sanctioned public keys
 /// * `address_lookup_table_cache` - Cache containing lookup table data
-/// 
+///
 /// # Returns
-/// `true` if any referenced lookup table address matches a sanctioned address
-fn is_ofac_address_in_lookup_table(
+/// The first sanctioned lookup table address found, else the first lookup table missing
+/// from the cache, else [`OfacVerdict::Clean`]
+fn lookup_table_verdict(
     tx: &VersionedTransaction,
     ofac_addresses: &HashSet<Pubkey>,
     address_lookup_table_cache: &DashMap<Pubkey, AddressLookupTableAccount>,
-) -> bool {
+) -> OfacVerdict {
+    let mut unresolved_lookup_table = None;
+
     // Check if transaction uses any address lookup tables
     if let Some(lookup_tables) = tx.message.address_table_lookups() {
         for table in lookup_tables {
             // Resolve the lookup table from cache
-            if let Some(lookup_info) = address_lookup_table_cache.get(&table.account_key) {
-                // Check both writable and readonly referenced addresses
-                for idx in table
-                    .writable_indexes
-                    .iter()
-                    .chain(table.readonly_indexes.iter())
-                {
-                    // Resolve the index to an actual address
-                    if let Some(account) = lookup_info.addresses.get(*idx as usize) {
-                        if ofac_addresses.contains(account) {
-                            return true;
-                        }
+            let Some(lookup_info) = address_lookup_table_cache.get(&table.account_key) else {
+                unresolved_lookup_table.get_or_insert(table.account_key);
+                continue;
+            };
+
+            // Resolve each index to an actual address
+            for idx in &table.writable_indexes {
+                if let Some(account) = lookup_info.addresses.get(*idx as usize) {
+                    if ofac_addresses.contains(account) {
+                        return OfacVerdict::LookupTableWritable {
+                            lookup_table: table.account_key,
+                            address: *account,
+                        };
+                    }
+                }
+            }
+            for idx in &table.readonly_indexes {
+                if let Some(account) = lookup_info.addresses.get(*idx as usize) {
+                    if ofac_addresses.contains(account) {
+                        return OfacVerdict::LookupTableReadonly {
+                            lookup_table: table.account_key,
+                            address: *account,
+                        };
                     }
                 }
             }
         }
     }
-    false
+
+    unresolved_lookup_table.map_or(OfacVerdict::Clean, OfacVerdict::UnresolvableLookupTable)
 }
 
 #[cfg(test)]
 mod tests {
-    use std::collections::HashSet;
+    use std::{collections::HashSet, time::Duration};
 
     use dashmap::DashMap;
     use solana_sdk::{
@@ -136,8 +402,13 @@ mod tests {
         transaction::{Transaction, VersionedTransaction},
     };
 
-    use crate::ofac::{
-        is_ofac_address_in_lookup_table, is_ofac_address_in_static_keys, is_tx_ofac_related,
+    use crate::{
+        lookup_table_fetcher::LookupTableFetchHandle,
+        ofac::{
+            is_tx_ofac_related, lookup_table_verdict, ofac_verdict, static_keys_verdict,
+            LookupTablePolicy, OfacFilter, OfacVerdict,
+        },
+        ofac_updater::OfacAddressesHandle,
     };
 
     #[test]
@@ -164,7 +435,7 @@ mod tests {
             Hash::default(),
         );
         let tx = VersionedTransaction::from(tx);
-        assert!(!is_ofac_address_in_static_keys(&tx, &ofac_addresses));
+        assert!(!static_keys_verdict(&tx, &ofac_addresses).is_sanctioned());
 
         // transaction with ofac account as writable
         let tx = Transaction::new_signed_with_payer(
@@ -182,7 +453,7 @@ mod tests {
             Hash::default(),
         );
         let tx = VersionedTransaction::from(tx);
-        assert!(is_ofac_address_in_static_keys(&tx, &ofac_addresses));
+        assert!(static_keys_verdict(&tx, &ofac_addresses).is_sanctioned());
 
         // transaction with ofac account as readonly
         let tx = Transaction::new_signed_with_payer(
@@ -201,7 +472,7 @@ mod tests {
         );
         let tx = VersionedTransaction::from(tx);
 
-        assert!(is_ofac_address_in_static_keys(&tx, &ofac_addresses));
+        assert!(static_keys_verdict(&tx, &ofac_addresses).is_sanctioned());
 
         // transaction with ofac account as signer
         let tx = Transaction::new_signed_with_payer(
@@ -219,7 +490,7 @@ mod tests {
             Hash::default(),
         );
         let tx = VersionedTransaction::from(tx);
-        assert!(is_ofac_address_in_static_keys(&tx, &ofac_addresses));
+        assert!(static_keys_verdict(&tx, &ofac_addresses).is_sanctioned());
     }
 
     #[test]
@@ -259,11 +530,13 @@ mod tests {
         });
         let tx = VersionedTransaction::try_new(message, &[&payer]).expect("valid tx");
 
-        assert!(is_ofac_address_in_lookup_table(
-            &tx,
-            &ofac_addresses,
-            &address_lookup_table_cache
-        ));
+        assert_eq!(
+            lookup_table_verdict(&tx, &ofac_addresses, &address_lookup_table_cache),
+            OfacVerdict::LookupTableReadonly {
+                lookup_table: lookup_table_pubkey,
+                address: ofac_pubkey,
+            }
+        );
 
         // test writeable ofac
         let message = VersionedMessage::V0(v0::Message {
@@ -286,11 +559,13 @@ mod tests {
             }],
         });
         let tx = VersionedTransaction::try_new(message, &[&payer]).expect("valid tx");
-        assert!(is_ofac_address_in_lookup_table(
-            &tx,
-            &ofac_addresses,
-            &address_lookup_table_cache
-        ));
+        assert_eq!(
+            lookup_table_verdict(&tx, &ofac_addresses, &address_lookup_table_cache),
+            OfacVerdict::LookupTableWritable {
+                lookup_table: lookup_table_pubkey,
+                address: ofac_pubkey,
+            }
+        );
 
         // test proximate ofac (in same lookup table, but not referenced)
         let message = VersionedMessage::V0(v0::Message {
@@ -313,11 +588,170 @@ mod tests {
             }],
         });
         let tx = VersionedTransaction::try_new(message, &[&payer]).expect("valid tx");
-        assert!(!is_ofac_address_in_lookup_table(
+        assert!(
+            !lookup_table_verdict(&tx, &ofac_addresses, &address_lookup_table_cache)
+                .is_sanctioned()
+        );
+    }
+
+    #[test]
+    fn test_ofac_verdict_unresolvable_lookup_table() {
+        let ofac_pubkey = Pubkey::new_unique();
+        let ofac_addresses: HashSet<Pubkey> = HashSet::from_iter([ofac_pubkey]);
+
+        let payer = Keypair::new();
+
+        let missing_lookup_table_pubkey = Pubkey::new_unique();
+        let lookup_table_pubkey = Pubkey::new_unique();
+        let lookup_table = AddressLookupTableAccount {
+            key: lookup_table_pubkey,
+            addresses: vec![ofac_pubkey, Pubkey::new_unique()],
+        };
+        let address_lookup_table_cache = DashMap::from_iter([(lookup_table_pubkey, lookup_table)]);
+
+        let build_tx = |address_table_lookups| {
+            let message = VersionedMessage::V0(v0::Message {
+                header: MessageHeader {
+                    num_required_signatures: 1,
+                    num_readonly_signed_accounts: 0,
+                    num_readonly_unsigned_accounts: 0,
+                },
+                recent_blockhash: Hash::new_unique(),
+                account_keys: vec![payer.pubkey(), Pubkey::new_unique()],
+                address_table_lookups,
+                instructions: vec![CompiledInstruction {
+                    program_id_index: 1,
+                    accounts: vec![0],
+                    data: vec![],
+                }],
+            });
+            VersionedTransaction::try_new(message, &[&payer]).expect("valid tx")
+        };
+
+        // only the missing table is referenced
+        let tx = build_tx(vec![MessageAddressTableLookup {
+            account_key: missing_lookup_table_pubkey,
+            writable_indexes: vec![0],
+            readonly_indexes: vec![],
+        }]);
+        let verdict = ofac_verdict(&tx, &ofac_addresses, &address_lookup_table_cache);
+        assert_eq!(
+            verdict,
+            OfacVerdict::UnresolvableLookupTable(missing_lookup_table_pubkey)
+        );
+        assert!(!verdict.is_sanctioned());
+        assert!(!is_tx_ofac_related(
             &tx,
             &ofac_addresses,
             &address_lookup_table_cache
         ));
+
+        // a sanctioned address in a resolvable table takes precedence over the missing table
+        let tx = build_tx(vec![
+            MessageAddressTableLookup {
+                account_key: missing_lookup_table_pubkey,
+                writable_indexes: vec![0],
+                readonly_indexes: vec![],
+            },
+            MessageAddressTableLookup {
+                account_key: lookup_table_pubkey,
+                writable_indexes: vec![],
+                readonly_indexes: vec![0],
+            },
+        ]);
+        assert_eq!(
+            ofac_verdict(&tx, &ofac_addresses, &address_lookup_table_cache),
+            OfacVerdict::LookupTableReadonly {
+                lookup_table: lookup_table_pubkey,
+                address: ofac_pubkey,
+            }
+        );
+    }
+
+    #[test]
+    fn test_lookup_table_policy_should_drop() {
+        let unresolvable = OfacVerdict::UnresolvableLookupTable(Pubkey::new_unique());
+        let sanctioned = OfacVerdict::StaticKey(Pubkey::new_unique());
+
+        let fail_open = OfacFilter::new(
+            OfacAddressesHandle::default(),
+            false,
+            "fail-open".parse().unwrap(),
+            None,
+        );
+        assert!(!fail_open.should_drop(&unresolvable));
+        assert!(fail_open.should_drop(&sanctioned));
+        assert!(!fail_open.should_drop(&OfacVerdict::Clean));
+
+        let fail_closed = OfacFilter::new(
+            OfacAddressesHandle::default(),
+            false,
+            LookupTablePolicy::FailClosed,
+            None,
+        );
+        assert!(fail_closed.should_drop(&unresolvable));
+        assert!(fail_closed.should_drop(&sanctioned));
+        assert!(!fail_closed.should_drop(&OfacVerdict::Clean));
+
+        assert!("fail-sometimes".parse::<LookupTablePolicy>().is_err());
+    }
+
+    #[test]
+    fn test_drop_and_fetch_drops_on_first_miss() {
+        let payer = Keypair::new();
+        let lookup_table_pubkey = Pubkey::new_unique();
+        let message = VersionedMessage::V0(v0::Message {
+            header: MessageHeader {
+                num_required_signatures: 1,
+                num_readonly_signed_accounts: 0,
+                num_readonly_unsigned_accounts: 0,
+            },
+            recent_blockhash: Hash::new_unique(),
+            account_keys: vec![payer.pubkey(), Pubkey::new_unique()],
+            address_table_lookups: vec![MessageAddressTableLookup {
+                account_key: lookup_table_pubkey,
+                writable_indexes: vec![0],
+                readonly_indexes: vec![],
+            }],
+            instructions: vec![CompiledInstruction {
+                program_id_index: 1,
+                accounts: vec![0],
+                data: vec![],
+            }],
+        });
+        let tx = VersionedTransaction::try_new(message, &[&payer]).expect("valid tx");
+
+        let (fetcher, fetch_receiver) = LookupTableFetchHandle::new(10, Duration::from_secs(60));
+        let ofac_filter = OfacFilter::new(
+            OfacAddressesHandle::default(),
+            false,
+            "drop-and-fetch".parse().unwrap(),
+            Some(fetcher),
+        );
+        let ofac_addresses = HashSet::from_iter([Pubkey::new_unique()]);
+        let address_lookup_table_cache = DashMap::new();
+
+        // the first transaction through an unseen table is dropped without waiting on RPC, and
+        // the table is queued to be fetched
+        let verdict = ofac_filter.verdict(&tx, &ofac_addresses, &address_lookup_table_cache);
+        assert_eq!(
+            verdict,
+            OfacVerdict::UnresolvableLookupTable(lookup_table_pubkey)
+        );
+        assert!(ofac_filter.should_drop(&verdict));
+        assert_eq!(fetch_receiver.try_recv(), Ok(lookup_table_pubkey));
+
+        // once the fetched table is cached, transactions using it are checked against it
+        address_lookup_table_cache.insert(
+            lookup_table_pubkey,
+            AddressLookupTableAccount {
+                key: lookup_table_pubkey,
+                addresses: vec![Pubkey::new_unique()],
+            },
+        );
+        let verdict = ofac_filter.verdict(&tx, &ofac_addresses, &address_lookup_table_cache);
+        assert_eq!(verdict, OfacVerdict::Clean);
+        assert!(fetch_receiver.is_empty());
     }
 
     #[test]
@@ -0,0 +1,64 @@
+//! Hot-reloadable OFAC address list backed by a watched file.
+//!
+//! The sanctions list lives in a file that compliance teams can edit without restarting
+//! the relayer. It's watched by a [`PubkeyListUpdater`], see [`crate::pubkey_list`] for the
+//! accepted formats, and swapped in atomically behind an [`OfacAddressesHandle`], so every
+//! OFAC check always sees the latest list.
+
+use std::{
+    collections::HashSet,
+    path::PathBuf,
+    sync::{atomic::AtomicBool, Arc},
+    thread,
+    time::Duration,
+};
+
+use solana_sdk::pubkey::Pubkey;
+
+use crate::pubkey_list::{PubkeyListHandle, PubkeyListUpdater};
+
+/// Shared access handle to the current set of OFAC-sanctioned addresses.
+pub type OfacAddressesHandle = PubkeyListHandle;
+
+/// Watches an OFAC address file and reloads it into an [`OfacAddressesHandle`] when it changes.
+pub struct OfacAddressesUpdater {
+    updater: PubkeyListUpdater,
+}
+
+impl OfacAddressesUpdater {
+    /// Loads the file once synchronously, then starts a thread that reloads it on change.
+    ///
+    /// # Arguments
+    /// * `path` - File containing the sanctions list
+    /// * `static_addresses` - Addresses that are always included (e.g. from the command line)
+    /// * `handle` - Shared handle that gets the merged list swapped in
+    /// * `refresh_interval` - How often to check the file for modifications
+    /// * `exit` - Shutdown signal for graceful termination
+    ///
+    /// # Panics
+    /// If the file can't be loaded at startup, since running without the configured
+    /// sanctions list isn't safe.
+    pub fn new(
+        path: PathBuf,
+        static_addresses: HashSet<Pubkey>,
+        handle: &OfacAddressesHandle,
+        refresh_interval: Duration,
+        exit: &Arc<AtomicBool>,
+    ) -> OfacAddressesUpdater {
+        let updater = PubkeyListUpdater::new(
+            "ofac_addresses",
+            path.clone(),
+            static_addresses,
+            handle,
+            refresh_interval,
+            exit,
+        )
+        .unwrap_or_else(|e| panic!("failed to load ofac addresses from {path:?}: {e}"));
+
+        OfacAddressesUpdater { updater }
+    }
+
+    pub fn join(self) -> thread::Result<()> {
+        self.updater.join()
+    }
+}
@@ -0,0 +1,272 @@
+//! Hot-reloadable pubkey lists backed by watched files.
+//!
+//! Lists such as the OFAC sanctions list or validator allow and deny lists live in files that
+//! operators can edit without restarting the relayer. Two formats are accepted:
+//! - **Plain text**: one base58 pubkey per line (or whitespace-separated), `#` starts a comment
+//! - **JSON**: an array of base58 pubkey strings
+//!
+//! A [`PubkeyListUpdater`] polls the file for modifications. Whenever it changes, it's parsed and
+//! the new set is swapped in atomically behind a [`PubkeyListHandle`], so every check always
+//! sees the latest list. A file that fails to parse leaves the previous list in place, and so
+//! does an empty file replacing a non-empty list, since that's far more likely a truncated or
+//! half-written file than an intentionally emptied one.
+
+use std::{
+    collections::HashSet,
+    fs, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
+    thread::{self, sleep, Builder, JoinHandle},
+    time::{Duration, Instant, SystemTime},
+};
+
+use log::{error, info};
+use solana_metrics::{datapoint_error, datapoint_info};
+use solana_sdk::pubkey::Pubkey;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum PubkeyListError {
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("json error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("invalid pubkey: {0}")]
+    InvalidPubkey(String),
+
+    #[error("file is empty, keeping the previous {0} pubkeys")]
+    Empty(usize),
+}
+
+pub type PubkeyListResult<T> = Result<T, PubkeyListError>;
+
+/// Shared access handle to a set of pubkeys that can be swapped out at runtime.
+///
+/// Readers take a cheap snapshot with [`PubkeyListHandle::load`]; writers replace the whole
+/// set at once, so a reader never observes a partially updated list.
+#[derive(Clone, Default)]
+pub struct PubkeyListHandle {
+    pubkeys: Arc<RwLock<Arc<HashSet<Pubkey>>>>,
+}
+
+impl PubkeyListHandle {
+    pub fn new(pubkeys: HashSet<Pubkey>) -> PubkeyListHandle {
+        PubkeyListHandle {
+            pubkeys: Arc::new(RwLock::new(Arc::new(pubkeys))),
+        }
+    }
+
+    /// Returns a snapshot of the current list
+    pub fn load(&self) -> Arc<HashSet<Pubkey>> {
+        self.pubkeys.read().unwrap().clone()
+    }
+
+    /// Atomically replaces the list, returning the previous one
+    pub fn store(&self, pubkeys: HashSet<Pubkey>) -> Arc<HashSet<Pubkey>> {
+        std::mem::replace(&mut *self.pubkeys.write().unwrap(), Arc::new(pubkeys))
+    }
+}
+
+/// Watches a pubkey list file and reloads it into a [`PubkeyListHandle`] when it changes.
+pub struct PubkeyListUpdater {
+    /// Polls the file for modifications
+    refresh_thread: JoinHandle<()>,
+}
+
+impl PubkeyListUpdater {
+    /// Loads the file once synchronously, then starts a thread that reloads it on change.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the list in logs, metrics and the thread name, e.g. "allowlist"
+    /// * `path` - File containing the list
+    /// * `static_pubkeys` - Pubkeys that are always included (e.g. from the command line)
+    /// * `handle` - Shared handle that gets the merged list swapped in
+    /// * `refresh_interval` - How often to check the file for modifications
+    /// * `exit` - Shutdown signal for graceful termination
+    pub fn new(
+        name: &'static str,
+        path: PathBuf,
+        static_pubkeys: HashSet<Pubkey>,
+        handle: &PubkeyListHandle,
+        refresh_interval: Duration,
+        exit: &Arc<AtomicBool>,
+    ) -> PubkeyListResult<PubkeyListUpdater> {
+        let mut last_modified = file_modified_time(&path).ok();
+        Self::reload(name, &path, &static_pubkeys, handle)?;
+
+        let handle = handle.clone();
+        let exit = exit.clone();
+        let refresh_thread = Builder::new()
+            .name(format!("{name}_updater"))
+            .spawn(move || {
+                let mut last_check = Instant::now();
+                while !exit.load(Ordering::Relaxed) {
+                    sleep(Duration::from_secs(1));
+                    if last_check.elapsed() < refresh_interval {
+                        continue;
+                    }
+                    last_check = Instant::now();
+
+                    let result: PubkeyListResult<()> = file_modified_time(&path)
+                        .map_err(Into::into)
+                        .and_then(|modified| {
+                            if last_modified != Some(modified) {
+                                Self::reload(name, &path, &static_pubkeys, &handle)?;
+                                last_modified = Some(modified);
+                            }
+                            Ok(())
+                        });
+                    if let Err(e) = result {
+                        error!("error reloading {name} from {path:?}: {e}");
+                        datapoint_error!(
+                            "pubkey_list_updater-error",
+                            "list" => name,
+                            ("error", e.to_string(), String),
+                        );
+                    }
+                }
+            })
+            .unwrap();
+
+        Ok(PubkeyListUpdater { refresh_thread })
+    }
+
+    pub fn join(self) -> thread::Result<()> {
+        self.refresh_thread.join()
+    }
+
+    fn reload(
+        name: &'static str,
+        path: &Path,
+        static_pubkeys: &HashSet<Pubkey>,
+        handle: &PubkeyListHandle,
+    ) -> PubkeyListResult<()> {
+        let mut pubkeys = read_pubkey_list_file(path)?;
+        let current_size = handle.load().len();
+        if pubkeys.is_empty() && current_size > 0 {
+            return Err(PubkeyListError::Empty(current_size));
+        }
+        pubkeys.extend(static_pubkeys.iter());
+
+        let new_size = pubkeys.len();
+        let old_size = handle.store(pubkeys).len();
+        info!("{name} reloaded from {path:?}: {old_size} -> {new_size} pubkeys");
+
+        datapoint_info!(
+            "pubkey_list_updater-reload",
+            "list" => name,
+            ("old_size", old_size, i64),
+            ("new_size", new_size, i64),
+        );
+        Ok(())
+    }
+}
+
+fn file_modified_time(path: &Path) -> io::Result<SystemTime> {
+    fs::metadata(path)?.modified()
+}
+
+/// Reads a pubkey list file in either plain text or JSON format.
+pub fn read_pubkey_list_file(path: &Path) -> PubkeyListResult<HashSet<Pubkey>> {
+    parse_pubkey_list(&fs::read_to_string(path)?)
+}
+
+/// Parses a JSON array of pubkeys if the contents look like JSON, otherwise
+/// whitespace-separated pubkeys with `#` comments.
+fn parse_pubkey_list(contents: &str) -> PubkeyListResult<HashSet<Pubkey>> {
+    if contents.trim_start().starts_with('[') {
+        let pubkeys: Vec<String> = serde_json::from_str(contents)?;
+        pubkeys
+            .iter()
+            .map(String::as_str)
+            .map(parse_pubkey)
+            .collect()
+    } else {
+        contents
+            .lines()
+            .map(|line| line.split('#').next().unwrap_or_default())
+            .flat_map(str::split_whitespace)
+            .map(parse_pubkey)
+            .collect()
+    }
+}
+
+fn parse_pubkey(pubkey: &str) -> PubkeyListResult<Pubkey> {
+    Pubkey::from_str(pubkey.trim()).map_err(|_| PubkeyListError::InvalidPubkey(pubkey.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashSet, fs};
+
+    use solana_sdk::pubkey::Pubkey;
+
+    use crate::pubkey_list::{
+        parse_pubkey_list, PubkeyListError, PubkeyListHandle, PubkeyListUpdater,
+    };
+
+    #[test]
+    fn test_parse_pubkey_list() {
+        let a = Pubkey::new_unique();
+        let b = Pubkey::new_unique();
+        let expected = HashSet::from_iter([a, b]);
+
+        let text = format!("# sanctioned\n{a}\n\n{b} # added later\n");
+        assert_eq!(parse_pubkey_list(&text).unwrap(), expected);
+
+        let json = format!("[\"{a}\", \"{b}\"]");
+        assert_eq!(parse_pubkey_list(&json).unwrap(), expected);
+
+        assert!(parse_pubkey_list("not-a-pubkey").is_err());
+        assert!(parse_pubkey_list("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_pubkey_list_handle_swap() {
+        let a = Pubkey::new_unique();
+        let handle = PubkeyListHandle::new(HashSet::from_iter([a]));
+        let snapshot = handle.load();
+
+        let old = handle.store(HashSet::new());
+        assert_eq!(old.len(), 1);
+        assert!(handle.load().is_empty());
+        // snapshots taken before the swap are unaffected
+        assert!(snapshot.contains(&a));
+    }
+
+    #[test]
+    fn test_reload_keeps_list_on_empty_file() {
+        let path = std::env::temp_dir().join(format!("pubkey_list-{}.txt", Pubkey::new_unique()));
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let handle = PubkeyListHandle::default();
+
+        // an empty list can start empty
+        fs::write(&path, "").unwrap();
+        PubkeyListUpdater::reload("test", &path, &HashSet::new(), &handle).unwrap();
+        assert!(handle.load().is_empty());
+
+        fs::write(&path, format!("{a}\n{b}\n")).unwrap();
+        PubkeyListUpdater::reload("test", &path, &HashSet::new(), &handle).unwrap();
+        assert_eq!(handle.load().len(), 2);
+
+        // a truncated file doesn't wipe the list
+        for truncated in ["", "# sanctioned\n", "["] {
+            fs::write(&path, truncated).unwrap();
+            assert!(PubkeyListUpdater::reload("test", &path, &HashSet::new(), &handle).is_err());
+            assert_eq!(handle.load().len(), 2);
+        }
+        fs::write(&path, "").unwrap();
+        assert!(matches!(
+            PubkeyListUpdater::reload("test", &path, &HashSet::new(), &handle),
+            Err(PubkeyListError::Empty(2))
+        ));
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -83,6 +83,9 @@ pub struct Tpu {
     
     /// Maintains up-to-date validator stake information for connection prioritization
     staked_nodes_updater_service: StakedNodesUpdaterService,
+
+    /// Validator stakes kept current by the staked nodes updater service
+    staked_nodes: Arc<RwLock<StakedNodes>>,
     
     /// Verifies transaction signatures for authenticity before banking stage
     sigverify_stage: SigVerifyStage,
@@ -221,6 +224,7 @@ impl Tpu {
             Tpu {
                 fetch_stage,
                 staked_nodes_updater_service,
+                staked_nodes,
                 sigverify_stage,
                 thread_handles: quic_tasks,
             },
@@ -228,6 +232,12 @@ impl Tpu {
         )
     }
 
+    /// Returns the validator stakes used for connection prioritization, which are refreshed
+    /// from RPC periodically.
+    pub fn staked_nodes(&self) -> Arc<RwLock<StakedNodes>> {
+        self.staked_nodes.clone()
+    }
+
     /// Gracefully shuts down all TPU stages and waits for threads to complete.
     /// This ensures clean resource cleanup and proper thread termination.
     /// 
@@ -1,4 +1,7 @@
-use tonic_build::configure;
+use tonic_build::{
+    configure,
+    manual::{self, Method, Service},
+};
 
 fn main() {
     const PROTOC_ENVAR: &str = "PROTOC";
@@ -8,6 +11,9 @@ fn main() {
     }
 
     configure()
+        // packets are fanned out to every subscribed validator, so share their data instead of
+        // copying it for each one
+        .bytes([".packet.Packet.data"])
         .compile(
             &[
                 "protos/auth.proto",
@@ -18,8 +24,39 @@ fn main() {
                 "protos/relayer.proto",
                 "protos/searcher.proto",
                 "protos/shared.proto",
+                // relayer-specific services that aren't part of the shared protos submodule
+                "relayer_protos/admin.proto",
             ],
-            &["protos"],
+            &["protos", "relayer_protos"],
         )
         .unwrap();
+
+    // the relayer's packet subscriptions stream messages encoded once and sent to every
+    // subscribed validator as is, which the generated prost codec can't do
+    let relayer = Service::builder()
+        .name("Relayer")
+        .package("relayer")
+        .method(
+            Method::builder()
+                .name("get_tpu_configs")
+                .route_name("GetTpuConfigs")
+                .input_type("crate::relayer::GetTpuConfigsRequest")
+                .output_type("crate::relayer::GetTpuConfigsResponse")
+                .codec_path("tonic::codec::ProstCodec")
+                .build(),
+        )
+        .method(
+            Method::builder()
+                .name("subscribe_packets")
+                .route_name("SubscribePackets")
+                .input_type("crate::relayer::SubscribePacketsRequest")
+                .output_type("bytes::Bytes")
+                .codec_path("crate::encoded::PreEncodedCodec")
+                .server_streaming()
+                .build(),
+        )
+        .build();
+    manual::Builder::new()
+        .build_client(false)
+        .compile(&[relayer]);
 }
@@ -1,10 +1,11 @@
+use bytes::Bytes;
 use solana_perf::packet::Packet;
 
 use crate::packet::{Meta as ProtoMeta, Packet as ProtoPacket, PacketFlags as ProtoPacketFlags};
 
 pub fn packet_to_proto_packet(p: &Packet) -> Option<ProtoPacket> {
     Some(ProtoPacket {
-        data: p.data(..)?.to_vec(),
+        data: Bytes::copy_from_slice(p.data(..)?),
         meta: Some(ProtoMeta {
             size: p.meta().size as u64,
             addr: p.meta().addr.to_string(),
@@ -0,0 +1,94 @@
+//! Serving streams of messages that are encoded once and sent to many clients.
+//!
+//! The relayer sends every packet batch to each subscribed validator. Rather than having tonic
+//! encode the same message once per subscriber, the batch is encoded into [`Bytes`] up front and
+//! the bytes are shared by every subscriber's stream. [`relayer_server`] is the Relayer service
+//! generated to stream such pre-encoded `SubscribePacketsResponse`s with [`PreEncodedCodec`];
+//! it's wire compatible with the service generated from `relayer.proto`.
+
+use std::marker::PhantomData;
+
+use bytes::{BufMut, Bytes};
+use prost::Message;
+use tonic::{
+    codec::{Codec, EncodeBuf, Encoder, ProstCodec},
+    Status,
+};
+
+include!(concat!(env!("OUT_DIR"), "/relayer.Relayer.rs"));
+
+/// Encodes a message for [`PreEncodedCodec`]
+pub fn encode_message<T: Message>(message: &T) -> Bytes {
+    Bytes::from(message.encode_to_vec())
+}
+
+/// Codec that sends messages already encoded with [`encode_message`] and decodes requests
+/// with prost.
+pub struct PreEncodedCodec<U> {
+    _pd: PhantomData<U>,
+}
+
+impl<U> Default for PreEncodedCodec<U> {
+    fn default() -> Self {
+        Self { _pd: PhantomData }
+    }
+}
+
+impl<U> Codec for PreEncodedCodec<U>
+where
+    U: Message + Default + Send + 'static,
+{
+    type Encode = Bytes;
+    type Decode = U;
+
+    type Encoder = PreEncodedEncoder;
+    type Decoder = <ProstCodec<(), U> as Codec>::Decoder;
+
+    fn encoder(&mut self) -> Self::Encoder {
+        PreEncodedEncoder
+    }
+
+    fn decoder(&mut self) -> Self::Decoder {
+        ProstCodec::<(), U>::default().decoder()
+    }
+}
+
+/// Writes pre-encoded messages as they are
+pub struct PreEncodedEncoder;
+
+impl Encoder for PreEncodedEncoder {
+    type Item = Bytes;
+    type Error = Status;
+
+    fn encode(&mut self, item: Bytes, buf: &mut EncodeBuf<'_>) -> Result<(), Status> {
+        buf.put(item);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+    use prost::Message;
+
+    use crate::{
+        encoded::encode_message,
+        relayer::{subscribe_packets_response, SubscribePacketsResponse},
+        shared::Heartbeat,
+    };
+
+    #[test]
+    fn test_encode_message() {
+        let response = SubscribePacketsResponse {
+            header: None,
+            msg: Some(subscribe_packets_response::Msg::Heartbeat(Heartbeat {
+                count: 1,
+            })),
+        };
+        let encoded = encode_message(&response);
+        assert_eq!(
+            SubscribePacketsResponse::decode(BytesMut::from(&encoded[..])).unwrap(),
+            response
+        );
+    }
+}
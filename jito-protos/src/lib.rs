@@ -1,4 +1,9 @@
 pub mod convert;
+pub mod encoded;
+
+pub mod admin {
+    tonic::include_proto!("admin");
+}
 
 pub mod auth {
     tonic::include_proto!("auth");
@@ -0,0 +1,212 @@
+//! Admin gRPC service for operating a running relayer.
+//!
+//! Lets operators inspect validator subscriptions, disconnect or temporarily ban misbehaving
+//! validators, revoke their refresh tokens, force a leader schedule refresh, and read the inputs
+//! behind the health state.
+//! The service has no authentication of its own, so it must only be bound to a private address.
+
+use std::{
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use jito_protos::admin::{
+    relayer_admin_server::RelayerAdmin, BanValidatorRequest, BanValidatorResponse,
+    DisconnectValidatorRequest, DisconnectValidatorResponse, GetHealthRequest, GetHealthResponse,
+    ListBansRequest, ListBansResponse, ListSubscriptionsRequest, ListSubscriptionsResponse,
+    RefreshLeaderScheduleRequest, RefreshLeaderScheduleResponse, RevokeRefreshTokensRequest,
+    RevokeRefreshTokensResponse, UnbanValidatorRequest, UnbanValidatorResponse, ValidatorBan,
+    ValidatorSubscription,
+};
+use jito_rpc::load_balancer::LoadBalancer;
+use log::info;
+use solana_sdk::pubkey::Pubkey;
+use tonic::{Request, Response, Status};
+
+use crate::{
+    health_manager::{HealthInputs, HealthState},
+    refresh_tokens::RefreshTokenStore,
+    relayer::RelayerHandle,
+    schedule_cache::LeaderScheduleUpdatingHandle,
+};
+
+pub struct RelayerAdminImpl {
+    relayer_handle: RelayerHandle,
+    leader_schedule_cache: LeaderScheduleUpdatingHandle,
+    rpc_load_balancer: Arc<LoadBalancer>,
+    health_inputs: HealthInputs,
+    is_connected_to_block_engine: Arc<AtomicBool>,
+    refresh_tokens: RefreshTokenStore,
+}
+
+impl RelayerAdminImpl {
+    pub fn new(
+        relayer_handle: RelayerHandle,
+        leader_schedule_cache: LeaderScheduleUpdatingHandle,
+        rpc_load_balancer: &Arc<LoadBalancer>,
+        health_inputs: HealthInputs,
+        is_connected_to_block_engine: &Arc<AtomicBool>,
+        refresh_tokens: RefreshTokenStore,
+    ) -> RelayerAdminImpl {
+        RelayerAdminImpl {
+            relayer_handle,
+            leader_schedule_cache,
+            rpc_load_balancer: rpc_load_balancer.clone(),
+            health_inputs,
+            is_connected_to_block_engine: is_connected_to_block_engine.clone(),
+            refresh_tokens,
+        }
+    }
+
+    fn parse_pubkey(pubkey: &str) -> Result<Pubkey, Status> {
+        Pubkey::from_str(pubkey).map_err(|_| Status::invalid_argument("Invalid pubkey supplied."))
+    }
+
+    fn unix_time_ms(time: SystemTime) -> u64 {
+        time.duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64
+    }
+}
+
+#[tonic::async_trait]
+impl RelayerAdmin for RelayerAdminImpl {
+    async fn list_subscriptions(
+        &self,
+        _: Request<ListSubscriptionsRequest>,
+    ) -> Result<Response<ListSubscriptionsResponse>, Status> {
+        let subscriptions = self
+            .relayer_handle
+            .subscriptions()
+            .into_iter()
+            .map(|subscription| ValidatorSubscription {
+                pubkey: subscription.pubkey.to_string(),
+                queue_depth: subscription.queue_depth as u64,
+                queue_capacity: subscription.queue_capacity as u64,
+                num_packets_forwarded: subscription.num_packets_forwarded,
+                num_packets_dropped: subscription.num_packets_dropped,
+            })
+            .collect();
+        Ok(Response::new(ListSubscriptionsResponse { subscriptions }))
+    }
+
+    async fn disconnect_validator(
+        &self,
+        request: Request<DisconnectValidatorRequest>,
+    ) -> Result<Response<DisconnectValidatorResponse>, Status> {
+        let pubkey = Self::parse_pubkey(&request.into_inner().pubkey)?;
+        let was_connected = self.relayer_handle.disconnect_validator(&pubkey);
+        info!("admin disconnected validator {pubkey}, was connected: {was_connected}");
+        Ok(Response::new(DisconnectValidatorResponse { was_connected }))
+    }
+
+    async fn ban_validator(
+        &self,
+        request: Request<BanValidatorRequest>,
+    ) -> Result<Response<BanValidatorResponse>, Status> {
+        let request = request.into_inner();
+        let pubkey = Self::parse_pubkey(&request.pubkey)?;
+        if request.duration_secs == 0 {
+            return Err(Status::invalid_argument(
+                "Ban duration must be greater than 0.",
+            ));
+        }
+
+        let duration = Duration::from_secs(request.duration_secs);
+        let (banned_until, was_connected) = self.relayer_handle.ban_validator(&pubkey, duration);
+        info!("admin banned validator {pubkey} for {duration:?}, was connected: {was_connected}");
+        Ok(Response::new(BanValidatorResponse {
+            banned_until_unix_ms: Self::unix_time_ms(banned_until),
+            was_connected,
+        }))
+    }
+
+    async fn unban_validator(
+        &self,
+        request: Request<UnbanValidatorRequest>,
+    ) -> Result<Response<UnbanValidatorResponse>, Status> {
+        let pubkey = Self::parse_pubkey(&request.into_inner().pubkey)?;
+        let was_banned = self.relayer_handle.unban_validator(&pubkey);
+        info!("admin unbanned validator {pubkey}, was banned: {was_banned}");
+        Ok(Response::new(UnbanValidatorResponse { was_banned }))
+    }
+
+    async fn list_bans(
+        &self,
+        _: Request<ListBansRequest>,
+    ) -> Result<Response<ListBansResponse>, Status> {
+        let bans = self
+            .relayer_handle
+            .banned_validators()
+            .into_iter()
+            .map(|(pubkey, banned_until)| ValidatorBan {
+                pubkey: pubkey.to_string(),
+                banned_until_unix_ms: Self::unix_time_ms(banned_until),
+            })
+            .collect();
+        Ok(Response::new(ListBansResponse { bans }))
+    }
+
+    async fn refresh_leader_schedule(
+        &self,
+        _: Request<RefreshLeaderScheduleRequest>,
+    ) -> Result<Response<RefreshLeaderScheduleResponse>, Status> {
+        let leader_schedule_cache = self.leader_schedule_cache.clone();
+        let rpc_load_balancer = self.rpc_load_balancer.clone();
+
+        // the refresh makes blocking RPC calls
+        let refreshed =
+            tokio::task::spawn_blocking(move || leader_schedule_cache.refresh(&rpc_load_balancer))
+                .await
+                .map_err(|_| Status::internal("leader schedule refresh task failed"))?;
+        if !refreshed {
+            return Err(Status::unavailable(
+                "Error fetching leader schedule from RPC.",
+            ));
+        }
+
+        let slots_in_schedule = self.leader_schedule_cache.num_slots() as u64;
+        info!("admin refreshed leader schedule, slots in schedule: {slots_in_schedule}");
+        Ok(Response::new(RefreshLeaderScheduleResponse {
+            slots_in_schedule,
+        }))
+    }
+
+    async fn revoke_refresh_tokens(
+        &self,
+        request: Request<RevokeRefreshTokensRequest>,
+    ) -> Result<Response<RevokeRefreshTokensResponse>, Status> {
+        let pubkey = Self::parse_pubkey(&request.into_inner().pubkey)?;
+        let num_revoked = self.refresh_tokens.revoke_validator(&pubkey);
+        info!("admin revoked {num_revoked} refresh token families of validator {pubkey}");
+        Ok(Response::new(RevokeRefreshTokensResponse {
+            num_revoked: num_revoked as u64,
+        }))
+    }
+
+    async fn get_health(
+        &self,
+        _: Request<GetHealthRequest>,
+    ) -> Result<Response<GetHealthResponse>, Status> {
+        Ok(Response::new(GetHealthResponse {
+            healthy: self.health_inputs.state() == HealthState::Healthy,
+            last_slot: self.health_inputs.last_slot(),
+            ms_since_last_slot: self
+                .health_inputs
+                .time_since_last_slot()
+                .map(|elapsed| elapsed.as_millis() as u64)
+                .unwrap_or(u64::MAX),
+            missing_slot_unhealthy_threshold_ms: self
+                .health_inputs
+                .missing_slot_unhealthy_threshold()
+                .as_millis() as u64,
+            connected_to_block_engine: self.is_connected_to_block_engine.load(Ordering::Relaxed),
+            num_connected_validators: self.relayer_handle.connected_validators().len() as u64,
+            slots_in_leader_schedule: self.leader_schedule_cache.num_slots() as u64,
+        }))
+    }
+}
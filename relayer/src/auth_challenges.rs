@@ -1,7 +1,9 @@
 //! Authentication challenge management for the Jito relayer.
 //! 
 //! This module implements a secure challenge-response authentication system that:
-//! - Prevents DOS attacks by limiting one challenge per IP address
+//! - Prevents DOS attacks by capping the number of challenges per IP address
+//! - Keeps one challenge per (IP, pubkey), so several validators can authenticate from one
+//!   address, e.g. behind a NAT or a host running a primary and a hot-spare identity
 //! - Uses cryptographic signatures to verify validator identity  
 //! - Automatically expires old challenges to prevent memory leaks
 //! - Maintains challenge priority ordering for efficient cleanup
@@ -13,10 +15,11 @@
 //! 4. Server verifies signature and issues JWT tokens
 //! 5. Challenge is removed after successful authentication
 
-use std::{cmp::Reverse, net::IpAddr, sync::Arc};
+use std::{cmp::Reverse, collections::HashMap, net::IpAddr, sync::Arc};
 
 use chrono::{NaiveDateTime, Utc};
 use keyed_priority_queue::KeyedPriorityQueue;
+use solana_sdk::pubkey::Pubkey;
 use tokio::sync::Mutex;
 
 use crate::auth_interceptor::Claims;
@@ -88,19 +91,57 @@ impl Ord for AuthChallenge {
     }
 }
 
+/// Identifies a challenge by the IP address and pubkey of the validator that requested it.
+pub(crate) type ChallengeKey = (IpAddr, Pubkey);
+
+/// Returned by [`AuthChallenges::push`] when the IP address already has the maximum number of
+/// challenges outstanding.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct TooManyChallenges;
+
+#[derive(Default)]
+struct AuthChallengesInner {
+    challenges: KeyedPriorityQueue<ChallengeKey, Reverse<AuthChallenge>>,
+    /// Number of challenges in `challenges` for each IP address
+    num_challenges_per_ip: HashMap<IpAddr, usize>,
+}
+
+impl AuthChallengesInner {
+    fn remove_ip_challenge(&mut self, ip: &IpAddr) {
+        if let Some(num_challenges) = self.num_challenges_per_ip.get_mut(ip) {
+            *num_challenges -= 1;
+            if *num_challenges == 0 {
+                self.num_challenges_per_ip.remove(ip);
+            }
+        }
+    }
+}
+
 /// Thread-safe container for managing authentication challenges across multiple IP addresses.
 /// 
-/// Uses a priority queue keyed by IP address to:
-/// - Limit one challenge per IP (DOS protection)
+/// Uses a priority queue keyed by IP address and pubkey to:
+/// - Keep one challenge per validator identity on each IP
+/// - Cap the number of challenges per IP (DOS protection)
 /// - Efficiently expire old challenges in chronological order
 /// - Support concurrent access from multiple gRPC handlers
 /// 
 /// The `Reverse` wrapper ensures older challenges (with earlier expiration times)
 /// are prioritized for cleanup, since the priority queue is normally max-heap.
-#[derive(Clone, Default)]
-pub(crate) struct AuthChallenges(Arc<Mutex<KeyedPriorityQueue<IpAddr, Reverse<AuthChallenge>>>>);
+#[derive(Clone)]
+pub(crate) struct AuthChallenges {
+    inner: Arc<Mutex<AuthChallengesInner>>,
+    /// Maximum number of challenges outstanding for a single IP address
+    max_challenges_per_ip: usize,
+}
 
 impl AuthChallenges {
+    pub(crate) fn new(max_challenges_per_ip: usize) -> Self {
+        Self {
+            inner: Arc::default(),
+            max_challenges_per_ip,
+        }
+    }
+
     /// Removes all expired challenges from the queue.
     /// 
     /// This is called periodically by a background task to prevent memory leaks
@@ -111,62 +152,165 @@ impl AuthChallenges {
     /// O(k) where k is the number of expired challenges. Since challenges are
     /// ordered by expiration time, we can stop as soon as we find a non-expired challenge.
     pub(crate) async fn remove_all_expired(&self) {
-        let mut inner = self.0.lock().await;
+        let mut inner = self.inner.lock().await;
         // Remove expired challenges from the front of the queue
-        while let Some((_ip_addr, auth_challenge)) = inner.peek() {
+        while let Some((_key, auth_challenge)) = inner.challenges.peek() {
             if auth_challenge.0.is_expired() {
-                inner.pop(); // Remove expired challenge
+                // Remove expired challenge
+                if let Some(((ip, _pubkey), _)) = inner.challenges.pop() {
+                    inner.remove_ip_challenge(&ip);
+                }
             } else {
                 break; // All remaining challenges are still valid
             }
         }
     }
 
-    /// Adds or updates a challenge for the given IP address.
+    /// Adds or updates the challenge for the given IP address and pubkey.
     /// 
-    /// If the IP already has a challenge, it will be replaced with the new one.
-    /// This enforces the "one challenge per IP" DOS protection policy.
+    /// If the validator already has a challenge from this IP, it will be replaced with the
+    /// new one. A challenge for a new pubkey is rejected once the IP has reached its cap.
     /// 
     /// # Arguments
-    /// * `ip` - The IP address of the validator requesting authentication
+    /// * `key` - The IP address and pubkey of the validator requesting authentication
     /// * `challenge` - The challenge wrapped in Reverse for priority queue ordering
-    pub(crate) async fn push(&self, ip: IpAddr, challenge: Reverse<AuthChallenge>) {
-        let mut inner = self.0.lock().await;
-        inner.push(ip, challenge); // Replaces existing challenge for this IP
+    pub(crate) async fn push(
+        &self,
+        key: ChallengeKey,
+        challenge: Reverse<AuthChallenge>,
+    ) -> Result<(), TooManyChallenges> {
+        let mut inner = self.inner.lock().await;
+        if inner.challenges.get_priority(&key).is_none() {
+            let num_challenges = inner.num_challenges_per_ip.entry(key.0).or_default();
+            if *num_challenges >= self.max_challenges_per_ip {
+                return Err(TooManyChallenges);
+            }
+            *num_challenges += 1;
+        }
+        inner.challenges.push(key, challenge); // Replaces existing challenge for this key
+        Ok(())
     }
 
     /// Returns the current number of active challenges in the system.
     /// 
     /// Used for capacity checking to prevent DOS attacks through challenge flooding.
     pub(crate) async fn len(&self) -> usize {
-        let inner = self.0.lock().await;
-        inner.len()
+        let inner = self.inner.lock().await;
+        inner.challenges.len()
     }
 
-    /// Retrieves the challenge for a specific IP address.
+    /// Retrieves the challenge for a specific IP address and pubkey.
     /// 
-    /// Returns None if the IP has no active challenge or if the challenge has expired.
+    /// Returns None if the validator has no challenge from this IP. Expired challenges may
+    /// still be returned until the cleanup task removes them.
     /// This is used during the authentication flow to validate challenge responses.
     /// 
     /// # Arguments
-    /// * `ip` - The IP address to lookup
+    /// * `key` - The IP address and pubkey to lookup
     /// 
     /// # Returns
-    /// The challenge for this IP, or None if no challenge exists
-    pub(crate) async fn get_priority(&self, ip: &IpAddr) -> Option<Reverse<AuthChallenge>> {
-        let inner = self.0.lock().await;
-        inner.get_priority(ip).cloned()
+    /// The challenge for this key, or None if no challenge exists
+    pub(crate) async fn get_priority(&self, key: &ChallengeKey) -> Option<Reverse<AuthChallenge>> {
+        let inner = self.inner.lock().await;
+        inner.challenges.get_priority(key).cloned()
     }
 
-    /// Removes the challenge for a specific IP address.
+    /// Removes the challenge for a specific IP address and pubkey.
     /// 
     /// This is called after successful authentication to clean up the used challenge.
     /// 
     /// # Parameters
     /// 
-    /// * `ip` - The IP address whose challenge should be removed
-    pub(crate) async fn remove(&self, ip: &IpAddr) {
-        let mut inner = self.0.lock().await;
-        let _ = inner.remove(ip);
+    /// * `key` - The IP address and pubkey whose challenge should be removed
+    pub(crate) async fn remove(&self, key: &ChallengeKey) {
+        let mut inner = self.inner.lock().await;
+        if inner.challenges.remove(key).is_some() {
+            inner.remove_ip_challenge(&key.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cmp::Reverse,
+        net::{IpAddr, Ipv4Addr},
+        ops::Add,
+    };
+
+    use chrono::{Duration, Utc};
+    use solana_sdk::pubkey::Pubkey;
+
+    use crate::{
+        auth_challenges::{AuthChallenge, AuthChallenges, TooManyChallenges},
+        auth_interceptor::Claims,
+    };
+
+    fn challenge(ip: IpAddr, pubkey: Pubkey, ttl: Duration) -> Reverse<AuthChallenge> {
+        let claims = Claims {
+            client_ip: ip,
+            client_pubkey: pubkey,
+            expires_at_utc: Utc::now().add(Duration::minutes(1)).naive_utc(),
+            jti: 0,
+            family: 0,
+        };
+        Reverse(AuthChallenge {
+            challenge: pubkey.to_string(),
+            access_claims: claims,
+            refresh_claims: claims,
+            expires_at_utc: Utc::now().add(ttl).naive_utc(),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_challenges_per_ip() {
+        let auth_challenges = AuthChallenges::new(2);
+        let ip = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+        let (primary, spare, third) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+
+        for pubkey in [primary, spare] {
+            auth_challenges
+                .push((ip, pubkey), challenge(ip, pubkey, Duration::minutes(1)))
+                .await
+                .unwrap();
+        }
+        // both identities keep their own challenge
+        for pubkey in [primary, spare] {
+            let auth_challenge = auth_challenges.get_priority(&(ip, pubkey)).await.unwrap();
+            assert_eq!(auth_challenge.0.challenge, pubkey.to_string());
+        }
+
+        // the IP is at its cap, but existing challenges can still be replaced
+        assert_eq!(
+            auth_challenges
+                .push((ip, third), challenge(ip, third, Duration::minutes(1)))
+                .await,
+            Err(TooManyChallenges)
+        );
+        auth_challenges
+            .push((ip, spare), challenge(ip, spare, Duration::minutes(1)))
+            .await
+            .unwrap();
+        let other_ip = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2));
+        auth_challenges
+            .push(
+                (other_ip, third),
+                challenge(other_ip, third, Duration::minutes(1)),
+            )
+            .await
+            .unwrap();
+
+        auth_challenges.remove(&(ip, primary)).await;
+        auth_challenges
+            .push((ip, third), challenge(ip, third, -Duration::minutes(1)))
+            .await
+            .unwrap();
+        auth_challenges.remove_all_expired().await;
+        assert_eq!(auth_challenges.len().await, 2);
+        assert!(auth_challenges.get_priority(&(ip, third)).await.is_none());
     }
 }
@@ -0,0 +1,336 @@
+//! Structured audit log of validator authentication.
+//!
+//! Challenges, token issuance, refreshes, rejected auth requests and packet subscriptions are
+//! written as JSON lines to a file or stdout, so operators can tell why a validator can't
+//! connect and feed the events to their log pipeline. Rejections carry a stable reason code.
+//!
+//! Events are handed to a writer thread over a bounded channel. When the writer falls behind,
+//! events are dropped and counted instead of slowing down the auth service.
+
+use std::{
+    fs::OpenOptions,
+    io::{self, BufWriter, Write},
+    net::IpAddr,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    thread::{self, Builder, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use crossbeam_channel::{bounded, Receiver, RecvTimeoutError, Sender};
+use log::error;
+use serde::Serialize;
+use solana_metrics::{datapoint_error, datapoint_info};
+
+use crate::refresh_tokens::RefreshTokenError;
+
+/// Events waiting to be written before new ones are dropped
+const AUTH_EVENT_QUEUE_CAPACITY: usize = 10_000;
+
+const REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Why an auth service request was rejected
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthFailureReason {
+    RelayerUnhealthy,
+    RateLimited,
+    Overloaded,
+    TooManyChallenges,
+    InvalidRole,
+    InvalidPubkey,
+    Unauthorized,
+    MissingChallenge,
+    ChallengeMismatch,
+    InvalidSignature,
+    InvalidRefreshToken,
+    RefreshTokenExpired,
+    RefreshTokenUnknown,
+    RefreshTokenRevoked,
+    RefreshTokenReused,
+    SigningError,
+}
+
+impl From<&RefreshTokenError> for AuthFailureReason {
+    fn from(e: &RefreshTokenError) -> AuthFailureReason {
+        match e {
+            RefreshTokenError::UnknownFamily | RefreshTokenError::UnknownToken => {
+                AuthFailureReason::RefreshTokenUnknown
+            }
+            RefreshTokenError::Revoked => AuthFailureReason::RefreshTokenRevoked,
+            RefreshTokenError::Reused => AuthFailureReason::RefreshTokenReused,
+        }
+    }
+}
+
+/// Why a packet subscription ended
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SubscriptionCloseReason {
+    /// The stream was closed by the validator or fell too far behind
+    SendFailed,
+    RelayerUnhealthy,
+    /// The validator subscribed again
+    Replaced,
+    /// Disconnected by an operator
+    Disconnected,
+    /// Banned by an operator
+    Banned,
+    /// The validator's queue stayed full for longer than the slow subscriber timeout
+    TooSlow,
+    /// The validator is no longer authorized, e.g. it was added to the denylist
+    Unauthorized,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum AuthEvent {
+    ChallengeIssued {
+        pubkey: String,
+        client_ip: IpAddr,
+    },
+    TokensIssued {
+        pubkey: String,
+        client_ip: IpAddr,
+        access_token_expires_at_unix_ts: i64,
+        refresh_token_expires_at_unix_ts: i64,
+    },
+    TokenRefreshed {
+        pubkey: String,
+        client_ip: IpAddr,
+        access_token_expires_at_unix_ts: i64,
+        refresh_token_rotated: bool,
+    },
+    AuthFailed {
+        rpc: &'static str,
+        reason: AuthFailureReason,
+        client_ip: IpAddr,
+        /// Unset when the request was rejected before its pubkey was known
+        pubkey: Option<String>,
+    },
+    SubscriptionOpened {
+        pubkey: String,
+        client_ip: Option<IpAddr>,
+    },
+    SubscriptionClosed {
+        pubkey: String,
+        reason: SubscriptionCloseReason,
+        connected_secs: i64,
+    },
+}
+
+#[derive(Serialize)]
+struct AuthEventRecord<'a> {
+    timestamp: String,
+    #[serde(flatten)]
+    event: &'a AuthEvent,
+}
+
+/// Handle for recording auth events. A disabled log (the default) discards every event.
+#[derive(Clone, Default)]
+pub struct AuthEventLog {
+    sender: Option<Sender<(DateTime<Utc>, AuthEvent)>>,
+    num_dropped: Arc<AtomicU64>,
+}
+
+impl AuthEventLog {
+    pub fn disabled() -> AuthEventLog {
+        AuthEventLog::default()
+    }
+
+    pub fn record(&self, event: AuthEvent) {
+        if let Some(sender) = &self.sender {
+            if sender.try_send((Utc::now(), event)).is_err() {
+                self.num_dropped.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+/// Writes the events recorded through its [`AuthEventLog`] to a file or stdout.
+pub struct AuthEventLogger {
+    auth_events: AuthEventLog,
+    writer_thread: JoinHandle<()>,
+}
+
+impl AuthEventLogger {
+    /// Starts a thread appending events to `path` as JSON lines, or writing them to stdout if
+    /// `path` is `-`.
+    ///
+    /// # Arguments
+    /// * `path` - File to append events to, created if it doesn't exist
+    /// * `exit` - Shutdown signal; queued events are written before the thread exits
+    pub fn new(path: PathBuf, exit: &Arc<AtomicBool>) -> io::Result<AuthEventLogger> {
+        let writer: Box<dyn Write + Send> = if path == Path::new("-") {
+            Box::new(io::stdout())
+        } else {
+            Box::new(OpenOptions::new().create(true).append(true).open(&path)?)
+        };
+
+        let (sender, receiver) = bounded(AUTH_EVENT_QUEUE_CAPACITY);
+        let auth_events = AuthEventLog {
+            sender: Some(sender),
+            num_dropped: Arc::default(),
+        };
+
+        let num_dropped = auth_events.num_dropped.clone();
+        let exit = exit.clone();
+        let writer_thread = Builder::new()
+            .name("auth_event_logger".to_string())
+            .spawn(move || Self::run(path, BufWriter::new(writer), receiver, &num_dropped, &exit))
+            .unwrap();
+
+        Ok(AuthEventLogger {
+            auth_events,
+            writer_thread,
+        })
+    }
+
+    pub fn handle(&self) -> AuthEventLog {
+        self.auth_events.clone()
+    }
+
+    pub fn join(self) -> thread::Result<()> {
+        self.writer_thread.join()
+    }
+
+    fn run(
+        path: PathBuf,
+        mut writer: impl Write,
+        receiver: Receiver<(DateTime<Utc>, AuthEvent)>,
+        num_dropped: &AtomicU64,
+        exit: &AtomicBool,
+    ) {
+        let mut num_written = 0u64;
+        let mut num_write_errors = 0u64;
+        let mut last_report = Instant::now();
+
+        loop {
+            let exiting = exit.load(Ordering::Relaxed);
+            let events: Vec<_> = if exiting {
+                receiver.try_iter().collect()
+            } else {
+                match receiver.recv_timeout(Duration::from_millis(100)) {
+                    Ok(event) => std::iter::once(event).chain(receiver.try_iter()).collect(),
+                    Err(RecvTimeoutError::Timeout) => Vec::new(),
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+            };
+
+            if !events.is_empty() {
+                // flushed once the queue is drained so lines show up promptly
+                let result = events
+                    .iter()
+                    .try_for_each(|(timestamp, event)| {
+                        Self::write_event(&mut writer, timestamp, event)
+                    })
+                    .and_then(|()| writer.flush());
+                match result {
+                    Ok(()) => num_written += events.len() as u64,
+                    Err(e) => {
+                        if num_write_errors == 0 {
+                            error!("error writing auth events to {path:?}: {e}");
+                        }
+                        num_write_errors += 1;
+                    }
+                }
+            }
+
+            if exiting || last_report.elapsed() >= REPORT_INTERVAL {
+                datapoint_info!(
+                    "auth_event_logger-stats",
+                    ("num_written", num_written, i64),
+                    ("num_dropped", num_dropped.swap(0, Ordering::Relaxed), i64),
+                );
+                if num_write_errors > 0 {
+                    datapoint_error!(
+                        "auth_event_logger-write_errors",
+                        ("num_write_errors", num_write_errors, i64),
+                    );
+                }
+                num_written = 0;
+                num_write_errors = 0;
+                last_report = Instant::now();
+            }
+
+            if exiting {
+                break;
+            }
+        }
+    }
+
+    fn write_event(
+        writer: &mut impl Write,
+        timestamp: &DateTime<Utc>,
+        event: &AuthEvent,
+    ) -> io::Result<()> {
+        let record = AuthEventRecord {
+            timestamp: timestamp.to_rfc3339_opts(SecondsFormat::Millis, true),
+            event,
+        };
+        serde_json::to_writer(&mut *writer, &record)?;
+        writer.write_all(b"\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        net::{IpAddr, Ipv4Addr},
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+    };
+
+    use serde_json::Value;
+
+    use crate::auth_events::{AuthEvent, AuthEventLog, AuthEventLogger, AuthFailureReason};
+
+    #[test]
+    fn test_auth_event_logger() {
+        let path =
+            std::env::temp_dir().join(format!("relayer-auth-events-{}.jsonl", std::process::id()));
+        let exit = Arc::new(AtomicBool::new(false));
+        let logger = AuthEventLogger::new(path.clone(), &exit).unwrap();
+        let auth_events = logger.handle();
+        let client_ip = IpAddr::V4(Ipv4Addr::LOCALHOST);
+
+        auth_events.record(AuthEvent::ChallengeIssued {
+            pubkey: "validator".to_string(),
+            client_ip,
+        });
+        auth_events.record(AuthEvent::AuthFailed {
+            rpc: "generate_auth_tokens",
+            reason: AuthFailureReason::InvalidSignature,
+            client_ip,
+            pubkey: None,
+        });
+        // a disabled log drops events without writing them anywhere
+        AuthEventLog::disabled().record(AuthEvent::ChallengeIssued {
+            pubkey: "validator".to_string(),
+            client_ip,
+        });
+
+        exit.store(true, Ordering::Relaxed);
+        logger.join().unwrap();
+
+        let lines: Vec<Value> = std::fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["event"], "challenge_issued");
+        assert_eq!(lines[0]["client_ip"], "127.0.0.1");
+        assert!(lines[0]["timestamp"].is_string());
+        assert_eq!(lines[1]["event"], "auth_failed");
+        assert_eq!(lines[1]["reason"], "invalid_signature");
+        assert_eq!(lines[1]["pubkey"], Value::Null);
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -1,13 +1,18 @@
-use std::{net::IpAddr, str::FromStr, sync::Arc};
+use std::{net::IpAddr, str::FromStr};
 
 use chrono::{DateTime, NaiveDateTime, Utc};
-use jwt::{AlgorithmType, Header, PKeyWithDigest, Token, Verified, VerifyWithKey};
+use ipnet::IpNet;
 use log::*;
-use openssl::pkey::Public;
 use serde::{Deserialize, Serialize};
+use solana_metrics::datapoint_error;
 use solana_sdk::pubkey::Pubkey;
 use tonic::{metadata::MetadataMap, service::Interceptor, Request, Status};
 
+use crate::{
+    keyring::{KeyringHandle, VerifiedToken},
+    refresh_tokens::{format_token_id, parse_token_id, TokenId},
+};
+
 /// What the JWT token will be encoded with.
 #[derive(Copy, Clone)]
 pub struct Claims {
@@ -19,6 +24,12 @@ pub struct Claims {
 
     /// This token's expirations.
     pub expires_at_utc: NaiveDateTime,
+
+    /// Unique id of this token.
+    pub jti: TokenId,
+
+    /// Refresh token family this token was issued under.
+    pub family: TokenId,
 }
 
 impl Claims {
@@ -36,6 +47,8 @@ impl From<&DeSerClaims> for Claims {
             expires_at_utc: DateTime::from_timestamp(de_ser_claims.expires_at_unix_ts, 0)
                 .unwrap()
                 .naive_utc(),
+            jti: parse_token_id(&de_ser_claims.jti),
+            family: parse_token_id(&de_ser_claims.family),
         }
     }
 }
@@ -45,6 +58,12 @@ pub struct DeSerClaims {
     pub client_ip: String,
     pub client_pubkey: String,
     pub expires_at_unix_ts: i64,
+    /// Hex token id; missing from tokens issued before ids were added
+    #[serde(default)]
+    pub jti: String,
+    /// Hex refresh token family id
+    #[serde(default, rename = "fam")]
+    pub family: String,
 }
 
 impl From<Claims> for DeSerClaims {
@@ -53,27 +72,49 @@ impl From<Claims> for DeSerClaims {
             client_ip: claims.client_ip.to_string(),
             client_pubkey: claims.client_pubkey.to_string(),
             expires_at_unix_ts: claims.expires_at_utc.and_utc().timestamp(),
+            jti: format_token_id(claims.jti),
+            family: format_token_id(claims.family),
         }
     }
 }
 
+/// Binds access tokens to the IP address they were issued to.
+#[derive(Clone, Debug, Default)]
+pub struct ClientIpBinding {
+    /// Ranges a client may move within, e.g. a validator behind a NAT pool. A token issued to
+    /// an address in one of these ranges is accepted from any address in the same range.
+    allowed_cidrs: Vec<IpNet>,
+}
+
+impl ClientIpBinding {
+    pub fn new(allowed_cidrs: Vec<IpNet>) -> Self {
+        Self { allowed_cidrs }
+    }
+
+    /// Whether a token issued to `token_ip` may be used from `remote_ip`.
+    pub fn allows(&self, token_ip: IpAddr, remote_ip: IpAddr) -> bool {
+        token_ip == remote_ip
+            || self
+                .allowed_cidrs
+                .iter()
+                .any(|cidr| cidr.contains(&token_ip) && cidr.contains(&remote_ip))
+    }
+}
+
 #[derive(Clone)]
 pub struct AuthInterceptor {
-    /// The key used to verify tokens.
-    verifying_key: Arc<PKeyWithDigest<Public>>,
+    /// The keys used to verify tokens, which also determine the expected signing algos.
+    keyring: KeyringHandle,
 
-    /// The tokens' expected signing algo.
-    expected_signing_algo: AlgorithmType,
+    /// When set, tokens are rejected if used from an IP they weren't issued to.
+    ip_binding: Option<ClientIpBinding>,
 }
 
 impl AuthInterceptor {
-    pub fn new(
-        verifying_key: Arc<PKeyWithDigest<Public>>,
-        expected_signing_algo: AlgorithmType,
-    ) -> Self {
+    pub fn new(keyring: KeyringHandle, ip_binding: Option<ClientIpBinding>) -> Self {
         Self {
-            verifying_key,
-            expected_signing_algo,
+            keyring,
+            ip_binding,
         }
     }
 
@@ -83,8 +124,8 @@ impl AuthInterceptor {
     /// 1. Finds the Authorization header in the request metadata
     /// 2. Validates the "Bearer " prefix format
     /// 3. Extracts the JWT token string
-    /// 4. Verifies the token signature using the configured public key
-    /// 5. Ensures the signing algorithm matches expectations
+    /// 4. Verifies the token signature using the keyring key named by its `kid` header,
+    ///    rejecting tokens whose `alg` header doesn't match that key's algorithm
     /// 
     /// # Arguments
     /// * `meta` - gRPC request metadata containing HTTP headers
@@ -95,10 +136,7 @@ impl AuthInterceptor {
     /// # Errors
     /// - `invalid_argument` if header format is malformed
     /// - `permission_denied` if token is missing, invalid, or verification fails
-    fn jwt_from_header(
-        &self,
-        meta: &MetadataMap,
-    ) -> Result<Token<Header, DeSerClaims, Verified>, Status> {
+    fn jwt_from_header(&self, meta: &MetadataMap) -> Result<VerifiedToken<DeSerClaims>, Status> {
         // Look for Authorization header in request metadata
         if let Some(auth_header) = meta.get(AUTHORIZATION_HEADER) {
             // Convert header value to string, handling potential encoding issues
@@ -120,20 +158,12 @@ impl AuthInterceptor {
                 return Err(Status::permission_denied("Missing jwt token."));
             }
 
-            // Verify the JWT token signature and decode claims
-            let jwt_token: Token<Header, DeSerClaims, Verified> =
-                VerifyWithKey::verify_with_key(split[1], self.verifying_key.as_ref()).map_err(
-                    |e| {
-                        warn!("error verifying token: {}", e);
-                        Status::permission_denied("Token failed verification.")
-                    },
-                )?;
-
-            // Verify the signing algorithm matches our security requirements
-            // This prevents algorithm confusion attacks
-            assert_eq!(jwt_token.header().algorithm, self.expected_signing_algo);
-
-            Ok(jwt_token)
+            // Verify the JWT token signature and algorithm, then decode claims
+            // Checking the algorithm against the key prevents algorithm confusion attacks
+            self.keyring.load().verify(split[1]).map_err(|e| {
+                warn!("error verifying token: {}", e);
+                Status::permission_denied("Token failed verification.")
+            })
         } else {
             // No Authorization header provided
             Err(Status::permission_denied(
@@ -150,8 +180,53 @@ impl Interceptor for AuthInterceptor {
     fn call(&mut self, mut req: Request<()>) -> Result<Request<()>, Status> {
         let jwt_token = self.jwt_from_header(req.metadata())?;
         let claims: Claims = jwt_token.claims().into();
+
+        if let Some(ip_binding) = &self.ip_binding {
+            let remote_ip = req.remote_addr().map(|addr| addr.ip());
+            if !remote_ip.is_some_and(|remote_ip| ip_binding.allows(claims.client_ip, remote_ip)) {
+                let remote_ip =
+                    remote_ip.map_or_else(|| "unknown".to_string(), |ip| ip.to_string());
+                warn!(
+                    "rejecting token for {} issued to {} used from {}",
+                    claims.client_pubkey, claims.client_ip, remote_ip
+                );
+                datapoint_error!(
+                    "auth_interceptor-client_ip_mismatch",
+                    ("client_pubkey", claims.client_pubkey.to_string(), String),
+                    ("token_ip", claims.client_ip.to_string(), String),
+                    ("remote_ip", remote_ip, String),
+                );
+                // unauthenticated rather than permission_denied: the client can authenticate
+                // again from its current address
+                return Err(Status::unauthenticated(
+                    "Token was issued to a different IP address.",
+                ));
+            }
+        }
+
         req.extensions_mut().insert(claims.client_pubkey);
 
         Ok(req)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::net::IpAddr;
+
+    use crate::auth_interceptor::ClientIpBinding;
+
+    #[test]
+    fn test_client_ip_binding() {
+        let ip = |ip: &str| ip.parse::<IpAddr>().unwrap();
+
+        let strict = ClientIpBinding::default();
+        assert!(strict.allows(ip("10.0.0.1"), ip("10.0.0.1")));
+        assert!(!strict.allows(ip("10.0.0.1"), ip("10.0.0.2")));
+
+        let nat_pool = ClientIpBinding::new(vec!["10.0.0.0/24".parse().unwrap()]);
+        assert!(nat_pool.allows(ip("10.0.0.1"), ip("10.0.0.2")));
+        assert!(!nat_pool.allows(ip("10.0.0.1"), ip("10.0.1.1")));
+        assert!(!nat_pool.allows(ip("192.168.0.1"), ip("10.0.0.2")));
+    }
+}
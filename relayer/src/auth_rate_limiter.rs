@@ -0,0 +1,231 @@
+//! Token bucket rate limits for the auth service.
+//!
+//! Issuing tokens means signing them, so a single host calling the auth service in a loop can
+//! burn a lot of CPU. Each RPC gets its own token buckets per client IP and per validator
+//! pubkey; a request is rejected with `resource_exhausted` when either bucket is empty.
+//! Buckets that have refilled completely are pruned, so idle clients don't use any memory.
+
+use std::{
+    hash::Hash,
+    net::IpAddr,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Instant,
+};
+
+use dashmap::DashMap;
+use solana_metrics::datapoint_info;
+use solana_sdk::pubkey::Pubkey;
+use tonic::Status;
+
+/// The rate limited auth service RPCs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AuthRpc {
+    GenerateAuthChallenge,
+    GenerateAuthTokens,
+    RefreshAccessToken,
+}
+
+impl AuthRpc {
+    const ALL: [AuthRpc; 3] = [
+        AuthRpc::GenerateAuthChallenge,
+        AuthRpc::GenerateAuthTokens,
+        AuthRpc::RefreshAccessToken,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuthRpc::GenerateAuthChallenge => "generate_auth_challenge",
+            AuthRpc::GenerateAuthTokens => "generate_auth_tokens",
+            AuthRpc::RefreshAccessToken => "refresh_access_token",
+        }
+    }
+}
+
+/// Sustained rate and burst size of a token bucket
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    pub requests_per_sec: f64,
+    pub burst: u32,
+}
+
+struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(limit: &RateLimit, now: Instant) -> TokenBucket {
+        TokenBucket {
+            tokens: limit.burst as f64,
+            last_refill: now,
+        }
+    }
+
+    fn refill(&mut self, limit: &RateLimit, now: Instant) {
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * limit.requests_per_sec).min(limit.burst as f64);
+        self.last_refill = now;
+    }
+
+    fn try_take(&mut self, limit: &RateLimit, now: Instant) -> bool {
+        self.refill(limit, now);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn is_full(&self, limit: &RateLimit, now: Instant) -> bool {
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.tokens + elapsed * limit.requests_per_sec >= limit.burst as f64
+    }
+}
+
+/// Token buckets for one kind of key, e.g. client IPs
+struct KeyedRateLimiter<K: Eq + Hash> {
+    limit: Option<RateLimit>,
+    buckets: DashMap<(AuthRpc, K), TokenBucket>,
+    /// Requests rejected since the last report, indexed like [`AuthRpc::ALL`]
+    num_rejected: [AtomicU64; 3],
+}
+
+impl<K: Eq + Hash> KeyedRateLimiter<K> {
+    fn new(limit: Option<RateLimit>) -> KeyedRateLimiter<K> {
+        KeyedRateLimiter {
+            limit,
+            buckets: DashMap::new(),
+            num_rejected: Default::default(),
+        }
+    }
+
+    fn try_acquire(&self, rpc: AuthRpc, key: K, now: Instant) -> bool {
+        let Some(limit) = &self.limit else {
+            return true;
+        };
+        let allowed = self
+            .buckets
+            .entry((rpc, key))
+            .or_insert_with(|| TokenBucket::new(limit, now))
+            .try_take(limit, now);
+        if !allowed {
+            self.num_rejected[rpc as usize].fetch_add(1, Ordering::Relaxed);
+        }
+        allowed
+    }
+
+    fn prune(&self, now: Instant) {
+        if let Some(limit) = &self.limit {
+            self.buckets.retain(|_, bucket| !bucket.is_full(limit, now));
+        }
+    }
+
+    fn take_num_rejected(&self, rpc: AuthRpc) -> u64 {
+        self.num_rejected[rpc as usize].swap(0, Ordering::Relaxed)
+    }
+}
+
+/// Per-IP and per-pubkey rate limits on the auth service RPCs
+pub struct AuthRateLimiter {
+    per_ip: KeyedRateLimiter<IpAddr>,
+    per_pubkey: KeyedRateLimiter<Pubkey>,
+}
+
+impl AuthRateLimiter {
+    /// Creates a rate limiter applying each limit to every RPC separately; a `None` limit
+    /// disables limiting by that key.
+    pub fn new(per_ip: Option<RateLimit>, per_pubkey: Option<RateLimit>) -> AuthRateLimiter {
+        AuthRateLimiter {
+            per_ip: KeyedRateLimiter::new(per_ip),
+            per_pubkey: KeyedRateLimiter::new(per_pubkey),
+        }
+    }
+
+    pub fn check_ip(&self, rpc: AuthRpc, client_ip: IpAddr) -> Result<(), Status> {
+        if self.per_ip.try_acquire(rpc, client_ip, Instant::now()) {
+            Ok(())
+        } else {
+            Err(Status::resource_exhausted(
+                "Too many requests from this IP address, try again later.",
+            ))
+        }
+    }
+
+    pub fn check_pubkey(&self, rpc: AuthRpc, pubkey: &Pubkey) -> Result<(), Status> {
+        if self.per_pubkey.try_acquire(rpc, *pubkey, Instant::now()) {
+            Ok(())
+        } else {
+            Err(Status::resource_exhausted(
+                "Too many requests for this pubkey, try again later.",
+            ))
+        }
+    }
+
+    /// Drops buckets that have refilled and reports the rejections since the last call
+    pub fn prune_and_report(&self) {
+        let now = Instant::now();
+        self.per_ip.prune(now);
+        self.per_pubkey.prune(now);
+
+        for rpc in AuthRpc::ALL {
+            datapoint_info!(
+                "auth_rate_limiter-stats",
+                "rpc" => rpc.as_str(),
+                ("ip_rejected", self.per_ip.take_num_rejected(rpc), i64),
+                ("pubkey_rejected", self.per_pubkey.take_num_rejected(rpc), i64),
+            );
+        }
+        datapoint_info!(
+            "auth_rate_limiter-buckets",
+            ("num_ip_buckets", self.per_ip.buckets.len(), i64),
+            ("num_pubkey_buckets", self.per_pubkey.buckets.len(), i64),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        net::{IpAddr, Ipv4Addr},
+        time::{Duration, Instant},
+    };
+
+    use crate::auth_rate_limiter::{AuthRpc, KeyedRateLimiter, RateLimit};
+
+    #[test]
+    fn test_keyed_rate_limiter() {
+        let limiter = KeyedRateLimiter::new(Some(RateLimit {
+            requests_per_sec: 2.0,
+            burst: 3,
+        }));
+        let ip = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        let now = Instant::now();
+
+        for _ in 0..3 {
+            assert!(limiter.try_acquire(AuthRpc::GenerateAuthTokens, ip, now));
+        }
+        assert!(!limiter.try_acquire(AuthRpc::GenerateAuthTokens, ip, now));
+        // each RPC has its own bucket
+        assert!(limiter.try_acquire(AuthRpc::RefreshAccessToken, ip, now));
+        assert_eq!(limiter.take_num_rejected(AuthRpc::GenerateAuthTokens), 1);
+
+        // half a second refills one token at 2 requests per second
+        let later = now + Duration::from_millis(500);
+        assert!(limiter.try_acquire(AuthRpc::GenerateAuthTokens, ip, later));
+        assert!(!limiter.try_acquire(AuthRpc::GenerateAuthTokens, ip, later));
+
+        // the refresh bucket has refilled by now
+        limiter.prune(later);
+        assert_eq!(limiter.buckets.len(), 1);
+        limiter.prune(later + Duration::from_secs(2));
+        assert!(limiter.buckets.is_empty());
+
+        let unlimited = KeyedRateLimiter::new(None);
+        assert!((0..100).all(|_| unlimited.try_acquire(AuthRpc::GenerateAuthTokens, ip, now)));
+    }
+}
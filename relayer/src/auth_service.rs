@@ -8,12 +8,23 @@
 //! 3. **Token Issuance**: Server verifies signature and issues JWT access/refresh tokens
 //! 4. **Token Refresh**: Long-lived refresh tokens can generate new access tokens
 //! 
+//! ## Refresh Token Rotation
+//! When enabled, each refresh returns a new refresh token in the `x-refresh-token` response
+//! metadata and retires the one presented. Reusing a retired refresh token revokes every token
+//! descended from the same authentication, see [`crate::refresh_tokens`].
+//! 
 //! ## Security Features
-//! - **DOS Protection**: One challenge per IP address prevents flooding attacks
+//! - **DOS Protection**: A per-IP cap on outstanding challenges prevents flooding attacks
+//! - **Rate Limiting**: Token buckets per client IP and per pubkey on every RPC
 //! - **Signature Verification**: Cryptographic proof of validator identity
 //! - **Token Binding**: Tokens tied to specific IP addresses and validator pubkeys
 //! - **Automatic Expiration**: Challenges and tokens expire to limit exposure
-//! - **Authorization Control**: Only whitelisted validators can authenticate
+//! - **Authorization Control**: Only whitelisted validators can authenticate or refresh, and
+//!   validators that stop being authorized lose their subscription and refresh tokens
+//! 
+//! ## Auditing
+//! Issued challenges and tokens, refreshes and every rejected request with its reason are
+//! recorded in the [`AuthEventLog`], and issued tokens start a session in the [`SessionTable`].
 //! 
 //! ## Token Types
 //! - **Access Tokens**: Short-lived (typically minutes), used for API authentication
@@ -37,21 +48,32 @@ use jito_protos::auth::{
     GenerateAuthTokensRequest, GenerateAuthTokensResponse, RefreshAccessTokenRequest,
     RefreshAccessTokenResponse, Role, Token as PbToken,
 };
-use jwt::{AlgorithmType, Header, PKeyWithDigest, SignWithKey, Token, VerifyWithKey};
 use log::*;
-use openssl::pkey::{Private, Public};
 use prost_types::Timestamp;
 use rand::{distributions::Alphanumeric, Rng};
+use solana_metrics::{datapoint_error, datapoint_info};
 use solana_sdk::pubkey::Pubkey;
 use tokio::{task::JoinHandle, time::interval};
-use tonic::{Request, Response, Status};
+use tonic::{metadata::MetadataValue, Request, Response, Status};
 
 use crate::{
-    auth_challenges::{AuthChallenge, AuthChallenges},
+    auth_challenges::{AuthChallenge, AuthChallenges, TooManyChallenges},
+    auth_events::{AuthEvent, AuthEventLog, AuthFailureReason},
     auth_interceptor::{Claims, DeSerClaims},
+    auth_rate_limiter::{AuthRateLimiter, AuthRpc},
     health_manager::HealthState,
+    keyring::KeyringHandle,
+    refresh_tokens::{RefreshTokenError, RefreshTokenGrant, RefreshTokenStore},
+    relayer::RelayerHandle,
+    sessions::SessionTable,
 };
 
+/// Response metadata key holding the rotated refresh token returned by RefreshAccessToken
+pub const REFRESH_TOKEN_METADATA_KEY: &str = "x-refresh-token";
+
+/// Response metadata key holding the rotated refresh token's expiration as a unix timestamp
+pub const REFRESH_TOKEN_EXPIRES_AT_METADATA_KEY: &str = "x-refresh-token-expires-at";
+
 /// Trait for validator authorization control.
 /// 
 /// Implementations determine which validator public keys are allowed to authenticate.
@@ -78,27 +100,28 @@ pub trait ValidatorAuther: Send + Sync + 'static {
 /// * `V` - Validator authorization implementation (e.g., whitelist, stake-based)
 pub struct AuthServiceImpl<V: ValidatorAuther> {
     /// Authorization policy for determining which validators can authenticate
-    validator_auther: V,
+    validator_auther: Arc<V>,
 
     /// Background task handle for periodic challenge cleanup
     _t_hdl: JoinHandle<()>,
 
-    /// Active authentication challenges indexed by IP address.
+    /// Background task handle for pruning rate limiter buckets and reporting rejections
+    _rate_limiter_t_hdl: JoinHandle<()>,
+
+    /// Background task handle for dropping subscriptions of validators no longer authorized
+    _authorization_t_hdl: JoinHandle<()>,
+
+    /// Active authentication challenges indexed by IP address and pubkey.
     /// 
     /// Uses a priority queue to efficiently expire old challenges and prevent DOS attacks:
-    /// - One challenge per IP address limits attack surface
+    /// - A cap on challenges per IP address limits attack surface
     /// - Priority ordering by expiration time enables efficient cleanup
     /// - Reverse ordering ensures oldest challenges are removed first
     auth_challenges: AuthChallenges,
 
-    /// RSA private key for signing JWT tokens.
-    /// Used to create cryptographically secure access and refresh tokens.
-    signing_key: PKeyWithDigest<Private>,
-    
-    /// RSA public key for token verification.
-    /// Shared with all services that need to validate JWT tokens.
-    /// Must correspond to the signing_key for proper token validation.
-    verifying_key: Arc<PKeyWithDigest<Public>>,
+    /// Keys for signing JWT tokens and verifying refresh tokens.
+    /// Shared with the interceptor that validates access tokens.
+    keyring: KeyringHandle,
 
     /// Time-to-live for access tokens (typically short, e.g., 15 minutes).
     /// Short TTL limits exposure if tokens are compromised.
@@ -112,28 +135,59 @@ pub struct AuthServiceImpl<V: ValidatorAuther> {
     /// Short TTL prevents challenge accumulation and DOS attacks.
     challenge_ttl: Duration,
 
+    /// Refresh token families, used to detect reuse and revoke refresh tokens.
+    refresh_tokens: RefreshTokenStore,
+
+    /// Whether new token families rotate, each refresh issuing a new refresh token and retiring
+    /// the one presented.
+    rotate_refresh_tokens: bool,
+
+    /// Per-IP and per-pubkey request limits on each RPC.
+    rate_limiter: Arc<AuthRateLimiter>,
+
+    /// Audit log of issued challenges and rejected requests.
+    auth_events: AuthEventLog,
+
+    /// Validator sessions, started when tokens are issued and updated on refresh.
+    sessions: SessionTable,
+
+    /// Used to drop the subscriptions of validators that are no longer authorized.
+    relayer_handle: RelayerHandle,
+
     /// Shared health state - authentication is disabled when relayer is unhealthy
     health_state: Arc<RwLock<HealthState>>,
 }
 
 /// Maximum number of concurrent authentication challenges allowed.
 /// 
-/// This limit prevents DOS attacks through challenge flooding. With one challenge
-/// per IP address, an attacker would need 100,000 unique IP addresses to exhaust
+/// This limit prevents DOS attacks through challenge flooding. With challenges capped
+/// per IP address, an attacker would need thousands of unique IP addresses to exhaust
 /// this capacity, making such attacks prohibitively expensive.
 const AUTH_CHALLENGES_CAPACITY: usize = 100_000;
 
+/// How often idle rate limiter buckets are pruned and rejections reported.
+const RATE_LIMITER_REPORT_INTERVAL: StdDuration = StdDuration::from_secs(10);
+
+/// How often subscribed validators are checked against the authorization policy.
+const AUTHORIZATION_CHECK_INTERVAL: StdDuration = StdDuration::from_secs(10);
+
 impl<V: ValidatorAuther> AuthServiceImpl<V> {
     /// Creates a new authentication service with the specified configuration.
     /// 
     /// # Arguments
     /// * `validator_auther` - Authorization policy for validator access control
-    /// * `signing_key` - RSA private key for signing JWT tokens
-    /// * `verifying_key` - RSA public key for token verification (shared with other services)
+    /// * `keyring` - Keys for signing and verifying tokens (shared with the interceptor)
     /// * `access_token_ttl` - Lifetime for access tokens (short-lived)
     /// * `refresh_token_ttl` - Lifetime for refresh tokens (longer-lived)
     /// * `challenge_ttl` - Lifetime for authentication challenges (very short)
     /// * `challenge_expiration_sleep_interval` - How often to clean up expired challenges
+    /// * `max_challenges_per_ip` - How many validators can authenticate from one IP at once
+    /// * `refresh_tokens` - Refresh token families, shared with the admin service for revocation
+    /// * `rotate_refresh_tokens` - Whether refresh tokens issued from now on rotate on refresh
+    /// * `rate_limiter` - Per-IP and per-pubkey request limits
+    /// * `auth_events` - Audit log for issued challenges and rejected requests
+    /// * `sessions` - Session table, shared with the relayer and the web server
+    /// * `relayer_handle` - Drops the subscriptions of validators that are no longer authorized
     /// * `exit` - Shutdown signal for graceful termination
     /// * `health_state` - Shared health status (auth disabled when unhealthy)
     /// 
@@ -142,17 +196,23 @@ impl<V: ValidatorAuther> AuthServiceImpl<V> {
     #[allow(clippy::too_many_arguments)]
     pub fn new(
         validator_auther: V,
-        signing_key: PKeyWithDigest<Private>,
-        verifying_key: Arc<PKeyWithDigest<Public>>,
+        keyring: KeyringHandle,
         access_token_ttl: StdDuration,
         refresh_token_ttl: StdDuration,
         challenge_ttl: StdDuration,
         challenge_expiration_sleep_interval: StdDuration,
+        max_challenges_per_ip: usize,
+        refresh_tokens: RefreshTokenStore,
+        rotate_refresh_tokens: bool,
+        rate_limiter: AuthRateLimiter,
+        auth_events: AuthEventLog,
+        sessions: SessionTable,
+        relayer_handle: RelayerHandle,
         exit: &Arc<AtomicBool>,
         health_state: Arc<RwLock<HealthState>>,
     ) -> Self {
         // Initialize empty challenge storage
-        let auth_challenges = AuthChallenges::default();
+        let auth_challenges = AuthChallenges::new(max_challenges_per_ip);
         
         // Start background task to periodically clean up expired challenges
         let _t_hdl = Self::start_challenge_expiration_task(
@@ -160,21 +220,97 @@ impl<V: ValidatorAuther> AuthServiceImpl<V> {
             challenge_expiration_sleep_interval,
             exit,
         );
+        let rate_limiter = Arc::new(rate_limiter);
+        let _rate_limiter_t_hdl = Self::start_rate_limiter_report_task(rate_limiter.clone(), exit);
+        let validator_auther = Arc::new(validator_auther);
+        let _authorization_t_hdl = Self::start_authorization_check_task(
+            validator_auther.clone(),
+            relayer_handle.clone(),
+            refresh_tokens.clone(),
+            exit,
+        );
 
         Self {
             auth_challenges,
             validator_auther,
-            signing_key,
-            verifying_key,
+            keyring,
             _t_hdl,
+            _rate_limiter_t_hdl,
+            _authorization_t_hdl,
             // Convert standard durations to chrono durations for timestamp arithmetic
             access_token_ttl: Duration::from_std(access_token_ttl).unwrap(),
             refresh_token_ttl: Duration::from_std(refresh_token_ttl).unwrap(),
             challenge_ttl: Duration::from_std(challenge_ttl).unwrap(),
+            refresh_tokens,
+            rotate_refresh_tokens,
+            rate_limiter,
+            auth_events,
+            sessions,
+            relayer_handle,
             health_state,
         }
     }
 
+    /// Starts a background task that periodically checks subscribed validators against the
+    /// authorization policy, so a validator that's denied after subscribing, e.g. by a denylist
+    /// reload, doesn't keep receiving packets.
+    fn start_authorization_check_task(
+        validator_auther: Arc<V>,
+        relayer_handle: RelayerHandle,
+        refresh_tokens: RefreshTokenStore,
+        exit: &Arc<AtomicBool>,
+    ) -> JoinHandle<()> {
+        let exit = exit.clone();
+        tokio::task::spawn(async move {
+            let mut interval = interval(AUTHORIZATION_CHECK_INTERVAL);
+            while !exit.load(Ordering::Relaxed) {
+                let _ = interval.tick().await;
+                for pubkey in relayer_handle.connected_validators() {
+                    if !validator_auther.is_authorized(&pubkey) {
+                        Self::drop_unauthorized(&relayer_handle, &refresh_tokens, &pubkey);
+                    }
+                }
+            }
+        })
+    }
+
+    /// Drops the subscription of a validator that's no longer authorized and revokes its refresh
+    /// tokens, so it has to be authorized again to get new ones.
+    fn drop_unauthorized(
+        relayer_handle: &RelayerHandle,
+        refresh_tokens: &RefreshTokenStore,
+        pubkey: &Pubkey,
+    ) {
+        let was_connected = relayer_handle.drop_unauthorized_validator(pubkey);
+        let num_revoked = refresh_tokens.revoke_validator(pubkey);
+        warn!(
+            "validator {pubkey} is no longer authorized, dropped its subscription: \
+             {was_connected}, revoked {num_revoked} refresh token families"
+        );
+        datapoint_info!(
+            "auth_service-validator_unauthorized",
+            ("pubkey", pubkey.to_string(), String),
+            ("was_connected", was_connected, bool),
+            ("num_revoked", num_revoked, i64),
+        );
+    }
+
+    /// Starts a background task that periodically drops refilled rate limiter buckets, bounding
+    /// their memory to the clients active in the last interval, and reports rejections.
+    fn start_rate_limiter_report_task(
+        rate_limiter: Arc<AuthRateLimiter>,
+        exit: &Arc<AtomicBool>,
+    ) -> JoinHandle<()> {
+        let exit = exit.clone();
+        tokio::task::spawn(async move {
+            let mut interval = interval(RATE_LIMITER_REPORT_INTERVAL);
+            while !exit.load(Ordering::Relaxed) {
+                let _ = interval.tick().await;
+                rate_limiter.prune_and_report();
+            }
+        })
+    }
+
     /// Starts a background task to periodically clean up expired authentication challenges.
     /// 
     /// This prevents memory leaks and DOS attacks by removing challenges that have passed
@@ -206,7 +342,7 @@ impl<V: ValidatorAuther> AuthServiceImpl<V> {
 
     /// Extracts the client's IP address from a gRPC request.
     /// 
-    /// The IP address is used for DOS protection (one challenge per IP) and token binding.
+    /// The IP address is used for DOS protection (a cap on challenges per IP) and token binding.
     /// 
     /// # Security Note
     /// If this service is behind a proxy, the remote_addr will be the proxy's IP,
@@ -260,6 +396,35 @@ impl<V: ValidatorAuther> AuthServiceImpl<V> {
             Ok(())
         }
     }
+
+    /// Signs the claims into a JWT with the active key, `token_name` naming the token in errors
+    fn sign_token(&self, claims: Claims, token_name: &str) -> Result<String, Status> {
+        self.keyring
+            .load()
+            .sign(DeSerClaims::from(claims))
+            .map_err(|e| {
+                error!("Error signing {token_name} claims: {e}");
+                Status::internal(format!("Error signing {token_name}."))
+            })
+    }
+
+    /// Records the rejected request in the audit log, returning the status to reply with
+    fn reject(
+        &self,
+        rpc: AuthRpc,
+        client_ip: IpAddr,
+        pubkey: Option<&Pubkey>,
+        reason: AuthFailureReason,
+        status: Status,
+    ) -> Status {
+        self.auth_events.record(AuthEvent::AuthFailed {
+            rpc: rpc.as_str(),
+            reason,
+            client_ip,
+            pubkey: pubkey.map(|pubkey| pubkey.to_string()),
+        });
+        status
+    }
 }
 
 #[tonic::async_trait]
@@ -268,63 +433,118 @@ impl<V: ValidatorAuther> AuthService for AuthServiceImpl<V> {
         &self,
         req: Request<GenerateAuthChallengeRequest>,
     ) -> Result<Response<GenerateAuthChallengeResponse>, Status> {
-        Self::check_health(&self.health_state)?;
         let auth_challenges = &self.auth_challenges;
 
-        if auth_challenges.len().await >= AUTH_CHALLENGES_CAPACITY {
-            return Err(Status::resource_exhausted("System overloaded."));
-        }
-
         let client_ip = Self::client_ip(&req)?;
-        if let Some(auth_challenge) = auth_challenges.get_priority(&client_ip).await {
-            if !auth_challenge.0.is_expired() {
-                return Ok(Response::new(GenerateAuthChallengeResponse {
-                    challenge: auth_challenge.0.challenge,
-                }));
-            }
+        let reject = |pubkey: Option<&Pubkey>, reason, status| {
+            self.reject(
+                AuthRpc::GenerateAuthChallenge,
+                client_ip,
+                pubkey,
+                reason,
+                status,
+            )
+        };
+        Self::check_health(&self.health_state)
+            .map_err(|status| reject(None, AuthFailureReason::RelayerUnhealthy, status))?;
+        self.rate_limiter
+            .check_ip(AuthRpc::GenerateAuthChallenge, client_ip)
+            .map_err(|status| reject(None, AuthFailureReason::RateLimited, status))?;
+
+        if auth_challenges.len().await >= AUTH_CHALLENGES_CAPACITY {
+            return Err(reject(
+                None,
+                AuthFailureReason::Overloaded,
+                Status::resource_exhausted("System overloaded."),
+            ));
         }
 
         let inner_req = req.into_inner();
 
         if inner_req.role != Role::Validator as i32 {
-            return Err(Status::invalid_argument("Role must be validator."));
+            return Err(reject(
+                None,
+                AuthFailureReason::InvalidRole,
+                Status::invalid_argument("Role must be validator."),
+            ));
         }
 
         if inner_req.pubkey.len() != solana_sdk::pubkey::PUBKEY_BYTES {
-            return Err(Status::invalid_argument(
-                "Pubkey must be 32 bytes in length",
+            return Err(reject(
+                None,
+                AuthFailureReason::InvalidPubkey,
+                Status::invalid_argument("Pubkey must be 32 bytes in length"),
             ));
         }
 
-        let pubkey = Pubkey::try_from(inner_req.pubkey)
-            .map_err(|_| Status::invalid_argument("Invalid pubkey supplied."))?;
+        let pubkey = Pubkey::try_from(inner_req.pubkey).map_err(|_| {
+            reject(
+                None,
+                AuthFailureReason::InvalidPubkey,
+                Status::invalid_argument("Invalid pubkey supplied."),
+            )
+        })?;
+        self.rate_limiter
+            .check_pubkey(AuthRpc::GenerateAuthChallenge, &pubkey)
+            .map_err(|status| reject(Some(&pubkey), AuthFailureReason::RateLimited, status))?;
+
+        if let Some(auth_challenge) = auth_challenges.get_priority(&(client_ip, pubkey)).await {
+            if !auth_challenge.0.is_expired() {
+                return Ok(Response::new(GenerateAuthChallengeResponse {
+                    challenge: auth_challenge.0.challenge,
+                }));
+            }
+        }
 
         if !self.validator_auther.is_authorized(&pubkey) {
-            return Err(Status::permission_denied(
-                "The supplied pubkey is not authorized to generate a challenge.",
+            return Err(reject(
+                Some(&pubkey),
+                AuthFailureReason::Unauthorized,
+                Status::permission_denied(
+                    "The supplied pubkey is not authorized to generate a challenge.",
+                ),
             ));
         }
 
         let challenge = Self::generate_challenge_token();
         auth_challenges
             .push(
-                client_ip,
+                (client_ip, pubkey),
                 Reverse(AuthChallenge {
                     challenge: challenge.clone(),
+                    // token ids are assigned once the challenge is answered
                     access_claims: Claims {
                         client_ip,
                         client_pubkey: pubkey,
                         expires_at_utc: Utc::now().add(self.access_token_ttl).naive_utc(),
+                        jti: 0,
+                        family: 0,
                     },
                     refresh_claims: Claims {
                         client_ip,
                         client_pubkey: pubkey,
                         expires_at_utc: Utc::now().add(self.refresh_token_ttl).naive_utc(),
+                        jti: 0,
+                        family: 0,
                     },
                     expires_at_utc: Utc::now().add(self.challenge_ttl).naive_utc(),
                 }),
             )
-            .await;
+            .await
+            .map_err(|TooManyChallenges| {
+                reject(
+                    Some(&pubkey),
+                    AuthFailureReason::TooManyChallenges,
+                    Status::resource_exhausted(
+                        "Too many validators are authenticating from this IP address, try again later.",
+                    ),
+                )
+            })?;
+
+        self.auth_events.record(AuthEvent::ChallengeIssued {
+            pubkey: pubkey.to_string(),
+            client_ip,
+        });
 
         Ok(Response::new(GenerateAuthChallengeResponse { challenge }))
     }
@@ -333,56 +553,93 @@ impl<V: ValidatorAuther> AuthService for AuthServiceImpl<V> {
         &self,
         req: Request<GenerateAuthTokensRequest>,
     ) -> Result<Response<GenerateAuthTokensResponse>, Status> {
-        Self::check_health(&self.health_state)?;
         let auth_challenges = &self.auth_challenges;
 
         let client_ip = Self::client_ip(&req)?;
+        let reject = |pubkey: Option<&Pubkey>, reason, status| {
+            self.reject(
+                AuthRpc::GenerateAuthTokens,
+                client_ip,
+                pubkey,
+                reason,
+                status,
+            )
+        };
+        Self::check_health(&self.health_state)
+            .map_err(|status| reject(None, AuthFailureReason::RelayerUnhealthy, status))?;
+        self.rate_limiter
+            .check_ip(AuthRpc::GenerateAuthTokens, client_ip)
+            .map_err(|status| reject(None, AuthFailureReason::RateLimited, status))?;
         let inner_req = req.into_inner();
 
         let client_pubkey = PublicKey::from_bytes(&inner_req.client_pubkey).map_err(|e| {
             warn!("Failed to create pubkey from string: {}", e);
-            Status::invalid_argument("Invalid pubkey supplied.")
+            reject(
+                None,
+                AuthFailureReason::InvalidPubkey,
+                Status::invalid_argument("Invalid pubkey supplied."),
+            )
         })?;
-        let solana_pubkey = Pubkey::try_from(client_pubkey.to_bytes())
-            .map_err(|_| Status::invalid_argument("Invalid pubkey supplied."))?;
-
-        let auth_challenge = if let Some(challenge) = auth_challenges.get_priority(&client_ip).await
+        let solana_pubkey = Pubkey::try_from(client_pubkey.to_bytes()).map_err(|_| {
+            reject(
+                None,
+                AuthFailureReason::InvalidPubkey,
+                Status::invalid_argument("Invalid pubkey supplied."),
+            )
+        })?;
+        let reject = |reason, status| reject(Some(&solana_pubkey), reason, status);
+        self.rate_limiter
+            .check_pubkey(AuthRpc::GenerateAuthTokens, &solana_pubkey)
+            .map_err(|status| reject(AuthFailureReason::RateLimited, status))?;
+
+        // challenges are keyed by pubkey, so this is the challenge requested for this pubkey
+        let challenge_key = (client_ip, solana_pubkey);
+        let auth_challenge = if let Some(challenge) =
+            auth_challenges.get_priority(&challenge_key).await
         {
             Ok(challenge)
         } else {
-            Err(Status::permission_denied(
-                "Must invoke the GenerateAuthChallenge method before calling any method.",
+            Err(reject(
+                AuthFailureReason::MissingChallenge,
+                Status::permission_denied(
+                    "Must invoke the GenerateAuthChallenge method for this pubkey before calling any method.",
+                ),
             ))
         }?;
 
-        // check the client passed in public key against the originally requested public key
-        if auth_challenge.0.access_claims.client_pubkey != solana_pubkey {
-            return Err(Status::permission_denied(
-                "The pubkey provided does not match the pubkey that generated the challenge.",
-            ));
-        }
-
         // Prepended with the pubkey to invalidate any tx this server could maliciously send.
         let expected_challenge = format!("{}-{}", solana_pubkey, auth_challenge.0.challenge);
         if expected_challenge != inner_req.challenge {
-            return Err(Status::invalid_argument(format!(
-                "The provided challenge does not match the expected challenge: {expected_challenge}"
-            )));
+            return Err(reject(
+                AuthFailureReason::ChallengeMismatch,
+                Status::invalid_argument(format!(
+                    "The provided challenge does not match the expected challenge: {expected_challenge}"
+                )),
+            ));
         }
 
         if inner_req.signed_challenge.len() != solana_sdk::signature::SIGNATURE_BYTES {
-            return Err(Status::invalid_argument("Signature must be 64 bytes."));
+            return Err(reject(
+                AuthFailureReason::InvalidSignature,
+                Status::invalid_argument("Signature must be 64 bytes."),
+            ));
         }
         let signed_challenge = {
             let sig_bytes =
                 &<[u8; 64]>::try_from(&inner_req.signed_challenge[..]).map_err(|e| {
                     error!("Invalid signature 1: {}", e);
-                    Status::invalid_argument("Invalid signature.")
+                    reject(
+                        AuthFailureReason::InvalidSignature,
+                        Status::invalid_argument("Invalid signature."),
+                    )
                 })?;
 
             Signature::from_bytes(sig_bytes).map_err(|e| {
                 error!("Invalid signature 2: {}", e);
-                Status::invalid_argument("Invalid signature.")
+                reject(
+                    AuthFailureReason::InvalidSignature,
+                    Status::invalid_argument("Invalid signature."),
+                )
             })?
         };
 
@@ -390,45 +647,50 @@ impl<V: ValidatorAuther> AuthService for AuthServiceImpl<V> {
             .verify(inner_req.challenge.as_bytes(), &signed_challenge)
             .map_err(|e| {
                 warn!("Challenge verification failed: {}", e);
-                Status::invalid_argument("Failed challenge verification. Did you sign with the supplied pubkey's corresponding private key?")
+                reject(
+                    AuthFailureReason::InvalidSignature,
+                    Status::invalid_argument("Failed challenge verification. Did you sign with the supplied pubkey's corresponding private key?"),
+                )
             })?;
 
-        let access_token = {
-            let header = Header {
-                algorithm: AlgorithmType::Rs256,
-                ..Default::default()
-            };
-            let claims: DeSerClaims = auth_challenge.0.access_claims.into();
-            Token::new(header, claims)
-                .sign_with_key(&self.signing_key)
-                .map_err(|e| {
-                    error!("Error signing access_token claims: {}", e);
-                    Status::internal("Error signing access_token.")
-                })
-        }?
-        .as_str()
-        .to_string();
-
-        let refresh_token = {
-            let header = Header {
-                algorithm: AlgorithmType::Rs256,
-                ..Default::default()
-            };
-            let claims: DeSerClaims = auth_challenge.0.refresh_claims.into();
-            Token::new(header, claims)
-                .sign_with_key(&self.signing_key)
-                .map_err(|e| {
-                    error!("Error signing refresh_token claims: {}", e);
-                    Status::internal("Error signing refresh_token.")
-                })
-        }?
-        .as_str()
-        .to_string();
+        // every authentication starts a new refresh token family
+        let refresh_expiry = auth_challenge.0.refresh_claims.expires_at_utc;
+        let (family, refresh_jti) = self.refresh_tokens.start_family(
+            &solana_pubkey,
+            refresh_expiry.and_utc().timestamp(),
+            self.rotate_refresh_tokens,
+        );
+
+        let access_token = self
+            .sign_token(
+                Claims {
+                    jti: RefreshTokenStore::new_token_id(),
+                    family,
+                    ..auth_challenge.0.access_claims
+                },
+                "access_token",
+            )
+            .map_err(|status| reject(AuthFailureReason::SigningError, status))?;
+        let refresh_token = self
+            .sign_token(
+                Claims {
+                    jti: refresh_jti,
+                    family,
+                    ..auth_challenge.0.refresh_claims
+                },
+                "refresh_token",
+            )
+            .map_err(|status| reject(AuthFailureReason::SigningError, status))?;
 
         let access_expiry = auth_challenge.0.access_claims.expires_at_utc;
-        let refresh_expiry = auth_challenge.0.refresh_claims.expires_at_utc;
 
-        auth_challenges.remove(&client_ip).await;
+        auth_challenges.remove(&challenge_key).await;
+        self.sessions.tokens_issued(
+            &solana_pubkey,
+            client_ip,
+            access_expiry.and_utc().timestamp(),
+            refresh_expiry.and_utc().timestamp(),
+        );
 
         Ok(Response::new(GenerateAuthTokensResponse {
             access_token: Some(PbToken {
@@ -452,46 +714,110 @@ impl<V: ValidatorAuther> AuthService for AuthServiceImpl<V> {
         &self,
         req: Request<RefreshAccessTokenRequest>,
     ) -> Result<Response<RefreshAccessTokenResponse>, Status> {
-        Self::check_health(&self.health_state)?;
-
+        let client_ip = Self::client_ip(&req)?;
+        let reject = |pubkey: Option<&Pubkey>, reason, status| {
+            self.reject(
+                AuthRpc::RefreshAccessToken,
+                client_ip,
+                pubkey,
+                reason,
+                status,
+            )
+        };
+        Self::check_health(&self.health_state)
+            .map_err(|status| reject(None, AuthFailureReason::RelayerUnhealthy, status))?;
+        self.rate_limiter
+            .check_ip(AuthRpc::RefreshAccessToken, client_ip)
+            .map_err(|status| reject(None, AuthFailureReason::RateLimited, status))?;
         let inner_req = req.into_inner();
 
         let refresh_token: &str = inner_req.refresh_token.as_str();
-        let refresh_claims: DeSerClaims = refresh_token
-            .verify_with_key(self.verifying_key.as_ref())
+        let refresh_token = self
+            .keyring
+            .load()
+            .verify::<DeSerClaims>(refresh_token)
             .map_err(|e| {
                 warn!("refresh_token failed to verify: {}", e);
-                Status::permission_denied("Invalid refresh_token supplied")
+                reject(
+                    None,
+                    AuthFailureReason::InvalidRefreshToken,
+                    Status::permission_denied("Invalid refresh_token supplied"),
+                )
             })?;
-        let refresh_claims: Claims = (&refresh_claims).into();
+        let refresh_claims: Claims = refresh_token.claims().into();
+        let reject = |reason, status| reject(Some(&refresh_claims.client_pubkey), reason, status);
+        // checked once the token is verified, before the more expensive signing
+        self.rate_limiter
+            .check_pubkey(AuthRpc::RefreshAccessToken, &refresh_claims.client_pubkey)
+            .map_err(|status| reject(AuthFailureReason::RateLimited, status))?;
 
         if refresh_claims.is_expired() {
-            return Err(Status::permission_denied("Client refresh_token has expired, please generate a new auth challenge to obtain a set of new access tokens."));
+            return Err(reject(
+                AuthFailureReason::RefreshTokenExpired,
+                Status::permission_denied("Client refresh_token has expired, please generate a new auth challenge to obtain a set of new access tokens."),
+            ));
         }
 
-        let expires_at_utc = Utc::now().add(self.access_token_ttl).naive_utc();
-        let access_claims: DeSerClaims = Claims {
-            client_ip: refresh_claims.client_ip,
-            client_pubkey: refresh_claims.client_pubkey,
-            expires_at_utc,
+        // the policy can change after the tokens were issued, e.g. the validator was denylisted
+        if !self
+            .validator_auther
+            .is_authorized(&refresh_claims.client_pubkey)
+        {
+            Self::drop_unauthorized(
+                &self.relayer_handle,
+                &self.refresh_tokens,
+                &refresh_claims.client_pubkey,
+            );
+            return Err(reject(
+                AuthFailureReason::Unauthorized,
+                Status::permission_denied("The supplied pubkey is no longer authorized."),
+            ));
+        }
+
+        let mut result = self
+            .refresh_tokens
+            .use_token(refresh_claims.family, refresh_claims.jti);
+        if result == Err(RefreshTokenError::UnknownFamily) && !self.rotate_refresh_tokens {
+            // tokens issued before families were tracked, or before a restart without a
+            // persisted store, have no family here; without rotation there's nothing to check
+            // them against, so they're accepted like before
+            result = Ok(RefreshTokenGrant {
+                jti: refresh_claims.jti,
+                expires_at_unix_ts: refresh_claims.expires_at_utc.and_utc().timestamp(),
+                rotated: false,
+            });
         }
-        .into();
-        let access_token = {
-            let header = Header {
-                algorithm: AlgorithmType::Rs256,
-                ..Default::default()
-            };
-            Token::new(header, access_claims)
-                .sign_with_key(&self.signing_key)
-                .map_err(|e| {
-                    error!("Error signing access_token claims: {}", e);
-                    Status::internal("Error signing access_token.")
-                })
-        }?
-        .as_str()
-        .to_string();
-
-        Ok(Response::new(RefreshAccessTokenResponse {
+        let grant = result.map_err(|e| {
+            if e == RefreshTokenError::Reused {
+                warn!(
+                    "refresh_token reused by {}, revoked its token family",
+                    refresh_claims.client_pubkey
+                );
+                datapoint_error!(
+                    "auth_service-refresh_token_reuse",
+                    ("pubkey", refresh_claims.client_pubkey.to_string(), String),
+                    ("client_ip", refresh_claims.client_ip.to_string(), String),
+                );
+            }
+            reject(
+                AuthFailureReason::from(&e),
+                Status::permission_denied(format!("Invalid refresh_token supplied: {e}, please generate a new auth challenge to obtain a set of new access tokens.")),
+            )
+        })?;
+
+        let expires_at_utc = Utc::now().add(self.access_token_ttl).naive_utc();
+        let access_token = self
+            .sign_token(
+                Claims {
+                    expires_at_utc,
+                    jti: RefreshTokenStore::new_token_id(),
+                    ..refresh_claims
+                },
+                "access_token",
+            )
+            .map_err(|status| reject(AuthFailureReason::SigningError, status))?;
+
+        let mut response = Response::new(RefreshAccessTokenResponse {
             access_token: Some(PbToken {
                 value: access_token,
                 expires_at_utc: Some(Timestamp {
@@ -499,6 +825,39 @@ impl<V: ValidatorAuther> AuthService for AuthServiceImpl<V> {
                     nanos: 0,
                 }),
             }),
-        }))
+        });
+
+        // the response message has no field for it, so the rotated token goes in metadata
+        if grant.rotated {
+            let refresh_token = self
+                .sign_token(
+                    Claims {
+                        jti: grant.jti,
+                        ..refresh_claims
+                    },
+                    "refresh_token",
+                )
+                .map_err(|status| reject(AuthFailureReason::SigningError, status))?;
+            let metadata = response.metadata_mut();
+            metadata.insert(
+                REFRESH_TOKEN_METADATA_KEY,
+                MetadataValue::try_from(refresh_token.as_str())
+                    .map_err(|_| Status::internal("Error encoding refresh_token."))?,
+            );
+            metadata.insert(
+                REFRESH_TOKEN_EXPIRES_AT_METADATA_KEY,
+                MetadataValue::from(grant.expires_at_unix_ts),
+            );
+        }
+
+        self.sessions.token_refreshed(
+            &refresh_claims.client_pubkey,
+            client_ip,
+            expires_at_utc.and_utc().timestamp(),
+            grant.expires_at_unix_ts,
+            grant.rotated,
+        );
+
+        Ok(response)
     }
 }
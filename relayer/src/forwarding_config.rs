@@ -0,0 +1,283 @@
+//! Forwarding parameters that can be changed on a running relayer.
+//!
+//! The packet delay, validator batch size, forward-all mode, slot lookahead, previous leader
+//! grace period and subscriber backpressure policy are read by the forwarding threads on every
+//! iteration from shared atomics, so an operator can retune them during a busy period without a
+//! restart dropping every validator subscription.
+//! Individual validators can override them with a [`ValidatorPolicy`].
+
+use std::sync::{
+    atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicU8, AtomicUsize, Ordering},
+    Mutex,
+};
+
+use log::info;
+use serde::{Deserialize, Serialize};
+use solana_metrics::datapoint_info;
+use thiserror::Error;
+
+use crate::{
+    subscriber_queue::Backpressure,
+    validator_policy::{ValidatorPolicies, ValidatorPolicy},
+};
+
+/// Point-in-time values of the forwarding parameters
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ForwardingParams {
+    /// How long packets are held before being forwarded to validators
+    pub packet_delay_ms: u32,
+    /// Number of packets per batch sent to validators
+    pub validator_packet_batch_size: usize,
+    /// Forward to every connected validator instead of only upcoming leaders
+    pub forward_all: bool,
+    /// Number of upcoming slots whose leaders receive packets
+    pub slot_lookahead: u64,
+    /// How long the previous leader keeps receiving packets after its slots end, covering
+    /// packets that race the slot boundary
+    pub previous_leader_grace_ms: u32,
+    /// What happens to packets sent to a validator whose queue is full
+    pub backpressure: Backpressure,
+    /// How long a validator's queue can stay full before it's disconnected under
+    /// [`Backpressure::Disconnect`]
+    pub slow_subscriber_timeout_ms: u32,
+}
+
+impl ForwardingParams {
+    /// Returns these parameters with the fields set by the validator's policy overridden
+    pub fn with_policy(&self, policy: Option<&ValidatorPolicy>) -> ForwardingParams {
+        let Some(policy) = policy else {
+            return *self;
+        };
+        ForwardingParams {
+            packet_delay_ms: policy.packet_delay_ms.unwrap_or(self.packet_delay_ms),
+            validator_packet_batch_size: policy
+                .validator_packet_batch_size
+                .unwrap_or(self.validator_packet_batch_size),
+            forward_all: policy.forward_all.unwrap_or(self.forward_all),
+            slot_lookahead: policy.slot_lookahead.unwrap_or(self.slot_lookahead),
+            previous_leader_grace_ms: self.previous_leader_grace_ms,
+            backpressure: policy.backpressure.unwrap_or(self.backpressure),
+            slow_subscriber_timeout_ms: self.slow_subscriber_timeout_ms,
+        }
+    }
+}
+
+/// A partial change to the forwarding parameters; unset fields are left as they are
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct ForwardingParamsUpdate {
+    pub packet_delay_ms: Option<u32>,
+    pub validator_packet_batch_size: Option<usize>,
+    pub forward_all: Option<bool>,
+    pub slot_lookahead: Option<u64>,
+    pub previous_leader_grace_ms: Option<u32>,
+    pub backpressure: Option<Backpressure>,
+    pub slow_subscriber_timeout_ms: Option<u32>,
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum ForwardingConfigError {
+    #[error("validator_packet_batch_size must be greater than 0")]
+    ZeroBatchSize,
+
+    #[error("slot_lookahead must be greater than 0")]
+    ZeroSlotLookahead,
+}
+
+/// Shared forwarding parameters, updated live through [`ForwardingConfig::update`]
+pub struct ForwardingConfig {
+    packet_delay_ms: AtomicU32,
+    validator_packet_batch_size: AtomicUsize,
+    forward_all: AtomicBool,
+    slot_lookahead: AtomicU64,
+    previous_leader_grace_ms: AtomicU32,
+    backpressure: AtomicU8,
+    slow_subscriber_timeout_ms: AtomicU32,
+    /// Per-validator overrides of the global parameters above
+    validator_policies: ValidatorPolicies,
+    /// Serializes updates so concurrent changes log consistent old and new values
+    update_lock: Mutex<()>,
+}
+
+impl ForwardingConfig {
+    pub fn new(
+        params: ForwardingParams,
+        validator_policies: ValidatorPolicies,
+    ) -> ForwardingConfig {
+        ForwardingConfig {
+            packet_delay_ms: AtomicU32::new(params.packet_delay_ms),
+            validator_packet_batch_size: AtomicUsize::new(params.validator_packet_batch_size),
+            forward_all: AtomicBool::new(params.forward_all),
+            slot_lookahead: AtomicU64::new(params.slot_lookahead),
+            previous_leader_grace_ms: AtomicU32::new(params.previous_leader_grace_ms),
+            backpressure: AtomicU8::new(params.backpressure as u8),
+            slow_subscriber_timeout_ms: AtomicU32::new(params.slow_subscriber_timeout_ms),
+            validator_policies,
+            update_lock: Mutex::new(()),
+        }
+    }
+
+    pub fn validator_policies(&self) -> &ValidatorPolicies {
+        &self.validator_policies
+    }
+
+    /// Shortest delay any validator needs, which is how long packets are held before they
+    /// reach the relayer event loop
+    pub fn min_packet_delay_ms(&self) -> u32 {
+        let packet_delay_ms = self.packet_delay_ms();
+        self.validator_policies
+            .min_packet_delay_ms()
+            .map_or(packet_delay_ms, |min| min.min(packet_delay_ms))
+    }
+
+    /// Longest delay any validator needs
+    pub fn max_packet_delay_ms(&self) -> u32 {
+        let packet_delay_ms = self.packet_delay_ms();
+        self.validator_policies
+            .max_packet_delay_ms()
+            .map_or(packet_delay_ms, |max| max.max(packet_delay_ms))
+    }
+
+    /// Largest slot lookahead any validator needs
+    pub fn max_slot_lookahead(&self) -> u64 {
+        let slot_lookahead = self.slot_lookahead();
+        self.validator_policies
+            .max_slot_lookahead()
+            .map_or(slot_lookahead, |max| max.max(slot_lookahead))
+    }
+
+    pub fn packet_delay_ms(&self) -> u32 {
+        self.packet_delay_ms.load(Ordering::Relaxed)
+    }
+
+    pub fn validator_packet_batch_size(&self) -> usize {
+        self.validator_packet_batch_size.load(Ordering::Relaxed)
+    }
+
+    pub fn forward_all(&self) -> bool {
+        self.forward_all.load(Ordering::Relaxed)
+    }
+
+    pub fn slot_lookahead(&self) -> u64 {
+        self.slot_lookahead.load(Ordering::Relaxed)
+    }
+
+    pub fn previous_leader_grace_ms(&self) -> u32 {
+        self.previous_leader_grace_ms.load(Ordering::Relaxed)
+    }
+
+    pub fn backpressure(&self) -> Backpressure {
+        Backpressure::from_u8(self.backpressure.load(Ordering::Relaxed))
+    }
+
+    pub fn slow_subscriber_timeout_ms(&self) -> u32 {
+        self.slow_subscriber_timeout_ms.load(Ordering::Relaxed)
+    }
+
+    pub fn params(&self) -> ForwardingParams {
+        ForwardingParams {
+            packet_delay_ms: self.packet_delay_ms(),
+            validator_packet_batch_size: self.validator_packet_batch_size(),
+            forward_all: self.forward_all(),
+            slot_lookahead: self.slot_lookahead(),
+            previous_leader_grace_ms: self.previous_leader_grace_ms(),
+            backpressure: self.backpressure(),
+            slow_subscriber_timeout_ms: self.slow_subscriber_timeout_ms(),
+        }
+    }
+
+    /// Applies the update after validating every field, logging and emitting a metric for each
+    /// changed parameter. `source` identifies who made the change in logs and metrics.
+    pub fn update(
+        &self,
+        update: &ForwardingParamsUpdate,
+        source: &str,
+    ) -> Result<ForwardingParams, ForwardingConfigError> {
+        if update.validator_packet_batch_size == Some(0) {
+            return Err(ForwardingConfigError::ZeroBatchSize);
+        }
+        if update.slot_lookahead == Some(0) {
+            return Err(ForwardingConfigError::ZeroSlotLookahead);
+        }
+
+        let _l = self.update_lock.lock().unwrap();
+        let old = self.params();
+        if let Some(packet_delay_ms) = update.packet_delay_ms {
+            self.packet_delay_ms
+                .store(packet_delay_ms, Ordering::Relaxed);
+        }
+        if let Some(validator_packet_batch_size) = update.validator_packet_batch_size {
+            self.validator_packet_batch_size
+                .store(validator_packet_batch_size, Ordering::Relaxed);
+        }
+        if let Some(forward_all) = update.forward_all {
+            self.forward_all.store(forward_all, Ordering::Relaxed);
+        }
+        if let Some(slot_lookahead) = update.slot_lookahead {
+            self.slot_lookahead.store(slot_lookahead, Ordering::Relaxed);
+        }
+        if let Some(previous_leader_grace_ms) = update.previous_leader_grace_ms {
+            self.previous_leader_grace_ms
+                .store(previous_leader_grace_ms, Ordering::Relaxed);
+        }
+        if let Some(backpressure) = update.backpressure {
+            self.backpressure
+                .store(backpressure as u8, Ordering::Relaxed);
+        }
+        if let Some(slow_subscriber_timeout_ms) = update.slow_subscriber_timeout_ms {
+            self.slow_subscriber_timeout_ms
+                .store(slow_subscriber_timeout_ms, Ordering::Relaxed);
+        }
+        let new = self.params();
+
+        for (parameter, old_value, new_value) in [
+            (
+                "packet_delay_ms",
+                old.packet_delay_ms as u64,
+                new.packet_delay_ms as u64,
+            ),
+            (
+                "validator_packet_batch_size",
+                old.validator_packet_batch_size as u64,
+                new.validator_packet_batch_size as u64,
+            ),
+            (
+                "forward_all",
+                old.forward_all as u64,
+                new.forward_all as u64,
+            ),
+            ("slot_lookahead", old.slot_lookahead, new.slot_lookahead),
+            (
+                "previous_leader_grace_ms",
+                old.previous_leader_grace_ms as u64,
+                new.previous_leader_grace_ms as u64,
+            ),
+            // logged as the policy's number, see [`Backpressure`]
+            (
+                "backpressure",
+                old.backpressure as u64,
+                new.backpressure as u64,
+            ),
+            (
+                "slow_subscriber_timeout_ms",
+                old.slow_subscriber_timeout_ms as u64,
+                new.slow_subscriber_timeout_ms as u64,
+            ),
+        ] {
+            if old_value != new_value {
+                info!(
+                    "forwarding parameter {parameter} changed from {old_value} to {new_value} \
+                     by {source}"
+                );
+                datapoint_info!(
+                    "forwarding_config-update",
+                    "parameter" => parameter,
+                    "source" => source,
+                    ("old_value", old_value, i64),
+                    ("new_value", new_value, i64),
+                );
+            }
+        }
+
+        Ok(new)
+    }
+}
@@ -20,12 +20,12 @@
 
 use std::{
     sync::{
-        atomic::{AtomicBool, Ordering},
+        atomic::{AtomicBool, AtomicU64, Ordering},
         Arc, RwLock,
     },
     thread,
     thread::{Builder, JoinHandle},
-    time::{Duration, Instant},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
 use crossbeam_channel::{select, tick, Receiver, Sender};
@@ -53,10 +53,54 @@ pub enum HealthState {
 pub struct HealthManager {
     /// Shared health state accessible by other components
     state: Arc<RwLock<HealthState>>,
+    /// Inputs the health state is derived from, for operator introspection
+    inputs: HealthInputs,
     /// Background thread handle for health monitoring
     manager_thread: JoinHandle<()>,
 }
 
+/// The values the health manager bases its health decision on.
+#[derive(Clone)]
+pub struct HealthInputs {
+    state: Arc<RwLock<HealthState>>,
+    /// Most recent slot received
+    last_slot: Arc<AtomicU64>,
+    /// Unix time in milliseconds when the most recent slot was received, 0 if none yet
+    last_slot_update_ms: Arc<AtomicU64>,
+    missing_slot_unhealthy_threshold: Duration,
+}
+
+impl HealthInputs {
+    pub fn state(&self) -> HealthState {
+        *self.state.read().unwrap()
+    }
+
+    pub fn last_slot(&self) -> Slot {
+        self.last_slot.load(Ordering::Relaxed)
+    }
+
+    /// Time since the most recent slot was received, None if no slot has been received
+    pub fn time_since_last_slot(&self) -> Option<Duration> {
+        match self.last_slot_update_ms.load(Ordering::Relaxed) {
+            0 => None,
+            last_slot_update_ms => Some(Duration::from_millis(
+                unix_time_ms().saturating_sub(last_slot_update_ms),
+            )),
+        }
+    }
+
+    pub fn missing_slot_unhealthy_threshold(&self) -> Duration {
+        self.missing_slot_unhealthy_threshold
+    }
+}
+
+fn unix_time_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
 /// Implementation of health monitoring and management.
 /// 
 /// The health manager tracks slot updates as a proxy for network connectivity
@@ -85,9 +129,18 @@ impl HealthManager {
     ) -> HealthManager {
         // Start in unhealthy state until we receive slot updates
         let health_state = Arc::new(RwLock::new(HealthState::Unhealthy));
+        let inputs = HealthInputs {
+            state: health_state.clone(),
+            last_slot: Arc::new(AtomicU64::new(0)),
+            last_slot_update_ms: Arc::new(AtomicU64::new(0)),
+            missing_slot_unhealthy_threshold,
+        };
+        let last_slot = inputs.last_slot.clone();
+        let last_slot_update_ms = inputs.last_slot_update_ms.clone();
         
         HealthManager {
             state: health_state.clone(),
+            inputs,
             manager_thread: Builder::new()
                 .name("health_manager".to_string())
                 .spawn(move || {
@@ -127,6 +180,8 @@ impl HealthManager {
                                 slot_sender.send(slot).expect("error forwarding slot, exiting");
                                 // Update timestamp to indicate we're receiving network data
                                 last_update = Instant::now();
+                                last_slot.store(slot, Ordering::Relaxed);
+                                last_slot_update_ms.store(unix_time_ms(), Ordering::Relaxed);
                             }
                             
                             // Periodic channel metrics reporting
@@ -159,6 +214,11 @@ impl HealthManager {
         self.state.clone()
     }
 
+    /// Returns a handle to the inputs behind the health state, for operator introspection.
+    pub fn inputs(&self) -> HealthInputs {
+        self.inputs.clone()
+    }
+
     /// Gracefully shuts down the health manager and waits for thread completion.
     /// 
     /// # Returns
@@ -0,0 +1,687 @@
+//! JWT signing keyring with key rotation.
+//!
+//! Tokens are signed by a single active key and carry its key id in the `kid` header, so the
+//! relayer can keep verifying tokens signed by earlier keys after switching to a new one.
+//!
+//! A keyring can be loaded from a directory of PEM files named `<kid>.pem`. The one private key
+//! in the directory is the active signing key; public keys are verification-only keys, e.g. keys
+//! that have been rotated out but still have unexpired tokens. To rotate, add the new private
+//! key and replace the old private key with its public key. The [`KeyringUpdater`] reloads the
+//! directory when it changes and keeps verifying keys that disappear from it until every token
+//! they signed has expired, but only for as long as the relayer runs, so keep the public key in
+//! the directory to honor those tokens across restarts.
+//!
+//! Keys can be RSA, P-256 or Ed25519 and each key's type selects its algorithm: RS256, ES256 or
+//! EdDSA. Tokens whose `alg` header doesn't match their key's algorithm are rejected.
+
+use std::{
+    collections::HashMap,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
+    thread::{self, sleep, Builder, JoinHandle},
+    time::{Duration, Instant, SystemTime},
+};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use log::{error, info};
+use openssl::{
+    bn::BigNum,
+    ecdsa::EcdsaSig,
+    error::ErrorStack,
+    hash::MessageDigest,
+    nid::Nid,
+    pkey::{HasPublic, Id, PKey, Private, Public},
+    sign::{Signer, Verifier},
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use solana_metrics::{datapoint_error, datapoint_info};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum KeyringError {
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("openssl error: {0}")]
+    Openssl(#[from] ErrorStack),
+
+    #[error("json error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("{0:?} isn't a PEM encoded private or public key: {1}")]
+    InvalidKey(PathBuf, ErrorStack),
+
+    #[error("{0:?} isn't a valid key id, key file names must be UTF-8")]
+    InvalidKeyId(PathBuf),
+
+    #[error("key {0} isn't an RSA, P-256 or Ed25519 key")]
+    UnsupportedKey(String),
+
+    #[error("found more than one signing key: {0} and {1}")]
+    MultipleSigningKeys(String, String),
+
+    #[error("no private signing key in {0:?}")]
+    NoSigningKey(PathBuf),
+
+    #[error("token signed by unknown key {0}")]
+    UnknownKeyId(String),
+
+    #[error("token is signed with {found} but key {kid} uses {expected}")]
+    AlgorithmMismatch {
+        kid: String,
+        expected: JwtAlgorithm,
+        found: JwtAlgorithm,
+    },
+
+    #[error("token isn't a base64 encoded JWT")]
+    MalformedToken,
+
+    #[error("token signature is invalid")]
+    InvalidSignature,
+}
+
+pub type KeyringResult<T> = Result<T, KeyringError>;
+
+/// JWT signing algorithm, determined by the type of the key
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JwtAlgorithm {
+    /// RSA PKCS#1 v1.5 with SHA-256
+    #[serde(rename = "RS256")]
+    Rs256,
+    /// ECDSA on P-256 with SHA-256
+    #[serde(rename = "ES256")]
+    Es256,
+    /// Ed25519
+    #[serde(rename = "EdDSA")]
+    EdDsa,
+}
+
+impl JwtAlgorithm {
+    /// Returns the algorithm used with the key, or None if the key type isn't supported
+    pub fn for_key<T: HasPublic>(key: &PKey<T>) -> Option<JwtAlgorithm> {
+        match key.id() {
+            Id::RSA => Some(JwtAlgorithm::Rs256),
+            Id::EC => {
+                let curve = key.ec_key().ok()?.group().curve_name();
+                (curve == Some(Nid::X9_62_PRIME256V1)).then_some(JwtAlgorithm::Es256)
+            }
+            Id::ED25519 => Some(JwtAlgorithm::EdDsa),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JwtAlgorithm::Rs256 => "RS256",
+            JwtAlgorithm::Es256 => "ES256",
+            JwtAlgorithm::EdDsa => "EdDSA",
+        }
+    }
+}
+
+impl fmt::Display for JwtAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// JOSE header of the tokens issued by the relayer
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct JwtHeader {
+    #[serde(rename = "alg")]
+    pub algorithm: JwtAlgorithm,
+    #[serde(rename = "kid", default, skip_serializing_if = "Option::is_none")]
+    pub key_id: Option<String>,
+}
+
+/// A token whose signature has been checked
+pub struct VerifiedToken<C> {
+    header: JwtHeader,
+    claims: C,
+}
+
+impl<C> VerifiedToken<C> {
+    pub fn header(&self) -> &JwtHeader {
+        &self.header
+    }
+
+    pub fn claims(&self) -> &C {
+        &self.claims
+    }
+}
+
+/// A key with the algorithm its type calls for
+struct JwtKey<T> {
+    algorithm: JwtAlgorithm,
+    key: PKey<T>,
+}
+
+/// Length of each of the r and s values in an ES256 signature
+const ES256_COMPONENT_LEN: usize = 32;
+
+impl<T: HasPublic> JwtKey<T> {
+    fn new(kid: &str, key: PKey<T>) -> KeyringResult<JwtKey<T>> {
+        let algorithm = JwtAlgorithm::for_key(&key)
+            .ok_or_else(|| KeyringError::UnsupportedKey(kid.to_string()))?;
+        Ok(JwtKey { algorithm, key })
+    }
+
+    fn verify(&self, message: &[u8], signature: &[u8]) -> KeyringResult<bool> {
+        let signature = match self.algorithm {
+            // JWTs carry the fixed size r || s encoding rather than the DER openssl uses
+            JwtAlgorithm::Es256 => {
+                if signature.len() != 2 * ES256_COMPONENT_LEN {
+                    return Ok(false);
+                }
+                let (r, s) = signature.split_at(ES256_COMPONENT_LEN);
+                EcdsaSig::from_private_components(BigNum::from_slice(r)?, BigNum::from_slice(s)?)?
+                    .to_der()?
+            }
+            JwtAlgorithm::Rs256 | JwtAlgorithm::EdDsa => signature.to_vec(),
+        };
+        let mut verifier = match self.algorithm {
+            JwtAlgorithm::EdDsa => Verifier::new_without_digest(&self.key)?,
+            JwtAlgorithm::Rs256 | JwtAlgorithm::Es256 => {
+                Verifier::new(MessageDigest::sha256(), &self.key)?
+            }
+        };
+        Ok(verifier.verify_oneshot(&signature, message)?)
+    }
+}
+
+impl JwtKey<Private> {
+    fn sign(&self, message: &[u8]) -> KeyringResult<Vec<u8>> {
+        let mut signer = match self.algorithm {
+            JwtAlgorithm::EdDsa => Signer::new_without_digest(&self.key)?,
+            JwtAlgorithm::Rs256 | JwtAlgorithm::Es256 => {
+                Signer::new(MessageDigest::sha256(), &self.key)?
+            }
+        };
+        let signature = signer.sign_oneshot_to_vec(message)?;
+        match self.algorithm {
+            JwtAlgorithm::Es256 => {
+                let signature = EcdsaSig::from_der(&signature)?;
+                let mut jose_signature = signature.r().to_vec_padded(ES256_COMPONENT_LEN as i32)?;
+                jose_signature.extend(signature.s().to_vec_padded(ES256_COMPONENT_LEN as i32)?);
+                Ok(jose_signature)
+            }
+            JwtAlgorithm::Rs256 | JwtAlgorithm::EdDsa => Ok(signature),
+        }
+    }
+}
+
+/// The active signing key and every key whose tokens are accepted, indexed by key id
+#[derive(Clone)]
+pub struct Keyring {
+    signing_kid: String,
+    signing_key: Arc<JwtKey<Private>>,
+    verifying_keys: HashMap<String, Arc<JwtKey<Public>>>,
+}
+
+impl Keyring {
+    /// Creates a keyring that signs with `signing_key`, whose public key is added to the
+    /// verifying keys under `signing_kid`. Each key's algorithm is chosen from its type.
+    pub fn new(
+        signing_kid: String,
+        signing_key: PKey<Private>,
+        verifying_keys: HashMap<String, PKey<Public>>,
+    ) -> KeyringResult<Keyring> {
+        let mut verifying_keys = verifying_keys
+            .into_iter()
+            .map(|(kid, key)| Ok((kid.clone(), Arc::new(JwtKey::new(&kid, key)?))))
+            .collect::<KeyringResult<HashMap<_, _>>>()?;
+        verifying_keys.insert(
+            signing_kid.clone(),
+            Arc::new(JwtKey::new(&signing_kid, public_key(&signing_key)?)?),
+        );
+
+        Ok(Keyring {
+            signing_key: Arc::new(JwtKey::new(&signing_kid, signing_key)?),
+            signing_kid,
+            verifying_keys,
+        })
+    }
+
+    /// Loads a single key pair. The key id is the signing key's file name without extension,
+    /// so the key can later be moved into a keyring directory without invalidating its tokens.
+    pub fn from_pem_files(
+        signing_key_path: &Path,
+        verifying_key_path: &Path,
+    ) -> KeyringResult<Keyring> {
+        let signing_kid = key_id(signing_key_path)?;
+        let signing_key = PKey::private_key_from_pem(&fs::read(signing_key_path)?)
+            .map_err(|e| KeyringError::InvalidKey(signing_key_path.to_owned(), e))?;
+        let verifying_key = PKey::public_key_from_pem(&fs::read(verifying_key_path)?)
+            .map_err(|e| KeyringError::InvalidKey(verifying_key_path.to_owned(), e))?;
+
+        let mut keyring = Keyring::new(signing_kid.clone(), signing_key, HashMap::new())?;
+        let verifying_key = JwtKey::new(&signing_kid, verifying_key)?;
+        keyring
+            .verifying_keys
+            .insert(signing_kid, Arc::new(verifying_key));
+        Ok(keyring)
+    }
+
+    /// Loads every `<kid>.pem` file in the directory; exactly one of them must be a private key.
+    pub fn load_dir(dir: &Path) -> KeyringResult<Keyring> {
+        let mut signing_key: Option<(String, PKey<Private>)> = None;
+        let mut verifying_keys = HashMap::new();
+
+        for path in pem_files(dir)? {
+            let kid = key_id(&path)?;
+            let pem = fs::read(&path)?;
+            if let Ok(private_key) = PKey::private_key_from_pem(&pem) {
+                if let Some((signing_kid, _)) = &signing_key {
+                    return Err(KeyringError::MultipleSigningKeys(signing_kid.clone(), kid));
+                }
+                signing_key = Some((kid, private_key));
+            } else {
+                let public_key = PKey::public_key_from_pem(&pem)
+                    .map_err(|e| KeyringError::InvalidKey(path.clone(), e))?;
+                verifying_keys.insert(kid, public_key);
+            }
+        }
+
+        let (signing_kid, signing_key) =
+            signing_key.ok_or_else(|| KeyringError::NoSigningKey(dir.to_owned()))?;
+        Keyring::new(signing_kid, signing_key, verifying_keys)
+    }
+
+    pub fn signing_kid(&self) -> &str {
+        &self.signing_kid
+    }
+
+    pub fn signing_algorithm(&self) -> JwtAlgorithm {
+        self.signing_key.algorithm
+    }
+
+    pub fn has_verifying_key(&self, kid: &str) -> bool {
+        self.verifying_keys.contains_key(kid)
+    }
+
+    pub fn verifying_kids(&self) -> impl Iterator<Item = &String> {
+        self.verifying_keys.keys()
+    }
+
+    /// Signs the claims with the active key, naming it in the `kid` header
+    pub fn sign<C: Serialize>(&self, claims: C) -> KeyringResult<String> {
+        let header = JwtHeader {
+            algorithm: self.signing_key.algorithm,
+            key_id: Some(self.signing_kid.clone()),
+        };
+        let message = format!("{}.{}", encode_segment(&header)?, encode_segment(&claims)?);
+        let signature = self.signing_key.sign(message.as_bytes())?;
+        Ok(format!("{message}.{}", URL_SAFE_NO_PAD.encode(signature)))
+    }
+
+    /// Verifies a token with the key named by its `kid` header, rejecting tokens whose `alg`
+    /// header doesn't match that key. Tokens issued before `kid` headers were added are verified
+    /// with the active key.
+    pub fn verify<C: DeserializeOwned>(&self, token: &str) -> KeyringResult<VerifiedToken<C>> {
+        let (message, signature) = token.rsplit_once('.').ok_or(KeyringError::MalformedToken)?;
+        let (header, claims) = message
+            .split_once('.')
+            .ok_or(KeyringError::MalformedToken)?;
+
+        let header: JwtHeader = decode_segment(header)?;
+        let kid = header
+            .key_id
+            .clone()
+            .unwrap_or_else(|| self.signing_kid.clone());
+        let verifying_key = self
+            .verifying_keys
+            .get(&kid)
+            .ok_or_else(|| KeyringError::UnknownKeyId(kid.clone()))?;
+        if header.algorithm != verifying_key.algorithm {
+            return Err(KeyringError::AlgorithmMismatch {
+                kid,
+                expected: verifying_key.algorithm,
+                found: header.algorithm,
+            });
+        }
+
+        let signature = URL_SAFE_NO_PAD
+            .decode(signature)
+            .map_err(|_| KeyringError::MalformedToken)?;
+        if !verifying_key.verify(message.as_bytes(), &signature)? {
+            return Err(KeyringError::InvalidSignature);
+        }
+
+        Ok(VerifiedToken {
+            header,
+            claims: decode_segment(claims)?,
+        })
+    }
+}
+
+fn encode_segment<T: Serialize>(value: &T) -> KeyringResult<String> {
+    Ok(URL_SAFE_NO_PAD.encode(serde_json::to_vec(value)?))
+}
+
+fn decode_segment<T: DeserializeOwned>(segment: &str) -> KeyringResult<T> {
+    let json = URL_SAFE_NO_PAD
+        .decode(segment)
+        .map_err(|_| KeyringError::MalformedToken)?;
+    Ok(serde_json::from_slice(&json)?)
+}
+
+fn public_key(private_key: &PKey<Private>) -> KeyringResult<PKey<Public>> {
+    Ok(PKey::public_key_from_pem(
+        &private_key.public_key_to_pem()?,
+    )?)
+}
+
+fn key_id(path: &Path) -> KeyringResult<String> {
+    path.file_stem()
+        .and_then(|stem| stem.to_str())
+        .map(str::to_string)
+        .ok_or_else(|| KeyringError::InvalidKeyId(path.to_owned()))
+}
+
+fn pem_files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "pem") {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// Shared access handle to the current keyring.
+#[derive(Clone)]
+pub struct KeyringHandle {
+    keyring: Arc<RwLock<Arc<Keyring>>>,
+}
+
+impl KeyringHandle {
+    pub fn new(keyring: Keyring) -> KeyringHandle {
+        KeyringHandle {
+            keyring: Arc::new(RwLock::new(Arc::new(keyring))),
+        }
+    }
+
+    /// Returns a snapshot of the current keyring
+    pub fn load(&self) -> Arc<Keyring> {
+        self.keyring.read().unwrap().clone()
+    }
+
+    /// Atomically replaces the keyring, returning the previous one
+    pub fn store(&self, keyring: Keyring) -> Arc<Keyring> {
+        std::mem::replace(&mut *self.keyring.write().unwrap(), Arc::new(keyring))
+    }
+}
+
+/// Watches a keyring directory and reloads it into a [`KeyringHandle`] when it changes.
+pub struct KeyringUpdater {
+    /// Polls the directory for modifications
+    refresh_thread: JoinHandle<()>,
+}
+
+impl KeyringUpdater {
+    /// Starts a thread that reloads the keyring directory on change. The handle should already
+    /// hold the directory's keyring.
+    ///
+    /// # Arguments
+    /// * `dir` - Directory of `<kid>.pem` key files
+    /// * `handle` - Shared handle that gets the reloaded keyring swapped in
+    /// * `refresh_interval` - How often to check the directory for modifications
+    /// * `retired_key_ttl` - How long to keep verifying with keys removed from the directory,
+    ///   which should cover the longest token lifetime
+    /// * `exit` - Shutdown signal for graceful termination
+    pub fn new(
+        dir: PathBuf,
+        handle: &KeyringHandle,
+        refresh_interval: Duration,
+        retired_key_ttl: Duration,
+        exit: &Arc<AtomicBool>,
+    ) -> KeyringUpdater {
+        let handle = handle.clone();
+        let exit = exit.clone();
+        let refresh_thread = Builder::new()
+            .name("keyring_updater".to_string())
+            .spawn(move || {
+                let mut last_modified = dir_modified_times(&dir).ok();
+                // keys removed from the directory, with when they were removed
+                let mut retired_keys: HashMap<String, (Arc<JwtKey<Public>>, Instant)> =
+                    HashMap::new();
+
+                let mut last_check = Instant::now();
+                while !exit.load(Ordering::Relaxed) {
+                    sleep(Duration::from_secs(1));
+                    if last_check.elapsed() < refresh_interval {
+                        continue;
+                    }
+                    last_check = Instant::now();
+
+                    let modified = match dir_modified_times(&dir) {
+                        Ok(modified) => modified,
+                        Err(e) => {
+                            error!("error reading keyring directory {dir:?}: {e}");
+                            datapoint_error!(
+                                "keyring_updater-error",
+                                ("error", e.to_string(), String)
+                            );
+                            continue;
+                        }
+                    };
+
+                    let has_expired_keys = retired_keys
+                        .values()
+                        .any(|(_, retired_at)| retired_at.elapsed() >= retired_key_ttl);
+                    if last_modified.as_ref() == Some(&modified) && !has_expired_keys {
+                        continue;
+                    }
+
+                    let keyring = if last_modified.as_ref() == Some(&modified) {
+                        // only retired keys changed, rebuild from the current keyring
+                        let mut keyring = Keyring::clone(&handle.load());
+                        keyring
+                            .verifying_keys
+                            .retain(|kid, _| !retired_keys.contains_key(kid));
+                        keyring
+                    } else {
+                        match Keyring::load_dir(&dir) {
+                            Ok(keyring) => keyring,
+                            Err(e) => {
+                                error!("error reloading keyring from {dir:?}: {e}");
+                                datapoint_error!(
+                                    "keyring_updater-error",
+                                    ("error", e.to_string(), String)
+                                );
+                                continue;
+                            }
+                        }
+                    };
+                    last_modified = Some(modified);
+                    Self::store(keyring, &handle, &mut retired_keys, retired_key_ttl);
+                }
+            })
+            .unwrap();
+
+        KeyringUpdater { refresh_thread }
+    }
+
+    pub fn join(self) -> thread::Result<()> {
+        self.refresh_thread.join()
+    }
+
+    /// Swaps in `keyring` along with the keys retired less than `retired_key_ttl` ago
+    fn store(
+        mut keyring: Keyring,
+        handle: &KeyringHandle,
+        retired_keys: &mut HashMap<String, (Arc<JwtKey<Public>>, Instant)>,
+        retired_key_ttl: Duration,
+    ) {
+        let old_keyring = handle.load();
+        for (kid, verifying_key) in &old_keyring.verifying_keys {
+            if !keyring.has_verifying_key(kid) {
+                retired_keys
+                    .entry(kid.clone())
+                    .or_insert_with(|| (verifying_key.clone(), Instant::now()));
+            }
+        }
+        retired_keys.retain(|kid, (_, retired_at)| {
+            retired_at.elapsed() < retired_key_ttl && !keyring.has_verifying_key(kid)
+        });
+        for (kid, (verifying_key, _)) in retired_keys.iter() {
+            keyring
+                .verifying_keys
+                .insert(kid.clone(), verifying_key.clone());
+        }
+
+        info!(
+            "keyring reloaded, signing with {} ({}), verifying keys: {:?}",
+            keyring.signing_kid,
+            keyring.signing_key.algorithm,
+            keyring.verifying_kids().collect::<Vec<_>>()
+        );
+        datapoint_info!(
+            "keyring_updater-reload",
+            ("signing_kid", keyring.signing_kid.clone(), String),
+            ("num_verifying_keys", keyring.verifying_keys.len(), i64),
+            ("num_retired_keys", retired_keys.len(), i64),
+        );
+        handle.store(keyring);
+    }
+}
+
+fn dir_modified_times(dir: &Path) -> io::Result<Vec<(PathBuf, SystemTime)>> {
+    pem_files(dir)?
+        .into_iter()
+        .map(|path| {
+            let modified = fs::metadata(&path)?.modified()?;
+            Ok((path, modified))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use openssl::{
+        ec::{EcGroup, EcKey},
+        nid::Nid,
+        pkey::PKey,
+        rsa::Rsa,
+    };
+    use serde::{Deserialize, Serialize};
+
+    use crate::keyring::{
+        encode_segment, JwtAlgorithm, JwtHeader, Keyring, KeyringError, KeyringResult,
+    };
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct TestClaims {
+        sub: String,
+    }
+
+    fn test_claims() -> TestClaims {
+        TestClaims {
+            sub: "validator".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_keyring_algorithms() {
+        let p256 = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        for (key, algorithm) in [
+            (
+                PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap(),
+                JwtAlgorithm::Rs256,
+            ),
+            (
+                PKey::from_ec_key(EcKey::generate(&p256).unwrap()).unwrap(),
+                JwtAlgorithm::Es256,
+            ),
+            (PKey::generate_ed25519().unwrap(), JwtAlgorithm::EdDsa),
+        ] {
+            let keyring = Keyring::new("key".to_string(), key, HashMap::new()).unwrap();
+            assert_eq!(keyring.signing_algorithm(), algorithm);
+
+            let token = keyring.sign(test_claims()).unwrap();
+            let verified = keyring.verify::<TestClaims>(&token).unwrap();
+            assert_eq!(verified.header().algorithm, algorithm);
+            assert_eq!(verified.claims(), &test_claims());
+
+            let (message, _) = token.rsplit_once('.').unwrap();
+            let forged = format!("{message}.AAAA");
+            assert!(keyring.verify::<TestClaims>(&forged).is_err());
+        }
+
+        let p384 = EcGroup::from_curve_name(Nid::SECP384R1).unwrap();
+        assert!(matches!(
+            Keyring::new(
+                "p384".to_string(),
+                PKey::from_ec_key(EcKey::generate(&p384).unwrap()).unwrap(),
+                HashMap::new(),
+            ),
+            Err(KeyringError::UnsupportedKey(kid)) if kid == "p384"
+        ));
+    }
+
+    #[test]
+    fn test_keyring_rejects_algorithm_mismatch() -> KeyringResult<()> {
+        let keyring = Keyring::new(
+            "rsa".to_string(),
+            PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap(),
+            HashMap::new(),
+        )?;
+        let token = keyring.sign(test_claims())?;
+
+        let (_, claims_and_signature) = token.split_once('.').unwrap();
+        let header = encode_segment(&JwtHeader {
+            algorithm: JwtAlgorithm::Es256,
+            key_id: Some("rsa".to_string()),
+        })?;
+        assert!(matches!(
+            keyring.verify::<TestClaims>(&format!("{header}.{claims_and_signature}")),
+            Err(KeyringError::AlgorithmMismatch {
+                expected: JwtAlgorithm::Rs256,
+                found: JwtAlgorithm::Es256,
+                ..
+            })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_keyring_verifies_retired_keys() {
+        let old_key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+        let new_key = PKey::generate_ed25519().unwrap();
+        let old_public_key =
+            PKey::public_key_from_pem(&old_key.public_key_to_pem().unwrap()).unwrap();
+
+        let old_keyring = Keyring::new("old".to_string(), old_key, HashMap::new()).unwrap();
+        let old_token = old_keyring.sign(test_claims()).unwrap();
+
+        // rotating to a key of another type switches algorithm without breaking old tokens
+        let new_keyring = Keyring::new(
+            "new".to_string(),
+            new_key,
+            HashMap::from([("old".to_string(), old_public_key)]),
+        )
+        .unwrap();
+        let verified = new_keyring.verify::<TestClaims>(&old_token).unwrap();
+        assert_eq!(verified.header().key_id.as_deref(), Some("old"));
+        assert_eq!(verified.claims(), &test_claims());
+
+        let unrelated_keyring = Keyring::new(
+            "unrelated".to_string(),
+            PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap(),
+            HashMap::new(),
+        )
+        .unwrap();
+        assert!(matches!(
+            unrelated_keyring.verify::<TestClaims>(&old_token),
+            Err(KeyringError::UnknownKeyId(kid)) if kid == "old"
+        ));
+    }
+}
@@ -9,6 +9,16 @@
 //! - **auth_service**: JWT-based challenge-response authentication for validators
 //! - **auth_interceptor**: gRPC middleware for validating JWT tokens
 //! - **auth_challenges**: DOS-resistant challenge management with expiration
+//! - **auth_rate_limiter**: Per-IP and per-pubkey token bucket limits on the auth service
+//! - **refresh_tokens**: Refresh token rotation, reuse detection and revocation
+//! - **keyring**: JWT signing and verifying keys, reloadable for key rotation
+//! - **validator_authers**: Composable rules deciding which validators can authenticate
+//! - **auth_events**: JSON lines audit log of authentication and subscription events
+//! - **sessions**: Per-validator sessions from authentication to packet subscription
+//! - **server_tls**: Optional TLS and mTLS for the gRPC server, reloadable without a restart
+//! 
+//! ### Operations
+//! - **admin_service**: Private gRPC service for inspecting and managing subscriptions
 //! 
 //! ### Health & Monitoring
 //! - **health_manager**: Tracks relayer connectivity and operational status
@@ -16,6 +26,10 @@
 //! 
 //! ### Core Relayer
 //! - **relayer**: Main packet forwarding service with OFAC filtering and metrics
+//! - **forwarding_config**: Forwarding parameters that can be retuned on a running relayer
+//! - **validator_policy**: Per-validator overrides of the forwarding parameters
+//! - **subscriber_queue**: Per-validator packet queues with a backpressure policy
+//! - **priority_fee**: Fee per compute unit used to order forwarded packets
 //! 
 //! ## Architecture
 //! 
@@ -32,9 +46,21 @@
 //! - OFAC sanctions filtering for regulatory compliance
 //! - Health-based connection management
 
+pub mod admin_service;
 mod auth_challenges;
+pub mod auth_events;
 pub mod auth_interceptor;
+pub mod auth_rate_limiter;
 pub mod auth_service;
+pub mod forwarding_config;
 pub mod health_manager;
+pub mod keyring;
+pub mod priority_fee;
+pub mod refresh_tokens;
 pub mod relayer;
 pub mod schedule_cache;
+pub mod server_tls;
+pub mod sessions;
+pub mod subscriber_queue;
+pub mod validator_authers;
+pub mod validator_policy;
@@ -0,0 +1,137 @@
+//! Priority of the transactions forwarded to validators.
+//!
+//! The relayer forwards packets in order of the compute unit price set by their compute budget
+//! instructions, so when a validator's queue fills up the transactions paying the highest
+//! priority fee are queued first and the cheapest ones are the ones dropped. Transactions paying
+//! the same price keep their arrival order.
+//!
+//! The base signature fee isn't counted: spread over the transaction's own compute unit limit
+//! it would let a sender outrank priced transactions just by requesting fewer compute units.
+
+use solana_sdk::{
+    borsh1::try_from_slice_unchecked,
+    compute_budget::{self, ComputeBudgetInstruction},
+    transaction::VersionedTransaction,
+};
+
+/// Compute units requested by each instruction of a transaction without a compute unit limit
+const DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT: u32 = 200_000;
+/// Most compute units a transaction can request
+const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+/// Compute unit price and limit set by a transaction's compute budget instructions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ComputeBudgetLimits {
+    /// Priority fee per compute unit, in micro-lamports
+    pub compute_unit_price: u64,
+    pub compute_unit_limit: u32,
+}
+
+impl ComputeBudgetLimits {
+    /// Reads the compute budget instructions of `tx`, returning `None` if they're malformed or
+    /// set the same limit twice, which the runtime rejects
+    pub fn from_transaction(tx: &VersionedTransaction) -> Option<ComputeBudgetLimits> {
+        let account_keys = tx.message.static_account_keys();
+        let mut compute_unit_price = None;
+        let mut compute_unit_limit = None;
+        let mut num_non_compute_budget_instructions = 0u32;
+
+        for instruction in tx.message.instructions() {
+            let program_id = account_keys.get(instruction.program_id_index as usize)?;
+            if !compute_budget::check_id(program_id) {
+                num_non_compute_budget_instructions += 1;
+                continue;
+            }
+            match try_from_slice_unchecked(&instruction.data).ok()? {
+                ComputeBudgetInstruction::SetComputeUnitPrice(price) => {
+                    if compute_unit_price.replace(price).is_some() {
+                        return None;
+                    }
+                }
+                ComputeBudgetInstruction::SetComputeUnitLimit(limit) => {
+                    if compute_unit_limit.replace(limit).is_some() {
+                        return None;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let compute_unit_limit = compute_unit_limit.unwrap_or_else(|| {
+            num_non_compute_budget_instructions
+                .saturating_mul(DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT)
+        });
+        Some(ComputeBudgetLimits {
+            compute_unit_price: compute_unit_price.unwrap_or_default(),
+            compute_unit_limit: compute_unit_limit.min(MAX_COMPUTE_UNIT_LIMIT),
+        })
+    }
+
+    /// Rank the transaction is forwarded with, higher first
+    pub fn priority(&self) -> u64 {
+        self.compute_unit_price
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::{
+        compute_budget::ComputeBudgetInstruction,
+        hash::Hash,
+        instruction::Instruction,
+        signature::{Keypair, Signer},
+        system_instruction,
+        transaction::{Transaction, VersionedTransaction},
+    };
+
+    use crate::priority_fee::ComputeBudgetLimits;
+
+    fn transaction(instructions: &[Instruction]) -> VersionedTransaction {
+        let payer = Keypair::new();
+        VersionedTransaction::from(Transaction::new_signed_with_payer(
+            instructions,
+            Some(&payer.pubkey()),
+            &[&payer],
+            Hash::default(),
+        ))
+    }
+
+    #[test]
+    fn test_priority() {
+        let payer = Keypair::new().pubkey();
+        let transfer = system_instruction::transfer(&payer, &payer, 1);
+
+        // without compute budget instructions, no priority fee is paid
+        let limits = ComputeBudgetLimits::from_transaction(&transaction(&[transfer.clone()]));
+        assert_eq!(
+            limits,
+            Some(ComputeBudgetLimits {
+                compute_unit_price: 0,
+                compute_unit_limit: 200_000,
+            })
+        );
+        assert_eq!(limits.unwrap().priority(), 0);
+
+        // a tiny compute unit limit doesn't outrank a transaction paying a priority fee
+        let unpriced = ComputeBudgetLimits::from_transaction(&transaction(&[
+            ComputeBudgetInstruction::set_compute_unit_limit(1_000),
+            transfer.clone(),
+        ]))
+        .unwrap();
+        let priced = ComputeBudgetLimits::from_transaction(&transaction(&[
+            ComputeBudgetInstruction::set_compute_unit_limit(1_400_000),
+            ComputeBudgetInstruction::set_compute_unit_price(1),
+            transfer.clone(),
+        ]))
+        .unwrap();
+        assert!(unpriced.priority() < priced.priority());
+
+        // the runtime rejects duplicate compute budget instructions
+        let tx = transaction(&[
+            ComputeBudgetInstruction::set_compute_unit_price(10_000),
+            ComputeBudgetInstruction::set_compute_unit_price(20_000),
+            transfer,
+        ]);
+        assert_eq!(ComputeBudgetLimits::from_transaction(&tx), None);
+    }
+}
@@ -0,0 +1,359 @@
+//! Refresh token rotation and revocation.
+//!
+//! Every refresh token belongs to a family that starts when a validator authenticates with a
+//! signed challenge. Families started while rotation is enabled rotate: each refresh retires the
+//! presented token and issues the next one in the family, which keeps the family's original
+//! expiration. A retired token being presented again means it was copied, so the whole family is
+//! revoked and the validator has to authenticate with a new challenge. Families started without
+//! rotation keep their one token until it expires, so there's nothing to detect reuse against.
+//!
+//! Families can be persisted to a JSON file by a [`RefreshTokenPersister`] so rotation state and
+//! revocations survive a restart. It writes changes on its own thread, so authenticating or
+//! refreshing never waits on disk. Tokens from a family the store doesn't know, issued before
+//! families were tracked or before a restart without persistence, are only rejected while
+//! rotation is enabled.
+
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, sleep, Builder, JoinHandle},
+    time::Duration,
+};
+
+use chrono::Utc;
+use log::error;
+use serde::{Deserialize, Serialize};
+use solana_metrics::datapoint_error;
+use solana_sdk::pubkey::Pubkey;
+use thiserror::Error;
+
+/// Random identifier of a token (`jti` claim) or token family
+pub type TokenId = u128;
+
+/// How often changed token families are written to the store's file
+const PERSIST_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Error, Debug)]
+pub enum RefreshTokenStoreError {
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("json error: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum RefreshTokenError {
+    #[error("refresh token isn't known to this relayer")]
+    UnknownFamily,
+
+    #[error("refresh token has been revoked")]
+    Revoked,
+
+    #[error("refresh token was already used")]
+    Reused,
+
+    #[error("refresh token isn't the current token of its family")]
+    UnknownToken,
+}
+
+/// The token to embed in a refreshed refresh token
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RefreshTokenGrant {
+    pub jti: TokenId,
+    pub expires_at_unix_ts: i64,
+    /// Whether `jti` replaces the presented token, which has been retired
+    pub rotated: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct TokenFamily {
+    client_pubkey: String,
+    /// Id of the only token in the family that may be used, as hex
+    current_jti: String,
+    expires_at_unix_ts: i64,
+    revoked: bool,
+    /// Whether each refresh retires the current token, set when the family is started
+    #[serde(default)]
+    rotating: bool,
+}
+
+/// Shared store of refresh token families, keyed by hex family id
+#[derive(Clone)]
+pub struct RefreshTokenStore {
+    families: Arc<Mutex<HashMap<String, TokenFamily>>>,
+    /// Set when the families change, cleared once they've been persisted
+    changed: Arc<AtomicBool>,
+}
+
+pub fn format_token_id(token_id: TokenId) -> String {
+    format!("{token_id:032x}")
+}
+
+/// Parses a hex token id, returning 0 (which is never issued) for tokens that don't have one
+pub fn parse_token_id(token_id: &str) -> TokenId {
+    TokenId::from_str_radix(token_id, 16).unwrap_or_default()
+}
+
+impl RefreshTokenStore {
+    /// Creates a store, loading the families persisted at `path` if the file exists
+    pub fn new(path: Option<&Path>) -> Result<RefreshTokenStore, RefreshTokenStoreError> {
+        let families = match path {
+            Some(path) if path.exists() => serde_json::from_str(&fs::read_to_string(path)?)?,
+            _ => HashMap::new(),
+        };
+        Ok(RefreshTokenStore {
+            families: Arc::new(Mutex::new(families)),
+            changed: Arc::default(),
+        })
+    }
+
+    pub fn new_token_id() -> TokenId {
+        // 0 is reserved for tokens issued without an id
+        rand::random::<TokenId>().max(1)
+    }
+
+    /// Starts a token family for a validator that just authenticated, rotating its token on
+    /// each refresh if `rotating` is set.
+    /// Returns the family id and the id of its first refresh token.
+    pub fn start_family(
+        &self,
+        client_pubkey: &Pubkey,
+        expires_at_unix_ts: i64,
+        rotating: bool,
+    ) -> (TokenId, TokenId) {
+        let family = Self::new_token_id();
+        let jti = Self::new_token_id();
+
+        let mut l_families = self.families.lock().unwrap();
+        l_families.insert(
+            format_token_id(family),
+            TokenFamily {
+                client_pubkey: client_pubkey.to_string(),
+                current_jti: format_token_id(jti),
+                expires_at_unix_ts,
+                revoked: false,
+                rotating,
+            },
+        );
+        self.mark_changed(&mut l_families);
+
+        (family, jti)
+    }
+
+    /// Checks that `jti` is the current token of `family`. If the family rotates, the token is
+    /// retired and the returned grant holds the id of its replacement, and presenting a retired
+    /// token revokes the family.
+    pub fn use_token(
+        &self,
+        family: TokenId,
+        jti: TokenId,
+    ) -> Result<RefreshTokenGrant, RefreshTokenError> {
+        let mut l_families = self.families.lock().unwrap();
+        let token_family = l_families
+            .get_mut(&format_token_id(family))
+            .ok_or(RefreshTokenError::UnknownFamily)?;
+        if token_family.revoked {
+            return Err(RefreshTokenError::Revoked);
+        }
+        if parse_token_id(&token_family.current_jti) != jti {
+            // only a rotating family retires tokens, so only there does a mismatch mean reuse
+            if !token_family.rotating {
+                return Err(RefreshTokenError::UnknownToken);
+            }
+            token_family.revoked = true;
+            self.mark_changed(&mut l_families);
+            return Err(RefreshTokenError::Reused);
+        }
+
+        let rotate = token_family.rotating;
+        let grant = RefreshTokenGrant {
+            jti: if rotate { Self::new_token_id() } else { jti },
+            expires_at_unix_ts: token_family.expires_at_unix_ts,
+            rotated: rotate,
+        };
+        if rotate {
+            token_family.current_jti = format_token_id(grant.jti);
+            self.mark_changed(&mut l_families);
+        }
+        Ok(grant)
+    }
+
+    /// Revokes every refresh token family issued to the validator, returning how many were live
+    pub fn revoke_validator(&self, client_pubkey: &Pubkey) -> usize {
+        let client_pubkey = client_pubkey.to_string();
+        let mut l_families = self.families.lock().unwrap();
+        let mut num_revoked = 0;
+        for token_family in l_families.values_mut() {
+            if token_family.client_pubkey == client_pubkey && !token_family.revoked {
+                token_family.revoked = true;
+                num_revoked += 1;
+            }
+        }
+        if num_revoked > 0 {
+            self.mark_changed(&mut l_families);
+        }
+        num_revoked
+    }
+
+    pub fn len(&self) -> usize {
+        self.families.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drops expired families and flags the rest to be persisted
+    fn mark_changed(&self, families: &mut HashMap<String, TokenFamily>) {
+        let now = Utc::now().timestamp();
+        families.retain(|_, token_family| token_family.expires_at_unix_ts > now);
+        self.changed.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Writes the families of a [`RefreshTokenStore`] to a file whenever they change.
+pub struct RefreshTokenPersister {
+    persist_thread: JoinHandle<()>,
+}
+
+impl RefreshTokenPersister {
+    /// Starts a thread writing the store's families to `path` once per interval if they've
+    /// changed. Write failures are logged and retried rather than failing the requests that
+    /// changed the store.
+    ///
+    /// # Arguments
+    /// * `store` - Store to persist, usually loaded from `path`
+    /// * `path` - JSON file the families are written to
+    /// * `exit` - Shutdown signal; pending changes are written before the thread exits
+    pub fn new(
+        store: &RefreshTokenStore,
+        path: PathBuf,
+        exit: &Arc<AtomicBool>,
+    ) -> RefreshTokenPersister {
+        let store = store.clone();
+        let exit = exit.clone();
+        let persist_thread = Builder::new()
+            .name("refresh_token_persister".to_string())
+            .spawn(move || loop {
+                let exiting = exit.load(Ordering::Relaxed);
+                if let Err(e) = Self::persist(&store, &path) {
+                    error!("error persisting refresh tokens to {path:?}: {e}");
+                    datapoint_error!(
+                        "refresh_token_persister-error",
+                        ("error", e.to_string(), String),
+                    );
+                }
+                if exiting {
+                    break;
+                }
+                sleep(PERSIST_INTERVAL);
+            })
+            .unwrap();
+
+        RefreshTokenPersister { persist_thread }
+    }
+
+    pub fn join(self) -> thread::Result<()> {
+        self.persist_thread.join()
+    }
+
+    /// Writes the store's families to `path` if they changed since they were last written
+    fn persist(store: &RefreshTokenStore, path: &Path) -> Result<(), RefreshTokenStoreError> {
+        if !store.changed.swap(false, Ordering::Relaxed) {
+            return Ok(());
+        }
+        let result = Self::write_families(store, path);
+        if result.is_err() {
+            store.changed.store(true, Ordering::Relaxed);
+        }
+        result
+    }
+
+    fn write_families(
+        store: &RefreshTokenStore,
+        path: &Path,
+    ) -> Result<(), RefreshTokenStoreError> {
+        // serialized under the lock, written to disk outside of it
+        let families = serde_json::to_vec(&*store.families.lock().unwrap())?;
+        // write to a temporary file first so a crash can't leave a truncated store behind
+        let tmp_path = path.with_extension("tmp");
+        fs::write(&tmp_path, families)?;
+        fs::rename(&tmp_path, path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use solana_sdk::pubkey::Pubkey;
+
+    use crate::refresh_tokens::{RefreshTokenError, RefreshTokenPersister, RefreshTokenStore};
+
+    #[test]
+    fn test_refresh_token_reuse_revokes_family() {
+        let store = RefreshTokenStore::new(None).unwrap();
+        let expires_at_unix_ts = Utc::now().timestamp() + 60;
+        let (family, jti) = store.start_family(&Pubkey::new_unique(), expires_at_unix_ts, true);
+
+        let grant = store.use_token(family, jti).unwrap();
+        assert_ne!(grant.jti, jti);
+        assert_eq!(grant.expires_at_unix_ts, expires_at_unix_ts);
+        assert!(grant.rotated);
+
+        // the rotated-out token is presented again, so neither token works afterwards
+        assert_eq!(store.use_token(family, jti), Err(RefreshTokenError::Reused));
+        assert_eq!(
+            store.use_token(family, grant.jti),
+            Err(RefreshTokenError::Revoked)
+        );
+        assert_eq!(
+            store.use_token(RefreshTokenStore::new_token_id(), jti),
+            Err(RefreshTokenError::UnknownFamily)
+        );
+
+        // families started without rotation keep their token and aren't revoked on a mismatch
+        let (family, jti) = store.start_family(&Pubkey::new_unique(), expires_at_unix_ts, false);
+        let grant = store.use_token(family, jti).unwrap();
+        assert_eq!(grant.jti, jti);
+        assert!(!grant.rotated);
+        assert_eq!(
+            store.use_token(family, RefreshTokenStore::new_token_id()),
+            Err(RefreshTokenError::UnknownToken)
+        );
+        assert!(store.use_token(family, jti).is_ok());
+    }
+
+    #[test]
+    fn test_refresh_token_store_persistence() {
+        let path = std::env::temp_dir().join(format!(
+            "relayer-refresh-tokens-{}.json",
+            std::process::id()
+        ));
+        let validator = Pubkey::new_unique();
+
+        let store = RefreshTokenStore::new(Some(&path)).unwrap();
+        let (family, jti) = store.start_family(&validator, Utc::now().timestamp() + 60, true);
+        let (other_family, other_jti) =
+            store.start_family(&Pubkey::new_unique(), Utc::now().timestamp() + 60, false);
+        store.start_family(&validator, Utc::now().timestamp() - 1, true);
+        assert_eq!(store.revoke_validator(&validator), 1);
+        assert!(store.use_token(other_family, other_jti).is_ok());
+        RefreshTokenPersister::persist(&store, &path).unwrap();
+
+        let reloaded = RefreshTokenStore::new(Some(&path)).unwrap();
+        assert_eq!(reloaded.len(), 2);
+        assert_eq!(
+            reloaded.use_token(family, jti),
+            Err(RefreshTokenError::Revoked)
+        );
+        assert!(reloaded.use_token(other_family, other_jti).is_ok());
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -17,23 +17,39 @@
 //! ### Packet Processing Pipeline
 //! 1. **Packet Reception**: Receives verified transaction packets from TPU
 //! 2. **OFAC Filtering**: Drops packets involving sanctioned addresses (if enabled)
-//! 3. **Leader-based Routing**: Forwards packets only to current/upcoming slot leaders
+//! 3. **Leader-based Routing**: Forwards packets to the current leader first, then upcoming
+//!    leaders in slot order, and to the previous leader for a grace period after its slots end,
+//!    applying any per-validator lookahead, batch size and delay policy
 //! 4. **Connection Management**: Maintains gRPC streams to authenticated validators
 //! 
 //! ### Subscription Management
 //! - Validators authenticate and subscribe to packet streams
 //! - Health-based connection dropping when relayer is unhealthy
 //! - Automatic cleanup of disconnected validator streams
+//! - Subscriptions opening and closing are tracked in the [`SessionTable`]
 //! 
 //! ### Performance Features
 //! - Configurable packet batching for throughput optimization
 //! - Comprehensive metrics collection and reporting
 //! - Non-blocking channel operations to prevent stalls
+//! - Per-validator backpressure policy for validators that can't keep up: drop the newest or
+//!   oldest batches, or disconnect validators whose queue stays full
 //! - Efficient crossbeam-based event loop for high performance
+//! - Packet fan-out split across forwarding threads, each owning a shard of the subscriptions
+//! 
+//! ## Threads
+//! 
+//! The event loop thread tracks slots and the leader window, registers subscriptions, checks
+//! health and reports metrics. It OFAC filters and converts each packet batch once, then hands
+//! it to every forwarding thread. A forwarding thread sends packets and heartbeats to the
+//! validators in its shard, holds batches for validators with a packet delay and drops
+//! subscriptions that fail to send. Its metrics are merged into the event loop's every interval.
 
 use std::{
-    collections::{hash_map::Entry, HashMap, HashSet},
+    cmp::Reverse,
+    collections::{HashMap, HashSet, VecDeque},
     net::IpAddr,
+    ops::RangeInclusive,
     sync::{
         atomic::{AtomicBool, AtomicU64, Ordering},
         Arc, RwLock,
@@ -43,16 +59,18 @@ use std::{
     time::{Duration, Instant, SystemTime},
 };
 
+use bytes::Bytes;
 use crossbeam_channel::{bounded, Receiver, RecvError, Sender};
 use dashmap::DashMap;
 use histogram::Histogram;
-use jito_core::ofac::is_tx_ofac_related;
+use jito_core::ofac::{OfacDropCounts, OfacFilter};
 use jito_protos::{
     convert::packet_to_proto_packet,
-    packet::PacketBatch as ProtoPacketBatch,
+    encoded::{encode_message, relayer_server::Relayer},
+    packet::{Packet as ProtoPacket, PacketBatch as ProtoPacketBatch},
     relayer::{
-        relayer_server::Relayer, subscribe_packets_response, GetTpuConfigsRequest,
-        GetTpuConfigsResponse, SubscribePacketsRequest, SubscribePacketsResponse,
+        subscribe_packets_response, GetTpuConfigsRequest, GetTpuConfigsResponse,
+        SubscribePacketsRequest, SubscribePacketsResponse,
     },
     shared::{Header, Heartbeat, Socket},
 };
@@ -66,17 +84,25 @@ use solana_sdk::{
     saturating_add_assign, transaction::VersionedTransaction,
 };
 use thiserror::Error;
-use tokio::sync::mpsc::{channel, error::TrySendError, Sender as TokioSender};
-use tokio_stream::wrappers::ReceiverStream;
 use tonic::{Request, Response, Status};
 
-use crate::{health_manager::HealthState, schedule_cache::LeaderScheduleUpdatingHandle};
+use crate::{
+    auth_events::SubscriptionCloseReason,
+    forwarding_config::{ForwardingConfig, ForwardingParams},
+    health_manager::HealthState,
+    priority_fee::ComputeBudgetLimits,
+    schedule_cache::{LeaderScheduleUpdatingHandle, LeaderWindow},
+    sessions::SessionTable,
+    subscriber_queue::{
+        subscriber_queue, Backpressure, SubscriberReceiver, SubscriberSender, TrySendError,
+    },
+};
 
 /// Statistics tracking for packet forwarding to individual validators.
 /// 
 /// These metrics help monitor relayer performance and identify validators
 /// with connection or capacity issues.
-#[derive(Default)]
+#[derive(Default, Clone, Copy)]
 struct PacketForwardStats {
     /// Total number of packet batches successfully forwarded to this validator
     num_packets_forwarded: u64,
@@ -84,6 +110,14 @@ struct PacketForwardStats {
     num_packets_dropped: u64,
 }
 
+/// Messages the event loop dropped for a forwarding thread because its queue was full
+#[derive(Default, Clone, Copy)]
+struct ForwardingDrops {
+    num_messages: u64,
+    /// Packets in the dropped messages, which none of the thread's validators received
+    num_packets: u64,
+}
+
 /// Comprehensive metrics collection for relayer performance monitoring.
 /// 
 /// These metrics are reported periodically to help operators monitor:
@@ -108,8 +142,18 @@ struct RelayerMetrics {
     pub metrics_latency_us: u64,
     /// Number of channel send failures due to full channels
     pub num_try_send_channel_full: u64,
+    /// Validators whose queue was at least half full at the end of this period
+    pub num_slow_subscribers: u64,
+    /// Validators disconnected this period for keeping their queue full
+    pub num_slow_subscriber_disconnects: u64,
+    /// Number of forwarded packets that set a compute unit price this period
+    pub num_priority_fee_packets: u64,
+    /// Messages dropped this period per forwarding thread, indexed by thread
+    pub forwarding_drops: Vec<ForwardingDrops>,
     /// Distribution of packet processing latencies from TPU to validator
     pub packet_latencies_us: Histogram,
+    /// Packets dropped by the OFAC filter, by reason
+    pub ofac_drops: OfacDropCounts,
 
     // Crossbeam event loop arm processing latencies
     /// Time spent processing slot updates
@@ -136,6 +180,10 @@ struct RelayerMetrics {
     pub delay_packet_receiver_max_len: usize,
     /// Total capacity of packet receiver channel
     pub delay_packet_receiver_capacity: usize,
+    /// Peak number of batches held for validators with a longer packet delay this period
+    pub delayed_packet_batches_max_len: usize,
+    /// Peak number of messages waiting for a forwarding thread this period
+    pub forwarding_receiver_max_len: usize,
     /// Total items queued across all validator subscription channels
     pub packet_subscriptions_total_queued: usize,
     /// Per-validator packet forwarding statistics
@@ -147,6 +195,7 @@ impl RelayerMetrics {
         slot_receiver_capacity: usize,
         subscription_receiver_capacity: usize,
         delay_packet_receiver_capacity: usize,
+        num_forwarding_threads: usize,
     ) -> Self {
         RelayerMetrics {
             highest_slot: 0,
@@ -157,7 +206,12 @@ impl RelayerMetrics {
             max_heartbeat_tick_latency_us: 0,
             metrics_latency_us: 0,
             num_try_send_channel_full: 0,
+            num_slow_subscribers: 0,
+            num_slow_subscriber_disconnects: 0,
+            num_priority_fee_packets: 0,
+            forwarding_drops: vec![ForwardingDrops::default(); num_forwarding_threads],
             packet_latencies_us: Histogram::default(),
+            ofac_drops: OfacDropCounts::default(),
             crossbeam_slot_receiver_processing_us: Histogram::default(),
             crossbeam_delay_packet_receiver_processing_us: Histogram::default(),
             crossbeam_subscription_receiver_processing_us: Histogram::default(),
@@ -169,6 +223,8 @@ impl RelayerMetrics {
             subscription_receiver_capacity,
             delay_packet_receiver_max_len: 0,
             delay_packet_receiver_capacity,
+            delayed_packet_batches_max_len: 0,
+            forwarding_receiver_max_len: 0,
             packet_subscriptions_total_queued: 0,
             packet_stats_per_validator: HashMap::new(),
         }
@@ -193,36 +249,48 @@ impl RelayerMetrics {
 
     fn update_packet_subscription_total_capacity(
         &mut self,
-        packet_subscriptions: &HashMap<
-            Pubkey,
-            TokioSender<Result<SubscribePacketsResponse, Status>>,
-        >,
+        packet_subscriptions: &PacketSubscriptions,
     ) {
         let packet_subscriptions_total_queued = packet_subscriptions
-            .values()
-            .map(|x| RelayerImpl::SUBSCRIBER_QUEUE_CAPACITY - x.capacity())
+            .senders()
+            .iter()
+            .map(|(_, x)| x.len())
             .sum::<usize>();
         self.packet_subscriptions_total_queued = packet_subscriptions_total_queued;
     }
 
-    fn increment_packets_forwarded(&mut self, validator_id: &Pubkey, num_packets: u64) {
-        self.packet_stats_per_validator
-            .entry(*validator_id)
-            .and_modify(|entry| saturating_add_assign!(entry.num_packets_forwarded, num_packets))
-            .or_insert(PacketForwardStats {
-                num_packets_forwarded: num_packets,
-                num_packets_dropped: 0,
-            });
-    }
-
-    fn increment_packets_dropped(&mut self, validator_id: &Pubkey, num_packets: u64) {
-        self.packet_stats_per_validator
-            .entry(*validator_id)
-            .and_modify(|entry| saturating_add_assign!(entry.num_packets_dropped, num_packets))
-            .or_insert(PacketForwardStats {
-                num_packets_forwarded: 0,
-                num_packets_dropped: num_packets,
-            });
+    /// Adds a forwarding thread's metrics for the period to the totals across threads
+    fn merge_forwarding_metrics(&mut self, forwarding_metrics: ForwardingMetrics) {
+        saturating_add_assign!(
+            self.num_removed_connections,
+            forwarding_metrics.num_removed_connections
+        );
+        saturating_add_assign!(
+            self.num_try_send_channel_full,
+            forwarding_metrics.num_try_send_channel_full
+        );
+        saturating_add_assign!(
+            self.num_slow_subscribers,
+            forwarding_metrics.num_slow_subscribers
+        );
+        saturating_add_assign!(
+            self.num_slow_subscriber_disconnects,
+            forwarding_metrics.num_slow_subscriber_disconnects
+        );
+        self.forwarding_receiver_max_len = std::cmp::max(
+            self.forwarding_receiver_max_len,
+            forwarding_metrics.receiver_max_len,
+        );
+        self.delayed_packet_batches_max_len = std::cmp::max(
+            self.delayed_packet_batches_max_len,
+            forwarding_metrics.delayed_packet_batches_max_len,
+        );
+        // a validator that disconnected and subscribed again can be in two threads' reports
+        for (pubkey, stats) in forwarding_metrics.packet_stats_per_validator {
+            let entry = self.packet_stats_per_validator.entry(pubkey).or_default();
+            saturating_add_assign!(entry.num_packets_forwarded, stats.num_packets_forwarded);
+            saturating_add_assign!(entry.num_packets_dropped, stats.num_packets_dropped);
+        }
     }
 
     fn report(&self) {
@@ -233,6 +301,13 @@ impl RelayerMetrics {
                 ("num_packets_dropped", stats.num_packets_dropped, i64),
             );
         }
+        for (thread_id, drops) in self.forwarding_drops.iter().enumerate() {
+            datapoint_info!("relayer_forwarding_thread_drops",
+                "thread_id" => thread_id.to_string(),
+                ("num_messages_dropped", drops.num_messages, i64),
+                ("num_packets_dropped", drops.num_packets, i64),
+            );
+        }
         datapoint_info!(
             "relayer_metrics",
             ("highest_slot", self.highest_slot, i64),
@@ -245,7 +320,47 @@ impl RelayerMetrics {
                 self.num_try_send_channel_full,
                 i64
             ),
+            ("num_slow_subscribers", self.num_slow_subscribers, i64),
+            (
+                "num_slow_subscriber_disconnects",
+                self.num_slow_subscriber_disconnects,
+                i64
+            ),
+            (
+                "num_priority_fee_packets",
+                self.num_priority_fee_packets,
+                i64
+            ),
+            (
+                "num_forwarding_packets_dropped",
+                self.forwarding_drops
+                    .iter()
+                    .map(|drops| drops.num_packets)
+                    .sum::<u64>(),
+                i64
+            ),
             ("metrics_latency_us", self.metrics_latency_us, i64),
+            ("num_ofac_dropped", self.ofac_drops.total(), i64),
+            (
+                "num_ofac_dropped_static_key",
+                self.ofac_drops.static_key,
+                i64
+            ),
+            (
+                "num_ofac_dropped_lookup_table_writable",
+                self.ofac_drops.lookup_table_writable,
+                i64
+            ),
+            (
+                "num_ofac_dropped_lookup_table_readonly",
+                self.ofac_drops.lookup_table_readonly,
+                i64
+            ),
+            (
+                "num_ofac_dropped_unresolvable_lookup_table",
+                self.ofac_drops.unresolvable_lookup_table,
+                i64
+            ),
             (
                 "max_heartbeat_tick_latency_us",
                 self.max_heartbeat_tick_latency_us,
@@ -412,6 +527,21 @@ impl RelayerMetrics {
                 self.delay_packet_receiver_capacity,
                 i64
             ),
+            (
+                "delayed_packet_batches_len",
+                self.delayed_packet_batches_max_len,
+                i64
+            ),
+            (
+                "forwarding_receiver_len",
+                self.forwarding_receiver_max_len,
+                i64
+            ),
+            (
+                "forwarding_receiver_capacity",
+                RelayerImpl::FORWARDING_QUEUE_CAPACITY,
+                i64
+            ),
             (
                 "packet_subscriptions_total_queued",
                 self.packet_subscriptions_total_queued,
@@ -421,6 +551,48 @@ impl RelayerMetrics {
     }
 }
 
+/// Metrics collected by a forwarding thread, sent to the event loop every metrics interval and
+/// merged into the [`RelayerMetrics`] it reports.
+#[derive(Default)]
+struct ForwardingMetrics {
+    /// Number of validator connections dropped by the thread this period
+    num_removed_connections: u64,
+    /// Number of channel send failures due to full channels
+    num_try_send_channel_full: u64,
+    /// Validators whose queue was at least half full when the metrics were sent
+    num_slow_subscribers: u64,
+    /// Validators disconnected by the thread this period for keeping their queue full
+    num_slow_subscriber_disconnects: u64,
+    /// Peak number of messages waiting for the thread this period
+    receiver_max_len: usize,
+    /// Peak number of batches held for validators with a longer packet delay this period
+    delayed_packet_batches_max_len: usize,
+    /// Per-validator packet forwarding statistics
+    packet_stats_per_validator: HashMap<Pubkey, PacketForwardStats>,
+}
+
+impl ForwardingMetrics {
+    fn increment_packets_forwarded(&mut self, validator_id: &Pubkey, num_packets: u64) {
+        self.packet_stats_per_validator
+            .entry(*validator_id)
+            .and_modify(|entry| saturating_add_assign!(entry.num_packets_forwarded, num_packets))
+            .or_insert(PacketForwardStats {
+                num_packets_forwarded: num_packets,
+                num_packets_dropped: 0,
+            });
+    }
+
+    fn increment_packets_dropped(&mut self, validator_id: &Pubkey, num_packets: u64) {
+        self.packet_stats_per_validator
+            .entry(*validator_id)
+            .and_modify(|entry| saturating_add_assign!(entry.num_packets_dropped, num_packets))
+            .or_insert(PacketForwardStats {
+                num_packets_forwarded: 0,
+                num_packets_dropped: num_packets,
+            });
+    }
+}
+
 /// Container for packet batches received from the TPU with timing information.
 /// 
 /// The timestamp enables latency tracking from packet reception through
@@ -428,8 +600,25 @@ impl RelayerMetrics {
 pub struct RelayerPacketBatches {
     /// Timestamp when packets were received from TPU (for latency measurement)
     pub stamp: Instant,
-    /// Verified transaction packet batch from banking stage
-    pub banking_packet_batch: BankingPacketBatch,
+    /// Verified transaction packet batches from banking stage, possibly several released
+    /// together so their packets are ordered by fee as one batch
+    pub banking_packet_batches: Vec<BankingPacketBatch>,
+}
+
+/// Packets of a TPU batch that passed the OFAC filter, converted once and shared by the
+/// forwarding threads
+struct FilteredPacketBatch {
+    /// Timestamp when packets were received from TPU (for latency measurement)
+    stamp: Instant,
+    packets: Vec<ProtoPacket>,
+}
+
+/// Work handed from the event loop to every forwarding thread
+#[derive(Clone)]
+enum ForwardingMessage {
+    Packets(Arc<FilteredPacketBatch>),
+    /// Heartbeat with its count for the current metrics period
+    Heartbeat(u64),
 }
 
 /// Types of subscriptions that can be registered with the relayer.
@@ -441,8 +630,10 @@ pub enum Subscription {
     ValidatorPacketSubscription {
         /// Validator's public key for identification and authorization
         pubkey: Pubkey,
-        /// gRPC stream sender for forwarding packets to this validator
-        sender: TokioSender<Result<SubscribePacketsResponse, Status>>,
+        /// IP address the validator subscribed from
+        client_ip: Option<IpAddr>,
+        /// Queue feeding the gRPC stream that forwards packets to this validator
+        sender: SubscriberSender,
     },
 }
 
@@ -455,31 +646,291 @@ pub enum RelayerError {
     /// Relayer is shutting down due to channel disconnection
     #[error("shutdown")]
     Shutdown(#[from] RecvError),
+    /// A forwarding thread stopped receiving packets
+    #[error("forwarding thread exited")]
+    ForwardingThreadExited,
 }
 
 pub type RelayerResult<T> = Result<T, RelayerError>;
 
-type PacketSubscriptions =
-    Arc<RwLock<HashMap<Pubkey, TokioSender<Result<SubscribePacketsResponse, Status>>>>>;
+/// Subscriptions forwarded to by a single forwarding thread
+type SubscriptionShard = RwLock<HashMap<Pubkey, SubscriberSender>>;
+/// Packet forwarding totals per subscribed validator since it subscribed
+type ValidatorPacketStats = Arc<RwLock<HashMap<Pubkey, PacketForwardStats>>>;
+/// Validators temporarily banned from subscribing, with the end of their ban
+type ValidatorBans = Arc<RwLock<HashMap<Pubkey, SystemTime>>>;
+
+/// Packet subscriptions split into one shard per forwarding thread. A validator stays in the
+/// shard it subscribed to until it disconnects; new validators go to the smallest shard.
+#[derive(Clone)]
+pub struct PacketSubscriptions {
+    shards: Arc<Vec<SubscriptionShard>>,
+}
+
+impl PacketSubscriptions {
+    fn new(num_shards: usize) -> PacketSubscriptions {
+        PacketSubscriptions {
+            shards: Arc::new((0..num_shards).map(|_| RwLock::default()).collect()),
+        }
+    }
+
+    fn shard(&self, shard: usize) -> &SubscriptionShard {
+        &self.shards[shard]
+    }
+
+    /// Adds the validator's subscription, returning the one it replaced.
+    /// Only called from the event loop, so concurrent inserts can't race.
+    fn insert(&self, pubkey: Pubkey, sender: SubscriberSender) -> Option<SubscriberSender> {
+        for shard in self.shards.iter() {
+            if let Some(existing) = shard.write().unwrap().get_mut(&pubkey) {
+                return Some(std::mem::replace(existing, sender));
+            }
+        }
+        self.shards
+            .iter()
+            .min_by_key(|shard| shard.read().unwrap().len())
+            .unwrap()
+            .write()
+            .unwrap()
+            .insert(pubkey, sender);
+        None
+    }
+
+    fn remove(&self, pubkey: &Pubkey) -> Option<SubscriberSender> {
+        self.shards
+            .iter()
+            .find_map(|shard| shard.write().unwrap().remove(pubkey))
+    }
+
+    /// Removes the validator's subscription only if it's still fed by `sender`, so a failure
+    /// reported for a stream the validator has since replaced doesn't tear down the new one
+    fn remove_if_current(
+        &self,
+        pubkey: &Pubkey,
+        sender: &SubscriberSender,
+    ) -> Option<SubscriberSender> {
+        self.shards.iter().find_map(|shard| {
+            let mut shard = shard.write().unwrap();
+            if shard.get(pubkey)?.same_queue(sender) {
+                shard.remove(pubkey)
+            } else {
+                None
+            }
+        })
+    }
+
+    fn len(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| shard.read().unwrap().len())
+            .sum()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.shards
+            .iter()
+            .all(|shard| shard.read().unwrap().is_empty())
+    }
+
+    fn pubkeys(&self) -> Vec<Pubkey> {
+        self.shards
+            .iter()
+            .flat_map(|shard| shard.read().unwrap().keys().cloned().collect::<Vec<_>>())
+            .collect()
+    }
+
+    fn senders(&self) -> Vec<(Pubkey, SubscriberSender)> {
+        self.shards
+            .iter()
+            .flat_map(|shard| {
+                shard
+                    .read()
+                    .unwrap()
+                    .iter()
+                    .map(|(pubkey, sender)| (*pubkey, sender.clone()))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+}
+
+/// A validator's packet subscription, as reported to operators
+pub struct SubscriptionInfo {
+    pub pubkey: Pubkey,
+    /// Messages waiting in the validator's stream
+    pub queue_depth: usize,
+    pub queue_capacity: usize,
+    /// Packets forwarded since the validator subscribed
+    pub num_packets_forwarded: u64,
+    /// Packets dropped because the validator's stream was full since it subscribed
+    pub num_packets_dropped: u64,
+}
+
+#[derive(Clone)]
 pub struct RelayerHandle {
     packet_subscriptions: PacketSubscriptions,
+    validator_packet_stats: ValidatorPacketStats,
+    validator_bans: ValidatorBans,
+    sessions: SessionTable,
 }
 
 impl RelayerHandle {
-    pub fn new(packet_subscriptions: &PacketSubscriptions) -> RelayerHandle {
+    pub fn new(
+        packet_subscriptions: &PacketSubscriptions,
+        validator_packet_stats: &ValidatorPacketStats,
+        validator_bans: &ValidatorBans,
+        sessions: &SessionTable,
+    ) -> RelayerHandle {
         RelayerHandle {
             packet_subscriptions: packet_subscriptions.clone(),
+            validator_packet_stats: validator_packet_stats.clone(),
+            validator_bans: validator_bans.clone(),
+            sessions: sessions.clone(),
         }
     }
 
-    pub fn connected_validators(&self) -> Vec<Pubkey> {
-        self.packet_subscriptions
-            .read()
+    /// Returns the current subscriptions with their queue depth and forwarding totals.
+    /// Totals are folded in once per metrics interval, so they lag by up to a second.
+    pub fn subscriptions(&self) -> Vec<SubscriptionInfo> {
+        let subscriptions = self.packet_subscriptions.senders();
+        let l_stats = self.validator_packet_stats.read().unwrap();
+        subscriptions
+            .iter()
+            .map(|(pubkey, sender)| {
+                let stats = l_stats.get(pubkey).copied().unwrap_or_default();
+                SubscriptionInfo {
+                    pubkey: *pubkey,
+                    queue_depth: sender.len(),
+                    queue_capacity: sender.capacity(),
+                    num_packets_forwarded: stats.num_packets_forwarded,
+                    num_packets_dropped: stats.num_packets_dropped,
+                }
+            })
+            .collect()
+    }
+
+    /// Drops the validator's subscription, returning false if it wasn't subscribed.
+    pub fn disconnect_validator(&self, pubkey: &Pubkey) -> bool {
+        self.remove_subscription(pubkey, SubscriptionCloseReason::Disconnected)
+    }
+
+    /// Drops the subscription of a validator that's no longer authorized, returning false if it
+    /// wasn't subscribed.
+    pub fn drop_unauthorized_validator(&self, pubkey: &Pubkey) -> bool {
+        self.remove_subscription(pubkey, SubscriptionCloseReason::Unauthorized)
+    }
+
+    fn remove_subscription(&self, pubkey: &Pubkey, reason: SubscriptionCloseReason) -> bool {
+        let was_connected = self.packet_subscriptions.remove(pubkey).is_some();
+        self.validator_packet_stats.write().unwrap().remove(pubkey);
+        if was_connected {
+            datapoint_info!(
+                "relayer_removed_subscription",
+                ("pubkey", pubkey.to_string(), String)
+            );
+            self.sessions.subscription_closed(pubkey, reason);
+        }
+        was_connected
+    }
+
+    /// Disconnects the validator and rejects its subscriptions for `duration`.
+    /// Returns the end of the ban and whether the validator was subscribed.
+    pub fn ban_validator(&self, pubkey: &Pubkey, duration: Duration) -> (SystemTime, bool) {
+        let banned_until = SystemTime::now() + duration;
+        self.validator_bans
+            .write()
             .unwrap()
-            .keys()
-            .cloned()
+            .insert(*pubkey, banned_until);
+        let was_connected = self.remove_subscription(pubkey, SubscriptionCloseReason::Banned);
+        datapoint_info!(
+            "relayer_banned_validator",
+            ("pubkey", pubkey.to_string(), String),
+            ("duration_secs", duration.as_secs(), i64),
+        );
+        (banned_until, was_connected)
+    }
+
+    /// Lifts the validator's ban, returning false if it wasn't banned.
+    pub fn unban_validator(&self, pubkey: &Pubkey) -> bool {
+        self.validator_bans
+            .write()
+            .unwrap()
+            .remove(pubkey)
+            .is_some_and(|banned_until| banned_until > SystemTime::now())
+    }
+
+    /// Returns the currently banned validators, pruning expired bans.
+    pub fn banned_validators(&self) -> Vec<(Pubkey, SystemTime)> {
+        let now = SystemTime::now();
+        let mut l_bans = self.validator_bans.write().unwrap();
+        l_bans.retain(|_, banned_until| *banned_until > now);
+        l_bans
+            .iter()
+            .map(|(pubkey, banned_until)| (*pubkey, *banned_until))
             .collect()
     }
+
+    pub fn is_banned(&self, pubkey: &Pubkey) -> bool {
+        self.validator_bans
+            .read()
+            .unwrap()
+            .get(pubkey)
+            .is_some_and(|banned_until| *banned_until > SystemTime::now())
+    }
+
+    pub fn connected_validators(&self) -> Vec<Pubkey> {
+        self.packet_subscriptions.pubkeys()
+    }
+}
+
+/// Tracks the leader window across slot updates, remembering when the current leader took over
+/// so the previous leader can keep receiving packets for a grace period
+struct LeaderTracker {
+    leader_window: LeaderWindow,
+    leader_changed_at: Instant,
+}
+
+impl LeaderTracker {
+    fn new() -> LeaderTracker {
+        LeaderTracker {
+            leader_window: LeaderWindow::default(),
+            leader_changed_at: Instant::now(),
+        }
+    }
+
+    fn update(&mut self, leader_window: LeaderWindow) {
+        if leader_window.current_leader() != self.leader_window.current_leader() {
+            self.leader_changed_at = Instant::now();
+        }
+        self.leader_window = leader_window;
+    }
+
+    /// Order in which a validator receives packets given its forwarding parameters, lowest first:
+    /// the current leader, then upcoming leaders within its lookahead in slot order, then the
+    /// previous leader during its grace period, then any other validator when forwarding to all.
+    /// Returns None if the validator shouldn't receive packets.
+    fn forwarding_rank(&self, pubkey: &Pubkey, params: &ForwardingParams) -> Option<usize> {
+        let leader_count = self.leader_window.upcoming().len();
+        if let Some((position, leader_slots)) = self.leader_window.upcoming_slots(pubkey) {
+            let slots_until_leader = leader_slots
+                .first_slot
+                .saturating_sub(self.leader_window.current_slot());
+            if slots_until_leader < params.slot_lookahead {
+                return Some(position);
+            }
+        }
+
+        let is_previous_leader = self
+            .leader_window
+            .previous()
+            .is_some_and(|leader_slots| leader_slots.leader == *pubkey);
+        let previous_leader_grace = Duration::from_millis(params.previous_leader_grace_ms as u64);
+        if is_previous_leader && self.leader_changed_at.elapsed() < previous_leader_grace {
+            return Some(leader_count);
+        }
+
+        params.forward_all.then_some(leader_count + 1)
+    }
 }
 
 pub struct RelayerImpl {
@@ -492,10 +943,17 @@ pub struct RelayerImpl {
     threads: Vec<JoinHandle<()>>,
     health_state: Arc<RwLock<HealthState>>,
     packet_subscriptions: PacketSubscriptions,
+    validator_packet_stats: ValidatorPacketStats,
+    validator_bans: ValidatorBans,
+    sessions: SessionTable,
 }
 
 impl RelayerImpl {
     pub const SUBSCRIBER_QUEUE_CAPACITY: usize = 50_000;
+    /// Queue fill ratio at which a validator is reported as a slow subscriber
+    pub const SLOW_SUBSCRIBER_FILL_RATIO: f64 = 0.5;
+    /// Packet batches and heartbeats waiting for each forwarding thread
+    pub const FORWARDING_QUEUE_CAPACITY: usize = 1_000;
 
     #[allow(clippy::too_many_arguments)]
     pub fn new(
@@ -507,21 +965,65 @@ impl RelayerImpl {
         tpu_fwd_quic_ports: Vec<u16>,
         health_state: Arc<RwLock<HealthState>>,
         exit: Arc<AtomicBool>,
-        ofac_addresses: HashSet<Pubkey>,
+        ofac_filter: OfacFilter,
         address_lookup_table_cache: Arc<DashMap<Pubkey, AddressLookupTableAccount>>,
-        validator_packet_batch_size: usize,
-        forward_all: bool,
-        slot_lookahead: u64,
+        forwarding_config: Arc<ForwardingConfig>,
+        sessions: SessionTable,
+        num_forwarding_threads: usize,
     ) -> Self {
         // receiver tracked as relayer_metrics.subscription_receiver_len
         let (subscription_sender, subscription_receiver) =
             bounded(LoadBalancer::SLOT_QUEUE_CAPACITY);
 
-        let packet_subscriptions = Arc::new(RwLock::new(HashMap::default()));
+        let packet_subscriptions = PacketSubscriptions::new(num_forwarding_threads);
+        let validator_packet_stats = Arc::new(RwLock::new(HashMap::default()));
+        let leader_tracker = Arc::new(RwLock::new(LeaderTracker::new()));
+
+        // forwarding threads report once per interval, so this only fills up if the event loop
+        // stalls for a few seconds
+        let (forwarding_metrics_sender, forwarding_metrics_receiver) =
+            bounded(4 * num_forwarding_threads);
+
+        let mut forwarding_senders = Vec::with_capacity(num_forwarding_threads);
+        let mut threads = Vec::with_capacity(num_forwarding_threads + 1);
+        for shard in 0..num_forwarding_threads {
+            // receiver tracked as relayer_metrics.forwarding_receiver_len
+            let (forwarding_sender, forwarding_receiver) = bounded(Self::FORWARDING_QUEUE_CAPACITY);
+            forwarding_senders.push(forwarding_sender);
 
-        let thread = {
+            let forwarding_metrics_sender = forwarding_metrics_sender.clone();
+            let exit = exit.clone();
+            let packet_subscriptions = packet_subscriptions.clone();
+            let validator_packet_stats = validator_packet_stats.clone();
+            let leader_tracker = leader_tracker.clone();
+            let forwarding_config = forwarding_config.clone();
+            let sessions = sessions.clone();
+            threads.push(
+                thread::Builder::new()
+                    .name(format!("relayer_impl-forwarding_thread_{shard}"))
+                    .spawn(move || {
+                        let res = Self::run_forwarding_loop(
+                            shard,
+                            forwarding_receiver,
+                            forwarding_metrics_sender,
+                            &exit,
+                            &packet_subscriptions,
+                            &validator_packet_stats,
+                            &leader_tracker,
+                            &forwarding_config,
+                            &sessions,
+                        );
+                        warn!("RelayerImpl forwarding thread {shard} exited with result {res:?}")
+                    })
+                    .unwrap(),
+            );
+        }
+
+        threads.push({
             let health_state = health_state.clone();
             let packet_subscriptions = packet_subscriptions.clone();
+            let validator_packet_stats = validator_packet_stats.clone();
+            let sessions = sessions.clone();
             thread::Builder::new()
                 .name("relayer_impl-event_loop_thread".to_string())
                 .spawn(move || {
@@ -529,35 +1031,46 @@ impl RelayerImpl {
                         slot_receiver,
                         subscription_receiver,
                         delay_packet_receiver,
+                        forwarding_senders,
+                        forwarding_metrics_receiver,
                         leader_schedule_cache,
-                        slot_lookahead,
                         health_state,
                         exit,
                         &packet_subscriptions,
-                        ofac_addresses,
+                        &validator_packet_stats,
+                        &leader_tracker,
+                        ofac_filter,
                         address_lookup_table_cache,
-                        validator_packet_batch_size,
-                        forward_all,
+                        &forwarding_config,
+                        &sessions,
                     );
                     warn!("RelayerImpl thread exited with result {res:?}")
                 })
                 .unwrap()
-        };
+        });
 
         Self {
             tpu_quic_ports,
             tpu_fwd_quic_ports,
             subscription_sender,
             public_ip,
-            threads: vec![thread],
+            threads,
             health_state,
             packet_subscriptions,
+            validator_packet_stats,
+            validator_bans: Arc::new(RwLock::new(HashMap::default())),
+            sessions,
             seq: AtomicU64::new(0),
         }
     }
 
     pub fn handle(&self) -> RelayerHandle {
-        RelayerHandle::new(&self.packet_subscriptions)
+        RelayerHandle::new(
+            &self.packet_subscriptions,
+            &self.validator_packet_stats,
+            &self.validator_bans,
+            &self.sessions,
+        )
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -565,15 +1078,18 @@ impl RelayerImpl {
         slot_receiver: Receiver<Slot>,
         subscription_receiver: Receiver<Subscription>,
         delay_packet_receiver: Receiver<RelayerPacketBatches>,
+        forwarding_senders: Vec<Sender<ForwardingMessage>>,
+        forwarding_metrics_receiver: Receiver<ForwardingMetrics>,
         leader_schedule_cache: LeaderScheduleUpdatingHandle,
-        slot_lookahead: u64,
         health_state: Arc<RwLock<HealthState>>,
         exit: Arc<AtomicBool>,
         packet_subscriptions: &PacketSubscriptions,
-        ofac_addresses: HashSet<Pubkey>,
+        validator_packet_stats: &ValidatorPacketStats,
+        leader_tracker: &RwLock<LeaderTracker>,
+        ofac_filter: OfacFilter,
         address_lookup_table_cache: Arc<DashMap<Pubkey, AddressLookupTableAccount>>,
-        validator_packet_batch_size: usize,
-        forward_all: bool,
+        forwarding_config: &ForwardingConfig,
+        sessions: &SessionTable,
     ) -> RelayerResult<()> {
         let mut highest_slot = Slot::default();
 
@@ -584,10 +1100,9 @@ impl RelayerImpl {
             slot_receiver.capacity().unwrap(),
             subscription_receiver.capacity().unwrap(),
             delay_packet_receiver.capacity().unwrap(),
+            forwarding_senders.len(),
         );
 
-        let mut slot_leaders = HashSet::new();
-
         while !exit.load(Ordering::Relaxed) {
             crossbeam_channel::select! {
                 recv(slot_receiver) -> maybe_slot => {
@@ -595,20 +1110,26 @@ impl RelayerImpl {
 
                     Self::update_highest_slot(maybe_slot, &mut highest_slot, &mut relayer_metrics)?;
 
-                    let slots: Vec<_> = (highest_slot..highest_slot + slot_lookahead).collect();
-                    slot_leaders = leader_schedule_cache.leaders_for_slots(&slots);
+                    let leader_window = leader_schedule_cache.leader_window(highest_slot, forwarding_config.max_slot_lookahead());
+                    leader_tracker.write().unwrap().update(leader_window);
 
                     let _ = relayer_metrics.crossbeam_slot_receiver_processing_us.increment(start.elapsed().as_micros() as u64);
                 },
                 recv(delay_packet_receiver) -> maybe_packet_batches => {
                     let start = Instant::now();
-                    let failed_forwards = Self::forward_packets(maybe_packet_batches, packet_subscriptions, &slot_leaders, &mut relayer_metrics, &ofac_addresses, &address_lookup_table_cache, validator_packet_batch_size, forward_all)?;
-                    Self::drop_connections(failed_forwards, packet_subscriptions, &mut relayer_metrics);
+                    let packet_batches = maybe_packet_batches?;
+                    let _ = relayer_metrics.packet_latencies_us.increment(packet_batches.stamp.elapsed().as_micros() as u64);
+
+                    // nothing to filter packets for without subscribers
+                    if !packet_subscriptions.is_empty() {
+                        let packet_batch = Self::filter_packets(&packet_batches, &mut relayer_metrics, &ofac_filter, &address_lookup_table_cache);
+                        Self::send_to_forwarding_threads(&forwarding_senders, ForwardingMessage::Packets(Arc::new(packet_batch)), &mut relayer_metrics)?;
+                    }
                     let _ = relayer_metrics.crossbeam_delay_packet_receiver_processing_us.increment(start.elapsed().as_micros() as u64);
                 },
                 recv(subscription_receiver) -> maybe_subscription => {
                     let start = Instant::now();
-                    Self::handle_subscription(maybe_subscription, packet_subscriptions, &mut relayer_metrics)?;
+                    Self::handle_subscription(maybe_subscription, packet_subscriptions, validator_packet_stats, sessions, &mut relayer_metrics)?;
                     let _ = relayer_metrics.crossbeam_subscription_receiver_processing_us.increment(start.elapsed().as_micros() as u64);
                 }
                 recv(heartbeat_tick) -> time_generated => {
@@ -618,35 +1139,41 @@ impl RelayerImpl {
                     }
 
                     // heartbeat if state is healthy, drop all connections on unhealthy
-                    let pubkeys_to_drop = match *health_state.read().unwrap() {
+                    let state = *health_state.read().unwrap();
+                    match state {
                         HealthState::Healthy => {
-                            Self::handle_heartbeat(
-                                packet_subscriptions,
-                                &mut relayer_metrics,
-                            )
-                        },
-                        HealthState::Unhealthy => packet_subscriptions.read().unwrap().keys().cloned().collect(),
-                    };
-                    Self::drop_connections(pubkeys_to_drop, packet_subscriptions, &mut relayer_metrics);
+                            Self::send_to_forwarding_threads(&forwarding_senders, ForwardingMessage::Heartbeat(relayer_metrics.num_heartbeats), &mut relayer_metrics)?;
+                            relayer_metrics.num_heartbeats += 1;
+                        }
+                        HealthState::Unhealthy => {
+                            let mut forwarding_metrics = ForwardingMetrics::default();
+                            let disconnected = packet_subscriptions.senders().into_iter().map(|(pubkey, sender)| (pubkey, sender, SubscriptionCloseReason::RelayerUnhealthy)).collect();
+                            Self::drop_connections(disconnected, packet_subscriptions, validator_packet_stats, sessions, &mut forwarding_metrics);
+                            relayer_metrics.merge_forwarding_metrics(forwarding_metrics);
+                        }
+                    }
                     let _ = relayer_metrics.crossbeam_heartbeat_tick_processing_us.increment(start.elapsed().as_micros() as u64);
                 }
                 recv(metrics_tick) -> time_generated => {
                     let start = Instant::now();
-                    let l_packet_subscriptions = packet_subscriptions.read().unwrap();
-                    relayer_metrics.num_current_connections = l_packet_subscriptions.len() as u64;
-                    relayer_metrics.update_packet_subscription_total_capacity(&l_packet_subscriptions);
-                    drop(l_packet_subscriptions);
+                    relayer_metrics.num_current_connections = packet_subscriptions.len() as u64;
+                    relayer_metrics.update_packet_subscription_total_capacity(packet_subscriptions);
+                    for forwarding_metrics in forwarding_metrics_receiver.try_iter() {
+                        relayer_metrics.merge_forwarding_metrics(forwarding_metrics);
+                    }
 
                     if let Ok(time_generated) = time_generated {
                         relayer_metrics.metrics_latency_us = time_generated.elapsed().as_micros() as u64;
                     }
                     let _ = relayer_metrics.crossbeam_metrics_tick_processing_us.increment(start.elapsed().as_micros() as u64);
 
+                    Self::accumulate_validator_packet_stats(&relayer_metrics, validator_packet_stats);
                     relayer_metrics.report();
                     relayer_metrics = RelayerMetrics::new(
                         slot_receiver.capacity().unwrap(),
                         subscription_receiver.capacity().unwrap(),
                         delay_packet_receiver.capacity().unwrap(),
+                        forwarding_senders.len(),
                     );
                 }
             }
@@ -660,184 +1187,483 @@ impl RelayerImpl {
         Ok(())
     }
 
+    /// Forwards packets and heartbeats to the validators in one shard of the subscriptions.
+    /// Exits with the event loop, which owns the other end of `receiver`.
+    #[allow(clippy::too_many_arguments)]
+    fn run_forwarding_loop(
+        shard: usize,
+        receiver: Receiver<ForwardingMessage>,
+        metrics_sender: Sender<ForwardingMetrics>,
+        exit: &AtomicBool,
+        packet_subscriptions: &PacketSubscriptions,
+        validator_packet_stats: &ValidatorPacketStats,
+        leader_tracker: &RwLock<LeaderTracker>,
+        forwarding_config: &ForwardingConfig,
+        sessions: &SessionTable,
+    ) -> RelayerResult<()> {
+        let subscriptions = packet_subscriptions.shard(shard);
+        let metrics_tick = crossbeam_channel::tick(Duration::from_millis(1000));
+        let mut forwarding_metrics = ForwardingMetrics::default();
+
+        // batches still owed to validators whose packet delay is longer than the batch has been
+        // held, with the longest delay they've been forwarded for so far
+        let mut delayed_packet_batches: VecDeque<(Arc<FilteredPacketBatch>, u32)> = VecDeque::new();
+        let delayed_packet_batches_tick = crossbeam_channel::tick(Duration::from_millis(5));
+
+        while !exit.load(Ordering::Relaxed) {
+            crossbeam_channel::select! {
+                recv(receiver) -> maybe_message => {
+                    let failed_forwards = match maybe_message? {
+                        ForwardingMessage::Packets(packet_batch) => {
+                            let held_ms = packet_batch.stamp.elapsed().as_millis() as u32;
+                            let failed_forwards = Self::forward_packets(&packet_batch, 0..=held_ms, subscriptions, leader_tracker, &mut forwarding_metrics, forwarding_config);
+                            if held_ms < forwarding_config.max_packet_delay_ms() {
+                                delayed_packet_batches.push_back((packet_batch, held_ms));
+                            }
+                            failed_forwards
+                        }
+                        ForwardingMessage::Heartbeat(count) => Self::handle_heartbeat(count, subscriptions, &mut forwarding_metrics),
+                    };
+                    Self::drop_connections(failed_forwards, packet_subscriptions, validator_packet_stats, sessions, &mut forwarding_metrics);
+                },
+                recv(delayed_packet_batches_tick) -> _ => {
+                    if delayed_packet_batches.is_empty() {
+                        continue;
+                    }
+                    let failed_forwards = Self::forward_delayed_packets(&mut delayed_packet_batches, subscriptions, leader_tracker, &mut forwarding_metrics, forwarding_config);
+                    Self::drop_connections(failed_forwards, packet_subscriptions, validator_packet_stats, sessions, &mut forwarding_metrics);
+                },
+                recv(metrics_tick) -> _ => {
+                    forwarding_metrics.num_slow_subscribers = Self::report_slow_subscribers(subscriptions);
+                    // only fails if the event loop fell behind or exited
+                    let _ = metrics_sender.try_send(std::mem::take(&mut forwarding_metrics));
+                }
+            }
+
+            forwarding_metrics.receiver_max_len =
+                std::cmp::max(forwarding_metrics.receiver_max_len, receiver.len());
+            forwarding_metrics.delayed_packet_batches_max_len = std::cmp::max(
+                forwarding_metrics.delayed_packet_batches_max_len,
+                delayed_packet_batches.len(),
+            );
+        }
+        Ok(())
+    }
+
+    /// Hands the message to every forwarding thread without blocking the event loop. A thread
+    /// whose queue is full misses the message, which is counted in its drops.
+    fn send_to_forwarding_threads(
+        forwarding_senders: &[Sender<ForwardingMessage>],
+        message: ForwardingMessage,
+        relayer_metrics: &mut RelayerMetrics,
+    ) -> RelayerResult<()> {
+        for (forwarding_sender, drops) in forwarding_senders
+            .iter()
+            .zip(relayer_metrics.forwarding_drops.iter_mut())
+        {
+            match forwarding_sender.try_send(message.clone()) {
+                Ok(()) => {}
+                Err(crossbeam_channel::TrySendError::Full(message)) => {
+                    drops.num_messages += 1;
+                    if let ForwardingMessage::Packets(packet_batch) = message {
+                        drops.num_packets += packet_batch.packets.len() as u64;
+                    }
+                }
+                Err(crossbeam_channel::TrySendError::Disconnected(_)) => {
+                    return Err(RelayerError::ForwardingThreadExited)
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Folds this period's per-validator counts into the totals exposed through [`RelayerHandle`]
+    fn accumulate_validator_packet_stats(
+        relayer_metrics: &RelayerMetrics,
+        validator_packet_stats: &ValidatorPacketStats,
+    ) {
+        let mut l_stats = validator_packet_stats.write().unwrap();
+        for (pubkey, stats) in &relayer_metrics.packet_stats_per_validator {
+            // entries only exist for validators that are still subscribed
+            if let Some(total) = l_stats.get_mut(pubkey) {
+                saturating_add_assign!(total.num_packets_forwarded, stats.num_packets_forwarded);
+                saturating_add_assign!(total.num_packets_dropped, stats.num_packets_dropped);
+            }
+        }
+    }
+
+    /// Removes the subscriptions fed by the given senders. Subscriptions the validator has
+    /// replaced since the sender failed are left alone.
     fn drop_connections(
-        disconnected_pubkeys: Vec<Pubkey>,
+        disconnected: Vec<(Pubkey, SubscriberSender, SubscriptionCloseReason)>,
         subscriptions: &PacketSubscriptions,
-        relayer_metrics: &mut RelayerMetrics,
+        validator_packet_stats: &ValidatorPacketStats,
+        sessions: &SessionTable,
+        forwarding_metrics: &mut ForwardingMetrics,
     ) {
-        relayer_metrics.num_removed_connections += disconnected_pubkeys.len() as u64;
-
-        let mut l_subscriptions = subscriptions.write().unwrap();
-        for disconnected in disconnected_pubkeys {
-            if let Some(sender) = l_subscriptions.remove(&disconnected) {
-                datapoint_info!(
-                    "relayer_removed_subscription",
-                    ("pubkey", disconnected.to_string(), String)
-                );
-                drop(sender);
+        if disconnected.is_empty() {
+            return;
+        }
+
+        let mut l_stats = validator_packet_stats.write().unwrap();
+        for (pubkey, sender, reason) in disconnected {
+            let Some(removed) = subscriptions.remove_if_current(&pubkey, &sender) else {
+                continue;
+            };
+            forwarding_metrics.num_removed_connections += 1;
+            if reason == SubscriptionCloseReason::TooSlow {
+                forwarding_metrics.num_slow_subscriber_disconnects += 1;
             }
+            l_stats.remove(&pubkey);
+            datapoint_info!(
+                "relayer_removed_subscription",
+                ("pubkey", pubkey.to_string(), String)
+            );
+            sessions.subscription_closed(&pubkey, reason);
+            drop(removed);
         }
     }
 
     fn handle_heartbeat(
-        subscriptions: &PacketSubscriptions,
-        relayer_metrics: &mut RelayerMetrics,
-    ) -> Vec<Pubkey> {
-        let failed_pubkey_updates = subscriptions
+        count: u64,
+        subscriptions: &SubscriptionShard,
+        forwarding_metrics: &mut ForwardingMetrics,
+    ) -> Vec<(Pubkey, SubscriberSender, SubscriptionCloseReason)> {
+        let heartbeat = encode_message(&SubscribePacketsResponse {
+            header: None,
+            msg: Some(subscribe_packets_response::Msg::Heartbeat(Heartbeat {
+                count,
+            })),
+        });
+        subscriptions
             .read()
             .unwrap()
             .iter()
             .filter_map(|(pubkey, sender)| {
-                // try send because it's a bounded channel and we don't want to block if the channel is full
-                match sender.try_send(Ok(SubscribePacketsResponse {
-                    header: None,
-                    msg: Some(subscribe_packets_response::Msg::Heartbeat(Heartbeat {
-                        count: relayer_metrics.num_heartbeats,
-                    })),
-                })) {
+                // heartbeats never push out packets, and slow validators are only disconnected
+                // by the packets they fall behind on
+                match sender.try_send(
+                    heartbeat.clone(),
+                    0,
+                    Backpressure::DropNewest,
+                    Duration::MAX,
+                ) {
                     Ok(_) => {}
-                    Err(TrySendError::Closed(_)) => return Some(*pubkey),
-                    Err(TrySendError::Full(_)) => {
-                        relayer_metrics.num_try_send_channel_full += 1;
-                        warn!("heartbeat channel is full for: {:?}", pubkey);
+                    Err(TrySendError::Closed) => {
+                        return Some((*pubkey, sender.clone(), SubscriptionCloseReason::SendFailed))
+                    }
+                    Err(TrySendError::Full | TrySendError::TooSlow) => {
+                        forwarding_metrics.num_try_send_channel_full += 1;
                     }
                 }
                 None
             })
-            .collect();
+            .collect()
+    }
 
-        relayer_metrics.num_heartbeats += 1;
+    /// Reports validators whose queue is at least [`Self::SLOW_SUBSCRIBER_FILL_RATIO`] full,
+    /// returning how many there are
+    fn report_slow_subscribers(subscriptions: &SubscriptionShard) -> u64 {
+        let mut num_slow_subscribers = 0;
+        for (pubkey, sender) in subscriptions.read().unwrap().iter() {
+            let fill_ratio = sender.fill_ratio();
+            if fill_ratio < Self::SLOW_SUBSCRIBER_FILL_RATIO {
+                continue;
+            }
+            num_slow_subscribers += 1;
 
-        failed_pubkey_updates
+            let full_for = sender.full_for();
+            if let Some(full_for) = full_for {
+                warn!("packet queue has been full for {full_for:?} for: {pubkey:?}");
+            }
+            datapoint_info!(
+                "relayer_slow_subscriber",
+                "pubkey" => pubkey.to_string(),
+                ("queue_len", sender.len(), i64),
+                ("queue_capacity", sender.capacity(), i64),
+                ("fill_ratio", fill_ratio, f64),
+                (
+                    "full_for_ms",
+                    full_for.map_or(0, |full_for| full_for.as_millis() as i64),
+                    i64
+                ),
+            );
+        }
+        num_slow_subscribers
     }
 
-    /// Returns pubkeys of subscribers that failed to send
-    fn forward_packets(
-        maybe_packet_batches: Result<RelayerPacketBatches, RecvError>,
-        subscriptions: &PacketSubscriptions,
-        slot_leaders: &HashSet<Pubkey>,
+    /// Drops discarded packets and packets the OFAC filter rejects, converting the rest to
+    /// proto packets for the forwarding threads. Packets are ordered by compute unit price,
+    /// highest first, so they're the first queued for each validator and the last dropped.
+    fn filter_packets(
+        packet_batches: &RelayerPacketBatches,
         relayer_metrics: &mut RelayerMetrics,
-        ofac_addresses: &HashSet<Pubkey>,
+        ofac_filter: &OfacFilter,
         address_lookup_table_cache: &Arc<DashMap<Pubkey, AddressLookupTableAccount>>,
-        validator_packet_batch_size: usize,
-        forward_all: bool,
-    ) -> RelayerResult<Vec<Pubkey>> {
-        let packet_batches = maybe_packet_batches?;
-
-        let _ = relayer_metrics
-            .packet_latencies_us
-            .increment(packet_batches.stamp.elapsed().as_micros() as u64);
+    ) -> FilteredPacketBatch {
+        // snapshot the sanctions list so a concurrent reload doesn't apply mid-batch
+        let ofac_addresses = ofac_filter.addresses().load();
 
         // remove discards + check for OFAC before forwarding
-        let packets: Vec<_> = packet_batches
-            .banking_packet_batch
-            .0
+        let mut packets = Vec::new();
+        for packet in packet_batches
+            .banking_packet_batches
             .iter()
-            .flat_map(|batch| {
-                batch
-                    .iter()
-                    .filter(|p| !p.meta().discard())
-                    .filter_map(|packet| {
-                        if !ofac_addresses.is_empty() {
-                            let tx: VersionedTransaction = packet.deserialize_slice(..).ok()?;
-                            if !is_tx_ofac_related(&tx, ofac_addresses, address_lookup_table_cache)
-                            {
-                                Some(packet)
-                            } else {
-                                None
-                            }
-                        } else {
-                            Some(packet)
-                        }
-                    })
-                    .filter_map(packet_to_proto_packet)
-            })
+            .flat_map(|banking_packet_batch| banking_packet_batch.0.iter())
+            .flat_map(|batch| batch.iter())
+            .filter(|p| !p.meta().discard())
+        {
+            let tx = packet.deserialize_slice::<VersionedTransaction, _>(..).ok();
+            if !ofac_addresses.is_empty() {
+                let Some(tx) = &tx else {
+                    continue;
+                };
+                let verdict = ofac_filter.verdict(tx, &ofac_addresses, address_lookup_table_cache);
+                if ofac_filter.should_drop(&verdict) {
+                    relayer_metrics.ofac_drops.increment(&verdict);
+                    ofac_filter.audit("relayer", tx, &verdict);
+                    continue;
+                }
+            }
+            let limits = tx.as_ref().and_then(ComputeBudgetLimits::from_transaction);
+            if limits.is_some_and(|limits| limits.compute_unit_price > 0) {
+                relayer_metrics.num_priority_fee_packets += 1;
+            }
+            // packets that can't be read are forwarded last, as if they paid nothing
+            let priority = limits.map_or(0, |limits| limits.priority());
+            if let Some(packet) = packet_to_proto_packet(packet) {
+                packets.push((priority, packet));
+            }
+        }
+        // stable, so packets paying the same price keep their arrival order
+        packets.sort_by_key(|(priority, _)| Reverse(*priority));
+
+        FilteredPacketBatch {
+            stamp: packet_batches.stamp,
+            packets: packets.into_iter().map(|(_, packet)| packet).collect(),
+        }
+    }
+
+    /// Forwards held batches to validators whose packet delay has elapsed since the last pass,
+    /// dropping batches once every delay has been served. Returns subscribers to disconnect and
+    /// why
+    fn forward_delayed_packets(
+        delayed_packet_batches: &mut VecDeque<(Arc<FilteredPacketBatch>, u32)>,
+        subscriptions: &SubscriptionShard,
+        leader_tracker: &RwLock<LeaderTracker>,
+        forwarding_metrics: &mut ForwardingMetrics,
+        forwarding_config: &ForwardingConfig,
+    ) -> Vec<(Pubkey, SubscriberSender, SubscriptionCloseReason)> {
+        let packet_delays_ms: HashSet<u32> = forwarding_config
+            .validator_policies()
+            .packet_delays_ms()
+            .chain([forwarding_config.packet_delay_ms()])
             .collect();
 
-        let mut proto_packet_batches =
-            Vec::with_capacity(packets.len() / validator_packet_batch_size);
-        for packet_chunk in packets.chunks(validator_packet_batch_size) {
-            proto_packet_batches.push(ProtoPacketBatch {
-                packets: packet_chunk.to_vec(),
-            });
+        let mut failed_forwards = Vec::new();
+        for (packet_batch, forwarded_through_ms) in delayed_packet_batches.iter_mut() {
+            let held_ms = packet_batch.stamp.elapsed().as_millis() as u32;
+            let delay_window = forwarded_through_ms.saturating_add(1)..=held_ms;
+            // avoid walking the subscriptions when no validator's delay elapsed since last pass
+            if !packet_delays_ms
+                .iter()
+                .any(|packet_delay_ms| delay_window.contains(packet_delay_ms))
+            {
+                continue;
+            }
+
+            failed_forwards.extend(Self::forward_packets(
+                packet_batch,
+                delay_window,
+                subscriptions,
+                leader_tracker,
+                forwarding_metrics,
+                forwarding_config,
+            ));
+            *forwarded_through_ms = held_ms;
         }
 
-        let l_subscriptions = subscriptions.read().unwrap();
+        let max_packet_delay_ms = forwarding_config.max_packet_delay_ms();
+        delayed_packet_batches.retain(|(packet_batch, _)| {
+            packet_batch.stamp.elapsed().as_millis() < max_packet_delay_ms as u128
+        });
 
-        let senders = if forward_all {
-            l_subscriptions.iter().collect::<Vec<(
-                &Pubkey,
-                &TokioSender<Result<SubscribePacketsResponse, Status>>,
-            )>>()
-        } else {
-            slot_leaders
+        failed_forwards
+    }
+
+    /// Forwards the batch to the subscribers whose packet delay falls within `delay_window`,
+    /// given how long the batch has been held, in [`LeaderTracker::forwarding_rank`] order.
+    /// Returns subscribers to disconnect and why
+    fn forward_packets(
+        packet_batch: &FilteredPacketBatch,
+        delay_window: RangeInclusive<u32>,
+        subscriptions: &SubscriptionShard,
+        leader_tracker: &RwLock<LeaderTracker>,
+        forwarding_metrics: &mut ForwardingMetrics,
+        forwarding_config: &ForwardingConfig,
+    ) -> Vec<(Pubkey, SubscriberSender, SubscriptionCloseReason)> {
+        let params = forwarding_config.params();
+        let validator_policies = forwarding_config.validator_policies();
+
+        // a validator receives packets if it has a forwarding rank, and only once the batch has
+        // been held for that validator's packet delay
+        let mut senders: Vec<(usize, Pubkey, SubscriberSender, ForwardingParams)> = {
+            let leader_tracker = leader_tracker.read().unwrap();
+            subscriptions
+                .read()
+                .unwrap()
                 .iter()
-                .filter_map(|pubkey| l_subscriptions.get(pubkey).map(|sender| (pubkey, sender)))
+                .filter_map(|(pubkey, sender)| {
+                    let params = params.with_policy(validator_policies.get(pubkey));
+                    if !delay_window.contains(&params.packet_delay_ms) {
+                        return None;
+                    }
+                    let forwarding_rank = leader_tracker.forwarding_rank(pubkey, &params)?;
+                    Some((forwarding_rank, *pubkey, sender.clone(), params))
+                })
                 .collect()
         };
+        if senders.is_empty() {
+            return Vec::new();
+        }
+        senders.sort_unstable_by_key(|(forwarding_rank, ..)| *forwarding_rank);
 
-        let mut failed_forwards = Vec::new();
-        for batch in &proto_packet_batches {
-            // NOTE: this is important to avoid divide-by-0 inside the validator if packets
-            // get routed to sigverify under the assumption theres > 0 packets in the batch
-            if batch.packets.is_empty() {
-                continue;
-            }
+        // validators can use different batch sizes, so encode the responses once per size and
+        // share the encoded bytes between validators
+        let ts = Timestamp::from(SystemTime::now());
+        let mut responses: HashMap<usize, Vec<(u64, Bytes)>> = HashMap::new();
+        for (_, _, _, params) in &senders {
+            let validator_packet_batch_size = params.validator_packet_batch_size;
+            responses
+                .entry(validator_packet_batch_size)
+                .or_insert_with(|| {
+                    packet_batch
+                        .packets
+                        // NOTE: chunks are never empty, which is important to avoid divide-by-0
+                        // inside the validator if packets get routed to sigverify under the
+                        // assumption theres > 0 packets in the batch
+                        .chunks(validator_packet_batch_size)
+                        .map(|packet_chunk| {
+                            let response = SubscribePacketsResponse {
+                                header: Some(Header {
+                                    ts: Some(ts.clone()),
+                                }),
+                                msg: Some(subscribe_packets_response::Msg::Batch(
+                                    ProtoPacketBatch {
+                                        packets: packet_chunk.to_vec(),
+                                    },
+                                )),
+                            };
+                            (packet_chunk.len() as u64, encode_message(&response))
+                        })
+                        .collect()
+                });
+        }
 
-            for (pubkey, sender) in &senders {
-                // try send because it's a bounded channel and we don't want to block if the channel is full
-                match sender.try_send(Ok(SubscribePacketsResponse {
-                    header: Some(Header {
-                        ts: Some(Timestamp::from(SystemTime::now())),
-                    }),
-                    msg: Some(subscribe_packets_response::Msg::Batch(batch.clone())),
-                })) {
-                    Ok(_) => {
-                        relayer_metrics
-                            .increment_packets_forwarded(pubkey, batch.packets.len() as u64);
+        // send everything to a validator before moving to the next so the current leader gets
+        // packets first
+        let mut failed_forwards = Vec::new();
+        for (_, pubkey, sender, params) in &senders {
+            let slow_subscriber_timeout =
+                Duration::from_millis(params.slow_subscriber_timeout_ms as u64);
+            let responses = Self::queue_order(
+                &responses[&params.validator_packet_batch_size],
+                params.backpressure,
+            );
+            for (num_packets, response) in responses {
+                // try send because it's a bounded queue and we don't want to block if it's full;
+                // slow validators are reported by the metrics tick instead of logging every drop
+                match sender.try_send(
+                    response.clone(),
+                    *num_packets,
+                    params.backpressure,
+                    slow_subscriber_timeout,
+                ) {
+                    Ok(num_dropped_packets) => {
+                        forwarding_metrics.increment_packets_forwarded(pubkey, *num_packets);
+                        if num_dropped_packets > 0 {
+                            forwarding_metrics
+                                .increment_packets_dropped(pubkey, num_dropped_packets);
+                        }
                     }
-                    Err(TrySendError::Full(_)) => {
-                        error!("packet channel is full for pubkey: {:?}", pubkey);
-                        relayer_metrics
-                            .increment_packets_dropped(pubkey, batch.packets.len() as u64);
+                    Err(TrySendError::Full) => {
+                        forwarding_metrics.increment_packets_dropped(pubkey, *num_packets);
                     }
-                    Err(TrySendError::Closed(_)) => {
+                    Err(TrySendError::Closed) => {
                         error!("channel is closed for pubkey: {:?}", pubkey);
-                        failed_forwards.push(**pubkey);
+                        failed_forwards.push((
+                            *pubkey,
+                            sender.clone(),
+                            SubscriptionCloseReason::SendFailed,
+                        ));
+                        break;
+                    }
+                    Err(TrySendError::TooSlow) => {
+                        warn!(
+                            "disconnecting slow subscriber, queue full for over {}ms: {:?}",
+                            params.slow_subscriber_timeout_ms, pubkey
+                        );
+                        failed_forwards.push((
+                            *pubkey,
+                            sender.clone(),
+                            SubscriptionCloseReason::TooSlow,
+                        ));
                         break;
                     }
                 }
             }
         }
-        Ok(failed_forwards)
+        failed_forwards
+    }
+
+    /// Orders a packet batch's responses, highest fee first, for queueing to a validator. A
+    /// drop-oldest queue pushes out its front when it's full, so the responses are queued lowest
+    /// fee first there and a batch's cheapest packets are pushed out before its priciest.
+    fn queue_order(responses: &[(u64, Bytes)], backpressure: Backpressure) -> Vec<&(u64, Bytes)> {
+        if backpressure == Backpressure::DropOldest {
+            responses.iter().rev().collect()
+        } else {
+            responses.iter().collect()
+        }
     }
 
     fn handle_subscription(
         maybe_subscription: Result<Subscription, RecvError>,
         subscriptions: &PacketSubscriptions,
+        validator_packet_stats: &ValidatorPacketStats,
+        sessions: &SessionTable,
         relayer_metrics: &mut RelayerMetrics,
     ) -> RelayerResult<()> {
         match maybe_subscription? {
-            Subscription::ValidatorPacketSubscription { pubkey, sender } => {
-                match subscriptions.write().unwrap().entry(pubkey) {
-                    Entry::Vacant(entry) => {
-                        entry.insert(sender);
+            Subscription::ValidatorPacketSubscription {
+                pubkey,
+                client_ip,
+                sender,
+            } => {
+                // totals restart with every new subscription
+                validator_packet_stats
+                    .write()
+                    .unwrap()
+                    .insert(pubkey, PacketForwardStats::default());
 
+                match subscriptions.insert(pubkey, sender) {
+                    None => {
                         relayer_metrics.num_added_connections += 1;
                         datapoint_info!(
                             "relayer_new_subscription",
                             ("pubkey", pubkey.to_string(), String)
                         );
                     }
-                    Entry::Occupied(mut entry) => {
+                    Some(_) => {
                         datapoint_info!(
                             "relayer_duplicate_subscription",
                             ("pubkey", pubkey.to_string(), String)
                         );
                         error!("already connected, dropping old connection: {pubkey:?}");
-                        entry.insert(sender);
+                        sessions.subscription_closed(&pubkey, SubscriptionCloseReason::Replaced);
                     }
                 }
+                sessions.subscription_opened(&pubkey, client_ip);
             }
         }
         Ok(())
@@ -892,7 +1718,7 @@ impl Relayer for RelayerImpl {
         }));
     }
 
-    type SubscribePacketsStream = ReceiverStream<Result<SubscribePacketsResponse, Status>>;
+    type SubscribePacketsStream = SubscriberReceiver;
 
     /// Validator calls this to subscribe to packets
     async fn subscribe_packets(
@@ -906,13 +1732,154 @@ impl Relayer for RelayerImpl {
             .get()
             .ok_or_else(|| Status::internal("internal error fetching public key"))?;
 
-        let (sender, receiver) = channel(RelayerImpl::SUBSCRIBER_QUEUE_CAPACITY);
+        if self.handle().is_banned(pubkey) {
+            return Err(Status::permission_denied("validator is temporarily banned"));
+        }
+
+        let client_ip = request.remote_addr().map(|addr| addr.ip());
+        let (sender, receiver) = subscriber_queue(RelayerImpl::SUBSCRIBER_QUEUE_CAPACITY);
         self.subscription_sender
             .send(Subscription::ValidatorPacketSubscription {
                 pubkey: *pubkey,
+                client_ip,
                 sender,
             })
             .map_err(|_| Status::internal("internal error adding subscription"))?;
-        Ok(Response::new(ReceiverStream::new(receiver)))
+        Ok(Response::new(receiver))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use jito_protos::{
+        encoded::encode_message,
+        relayer::{subscribe_packets_response, SubscribePacketsResponse},
+        shared::Heartbeat,
+    };
+    use solana_sdk::pubkey::Pubkey;
+    use tokio_stream::StreamExt;
+
+    use crate::{
+        auth_events::SubscriptionCloseReason,
+        relayer::{
+            ForwardingMetrics, PacketForwardStats, PacketSubscriptions, RelayerImpl,
+            ValidatorPacketStats,
+        },
+        sessions::SessionTable,
+        subscriber_queue::{subscriber_queue, Backpressure},
+    };
+
+    #[test]
+    fn test_packet_subscriptions_shards() {
+        let subscriptions = PacketSubscriptions::new(2);
+        let (a, b, c) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+
+        // new validators go to the smallest shard
+        for pubkey in [a, b, c] {
+            assert!(subscriptions
+                .insert(pubkey, subscriber_queue(1).0)
+                .is_none());
+        }
+        assert_eq!(subscriptions.shard(0).read().unwrap().len(), 2);
+        assert!(subscriptions.shard(1).read().unwrap().contains_key(&b));
+
+        // resubscribing replaces the subscription in place
+        assert!(subscriptions.insert(b, subscriber_queue(1).0).is_some());
+        assert!(subscriptions.shard(1).read().unwrap().contains_key(&b));
+        assert_eq!(subscriptions.len(), 3);
+
+        assert!(subscriptions.remove(&a).is_some());
+        assert!(subscriptions.remove(&a).is_none());
+        let mut pubkeys = subscriptions.pubkeys();
+        pubkeys.sort();
+        let mut expected = vec![b, c];
+        expected.sort();
+        assert_eq!(pubkeys, expected);
+    }
+
+    #[test]
+    fn test_drop_connections_after_resubscribe() {
+        let subscriptions = PacketSubscriptions::new(1);
+        let validator_packet_stats = ValidatorPacketStats::default();
+        let sessions = SessionTable::default();
+        let pubkey = Pubkey::new_unique();
+
+        let (failed_sender, _failed_receiver) = subscriber_queue(1);
+        subscriptions.insert(pubkey, failed_sender.clone());
+        // the validator resubscribes after a send on its old stream failed, but before the
+        // failure is handled
+        let (sender, _receiver) = subscriber_queue(1);
+        subscriptions.insert(pubkey, sender);
+        validator_packet_stats
+            .write()
+            .unwrap()
+            .insert(pubkey, PacketForwardStats::default());
+        sessions.subscription_opened(&pubkey, None);
+
+        let mut forwarding_metrics = ForwardingMetrics::default();
+        RelayerImpl::drop_connections(
+            vec![(pubkey, failed_sender, SubscriptionCloseReason::SendFailed)],
+            &subscriptions,
+            &validator_packet_stats,
+            &sessions,
+            &mut forwarding_metrics,
+        );
+        assert_eq!(subscriptions.len(), 1);
+        assert!(validator_packet_stats.read().unwrap().contains_key(&pubkey));
+        assert!(sessions.sessions()[0].subscribed_at_unix_ts.is_some());
+        assert_eq!(forwarding_metrics.num_removed_connections, 0);
+
+        // a failure of the current stream still removes it
+        let (_, current_sender) = subscriptions.senders().pop().unwrap();
+        RelayerImpl::drop_connections(
+            vec![(pubkey, current_sender, SubscriptionCloseReason::SendFailed)],
+            &subscriptions,
+            &validator_packet_stats,
+            &sessions,
+            &mut forwarding_metrics,
+        );
+        assert!(subscriptions.is_empty());
+        assert!(validator_packet_stats.read().unwrap().is_empty());
+        assert!(sessions.sessions().is_empty());
+        assert_eq!(forwarding_metrics.num_removed_connections, 1);
+    }
+
+    #[tokio::test]
+    async fn test_queue_order_drop_oldest() {
+        // responses for one packet batch, highest fee first, tagged by fee
+        let tagged = |fee: u64| {
+            encode_message(&SubscribePacketsResponse {
+                header: None,
+                msg: Some(subscribe_packets_response::Msg::Heartbeat(Heartbeat {
+                    count: fee,
+                })),
+            })
+        };
+        let responses = vec![(1, tagged(3)), (1, tagged(2)), (1, tagged(1))];
+
+        for backpressure in [Backpressure::DropNewest, Backpressure::DropOldest] {
+            let (sender, mut receiver) = subscriber_queue(2);
+            for (num_packets, response) in RelayerImpl::queue_order(&responses, backpressure) {
+                let _ =
+                    sender.try_send(response.clone(), *num_packets, backpressure, Duration::MAX);
+            }
+            drop(sender);
+
+            // either way the cheapest response is the one dropped
+            let mut queued = Vec::new();
+            while let Some(response) = receiver.next().await {
+                queued.push(response.unwrap());
+            }
+            queued.sort();
+            let mut expected = vec![tagged(3), tagged(2)];
+            expected.sort();
+            assert_eq!(queued, expected, "{backpressure:?}");
+        }
     }
 }
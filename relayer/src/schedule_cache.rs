@@ -11,16 +11,119 @@ use std::{
 };
 
 use jito_rpc::load_balancer::LoadBalancer;
-use log::{debug, error};
+use log::{debug, error, warn};
 use solana_metrics::datapoint_info;
 use solana_sdk::{
-    clock::{Slot, DEFAULT_SLOTS_PER_EPOCH},
+    clock::{Epoch, Slot, DEFAULT_SLOTS_PER_EPOCH, NUM_CONSECUTIVE_LEADER_SLOTS},
     pubkey::Pubkey,
 };
 
-pub struct LeaderScheduleCacheUpdater {
+/// A run of consecutive slots led by one validator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LeaderSlots {
+    pub leader: Pubkey,
+    pub first_slot: Slot,
+    pub last_slot: Slot,
+}
+
+/// Leaders around the current slot, used to decide which validators receive packets and in
+/// which order
+#[derive(Debug, Clone, Default)]
+pub struct LeaderWindow {
+    current_slot: Slot,
+    /// Leader whose slots ended before the current leader's began, if known
+    previous: Option<LeaderSlots>,
+    /// Current leader first, then upcoming leaders in slot order
+    upcoming: Vec<LeaderSlots>,
+    /// Position in `upcoming` of each leader's first run of slots
+    positions: HashMap<Pubkey, usize>,
+}
+
+impl LeaderWindow {
+    pub fn new(
+        current_slot: Slot,
+        previous: Option<LeaderSlots>,
+        upcoming: Vec<LeaderSlots>,
+    ) -> LeaderWindow {
+        let mut positions = HashMap::with_capacity(upcoming.len());
+        for (position, leader_slots) in upcoming.iter().enumerate() {
+            positions.entry(leader_slots.leader).or_insert(position);
+        }
+        LeaderWindow {
+            current_slot,
+            previous,
+            upcoming,
+            positions,
+        }
+    }
+
+    pub fn current_slot(&self) -> Slot {
+        self.current_slot
+    }
+
+    pub fn previous(&self) -> Option<&LeaderSlots> {
+        self.previous.as_ref()
+    }
+
+    pub fn upcoming(&self) -> &[LeaderSlots] {
+        &self.upcoming
+    }
+
+    /// Leader of the current slot, if it's in the schedule
+    pub fn current_leader(&self) -> Option<Pubkey> {
+        self.upcoming
+            .first()
+            .filter(|leader_slots| leader_slots.first_slot <= self.current_slot)
+            .map(|leader_slots| leader_slots.leader)
+    }
+
+    /// Returns the leader's first run of slots in the window and its position in leader order,
+    /// 0 being the current leader
+    pub fn upcoming_slots(&self, leader: &Pubkey) -> Option<(usize, &LeaderSlots)> {
+        let position = *self.positions.get(leader)?;
+        Some((position, &self.upcoming[position]))
+    }
+
+    /// Number of slots until the leader's first slot in the window, 0 if it's the current leader
+    pub fn slots_until_leader(&self, leader: &Pubkey) -> Option<u64> {
+        self.upcoming_slots(leader)
+            .map(|(_, leader_slots)| leader_slots.first_slot.saturating_sub(self.current_slot))
+    }
+}
+
+/// Leader schedule of the current epoch and, once it's known, the next one
+#[derive(Debug, Default)]
+pub struct LeaderSchedule {
+    /// Epoch the schedule starts at
+    epoch: Epoch,
     /// Maps slots to scheduled pubkey
-    schedules: Arc<RwLock<HashMap<Slot, Pubkey>>>,
+    slot_leaders: HashMap<Slot, Pubkey>,
+    /// Every validator with a slot in the schedule, so checking whether a validator is
+    /// scheduled doesn't scan every slot
+    leaders: HashSet<Pubkey>,
+    /// Whether the next epoch's slots are in the schedule
+    includes_next_epoch: bool,
+}
+
+impl LeaderSchedule {
+    pub fn new(
+        epoch: Epoch,
+        slot_leaders: HashMap<Slot, Pubkey>,
+        includes_next_epoch: bool,
+    ) -> LeaderSchedule {
+        let leaders = slot_leaders.values().copied().collect();
+        LeaderSchedule {
+            epoch,
+            slot_leaders,
+            leaders,
+            includes_next_epoch,
+        }
+    }
+}
+
+pub struct LeaderScheduleCacheUpdater {
+    /// Leader schedule of the current and next epochs
+    schedules: Arc<RwLock<LeaderSchedule>>,
 
     /// Refreshes leader schedule
     refresh_thread: JoinHandle<()>,
@@ -28,33 +131,96 @@ pub struct LeaderScheduleCacheUpdater {
 
 #[derive(Clone)]
 pub struct LeaderScheduleUpdatingHandle {
-    schedule: Arc<RwLock<HashMap<Slot, Pubkey>>>,
+    schedule: Arc<RwLock<LeaderSchedule>>,
 }
 
 /// Access handle to a constantly updating leader schedule
 impl LeaderScheduleUpdatingHandle {
-    pub fn new(schedule: Arc<RwLock<HashMap<Slot, Pubkey>>>) -> LeaderScheduleUpdatingHandle {
+    pub fn new(schedule: Arc<RwLock<LeaderSchedule>>) -> LeaderScheduleUpdatingHandle {
         LeaderScheduleUpdatingHandle { schedule }
     }
 
     pub fn leader_for_slot(&self, slot: &Slot) -> Option<Pubkey> {
-        self.schedule.read().unwrap().get(slot).cloned()
+        self.schedule
+            .read()
+            .unwrap()
+            .slot_leaders
+            .get(slot)
+            .cloned()
     }
 
     pub fn leaders_for_slots(&self, slots: &[Slot]) -> HashSet<Pubkey> {
-        let schedule = self.schedule.read().unwrap();
+        let l_schedule = self.schedule.read().unwrap();
+        let schedule = &l_schedule.slot_leaders;
         slots
             .iter()
             .filter_map(|s| schedule.get(s).cloned())
             .collect()
     }
 
+    /// Returns the leaders of the `num_slots` slots starting at `current_slot` in slot order,
+    /// along with the leader whose slots ended just before the current leader's began
+    pub fn leader_window(&self, current_slot: Slot, num_slots: u64) -> LeaderWindow {
+        let l_schedule = self.schedule.read().unwrap();
+        let schedule = &l_schedule.slot_leaders;
+
+        let mut upcoming: Vec<LeaderSlots> = Vec::new();
+        for slot in current_slot..current_slot.saturating_add(num_slots) {
+            let Some(leader) = schedule.get(&slot) else {
+                continue;
+            };
+            match upcoming.last_mut() {
+                Some(last) if last.leader == *leader && last.last_slot + 1 == slot => {
+                    last.last_slot = slot;
+                }
+                _ => upcoming.push(LeaderSlots {
+                    leader: *leader,
+                    first_slot: slot,
+                    last_slot: slot,
+                }),
+            }
+        }
+
+        let current_leader = schedule.get(&current_slot);
+        let mut previous: Option<LeaderSlots> = None;
+        for slot in
+            (current_slot.saturating_sub(2 * NUM_CONSECUTIVE_LEADER_SLOTS)..current_slot).rev()
+        {
+            let Some(leader) = schedule.get(&slot) else {
+                break;
+            };
+            match &mut previous {
+                Some(previous) if previous.leader == *leader => previous.first_slot = slot,
+                Some(_) => break,
+                None if Some(leader) == current_leader => {}
+                None => {
+                    previous = Some(LeaderSlots {
+                        leader: *leader,
+                        first_slot: slot,
+                        last_slot: slot,
+                    })
+                }
+            }
+        }
+
+        LeaderWindow::new(current_slot, previous, upcoming)
+    }
+
+    /// Number of slots in the cached leader schedule
+    pub fn num_slots(&self) -> usize {
+        self.schedule.read().unwrap().slot_leaders.len()
+    }
+
+    /// Refreshes the leader schedule from RPC now, outside of the periodic refresh.
+    /// Returns false if the RPC calls failed.
+    pub fn refresh(&self, load_balancer: &Arc<LoadBalancer>) -> bool {
+        LeaderScheduleCacheUpdater::update_leader_cache(load_balancer, &self.schedule, true)
+    }
+
+    /// Whether the validator has a slot in the current epoch or, once its schedule is known,
+    /// the next one, so next epoch's leaders can connect before the epoch boundary
     pub fn is_scheduled_validator(&self, pubkey: &Pubkey) -> bool {
-        self.schedule
-            .read()
-            .unwrap()
-            .iter()
-            .any(|(_, scheduled_pubkey)| scheduled_pubkey == pubkey)
+        self.schedule.read().unwrap().leaders.contains(pubkey)
     }
 }
 
@@ -63,7 +229,7 @@ impl LeaderScheduleCacheUpdater {
         load_balancer: &Arc<LoadBalancer>,
         exit: &Arc<AtomicBool>,
     ) -> LeaderScheduleCacheUpdater {
-        let schedules = Arc::new(RwLock::new(HashMap::new()));
+        let schedules = Arc::new(RwLock::new(LeaderSchedule::default()));
         let refresh_thread = Self::refresh_thread(schedules.clone(), load_balancer.clone(), exit);
         LeaderScheduleCacheUpdater {
             schedules,
@@ -81,7 +247,7 @@ impl LeaderScheduleCacheUpdater {
     }
 
     fn refresh_thread(
-        schedule: Arc<RwLock<HashMap<Slot, Pubkey>>>,
+        schedule: Arc<RwLock<LeaderSchedule>>,
         load_balancer: Arc<LoadBalancer>,
         exit: &Arc<AtomicBool>,
     ) -> JoinHandle<()> {
@@ -93,19 +259,22 @@ impl LeaderScheduleCacheUpdater {
                     let mut update_ok_count = 0;
                     let mut update_fail_count = 0;
 
-                    match Self::update_leader_cache(&load_balancer, &schedule) {
+                    match Self::update_leader_cache(&load_balancer, &schedule, false) {
                         true => update_ok_count += 1,
                         false => update_fail_count += 1,
                     }
 
-                    let slots_in_schedule = schedule.read().unwrap().len();
-
+                    let l_schedule = schedule.read().unwrap();
                     datapoint_info!(
                         "schedule-cache-update",
                         ("update_ok_count", update_ok_count, i64),
                         ("update_fail_count", update_fail_count, i64),
-                        ("slots_in_schedule", slots_in_schedule, i64),
+                        ("slots_in_schedule", l_schedule.slot_leaders.len(), i64),
+                        ("leaders_in_schedule", l_schedule.leaders.len(), i64),
+                        ("epoch", l_schedule.epoch, i64),
+                        ("includes_next_epoch", l_schedule.includes_next_epoch, bool),
                     );
+                    drop(l_schedule);
 
                     sleep(Duration::from_secs(10));
                 }
@@ -113,62 +282,177 @@ impl LeaderScheduleCacheUpdater {
             .unwrap()
     }
 
-    /// Fetches the current leader schedule from Solana RPC and updates the cache.
+    /// Fetches the current and next epochs' leader schedules from Solana RPC and updates the
+    /// cache.
     /// 
-    /// This method performs the actual RPC calls to get epoch info and leader schedule,
-    /// then converts the relative slot numbers to absolute slot numbers for easier lookup.
+    /// The leader schedule is fixed for an epoch, so the RPC calls for the schedules are only
+    /// made when the epoch changed or the next epoch's schedule is still missing.
     /// 
     /// # Process
     /// 1. Get current epoch info to determine slot offset
-    /// 2. Fetch leader schedule for current epoch
-    /// 3. Convert relative slots to absolute slots using epoch offset
+    /// 2. Fetch leader schedules for the current and next epochs
+    /// 3. Convert relative slots to absolute slots using each epoch's first slot
     /// 4. Update the shared schedule cache atomically
     /// 
     /// # Arguments
     /// * `load_balancer` - RPC client pool for network requests
     /// * `schedule` - Shared schedule cache to update
+    /// * `force` - Fetch the schedules even if the cached ones are up to date
     /// 
     /// # Returns
     /// `true` if update was successful, `false` if RPC calls failed
     pub fn update_leader_cache(
         load_balancer: &Arc<LoadBalancer>,
-        schedule: &Arc<RwLock<HashMap<Slot, Pubkey>>>,
+        schedule: &Arc<RwLock<LeaderSchedule>>,
+        force: bool,
     ) -> bool {
         // Get RPC client from load balancer (selects best available)
         let rpc_client = load_balancer.rpc_client();
 
         // First, get current epoch information
-        if let Ok(epoch_info) = rpc_client.get_epoch_info() {
-            // Then, get the leader schedule for current epoch
-            if let Ok(Some(leader_schedule)) = rpc_client.get_leader_schedule(None) {
-                // Calculate epoch start slot for converting relative to absolute slots
-                let epoch_offset = epoch_info.absolute_slot - epoch_info.slot_index;
-
-                debug!("read leader schedule of length: {}", leader_schedule.len());
-
-                // Build new schedule mapping with absolute slot numbers
-                let mut new_schedule = HashMap::with_capacity(DEFAULT_SLOTS_PER_EPOCH as usize);
-                for (pk_str, slots) in leader_schedule.iter() {
-                    // Parse validator pubkey from string
-                    if let Ok(pubkey) = Pubkey::from_str(pk_str) {
-                        // Convert each relative slot to absolute slot and add to mapping
-                        for slot in slots.iter() {
-                            new_schedule.insert(*slot as u64 + epoch_offset, pubkey);
-                        }
-                    }
-                }
-                
-                // Atomically replace the entire schedule cache
-                *schedule.write().unwrap() = new_schedule;
+        let Ok(epoch_info) = rpc_client.get_epoch_info() else {
+            error!("Couldn't Get Epoch Info from RPC!!!");
+            return false;
+        };
+        {
+            let l_schedule = schedule.read().unwrap();
+            if !force && l_schedule.epoch == epoch_info.epoch && l_schedule.includes_next_epoch {
+                return true;
+            }
+        }
 
-                return true; // Successful update
-            } else {
-                error!("Couldn't Get Leader Schedule Update from RPC!!!")
-            };
+        // Calculate epoch start slots for converting relative to absolute slots
+        let epoch_start_slot = epoch_info.absolute_slot - epoch_info.slot_index;
+        let next_epoch_start_slot = epoch_start_slot + epoch_info.slots_in_epoch;
+
+        let mut slot_leaders = HashMap::with_capacity(2 * DEFAULT_SLOTS_PER_EPOCH as usize);
+        // Then, get the leader schedule for current epoch
+        if let Ok(Some(leader_schedule)) = rpc_client.get_leader_schedule(Some(epoch_start_slot)) {
+            debug!("read leader schedule of length: {}", leader_schedule.len());
+            Self::insert_slot_leaders(&mut slot_leaders, &leader_schedule, epoch_start_slot);
         } else {
-            error!("Couldn't Get Epoch Info from RPC!!!")
+            error!("Couldn't Get Leader Schedule Update from RPC!!!");
+            return false;
+        }
+
+        // the next epoch's schedule is known an epoch ahead, but not every RPC node serves it
+        let includes_next_epoch = match rpc_client.get_leader_schedule(Some(next_epoch_start_slot))
+        {
+            Ok(Some(leader_schedule)) => {
+                Self::insert_slot_leaders(
+                    &mut slot_leaders,
+                    &leader_schedule,
+                    next_epoch_start_slot,
+                );
+                true
+            }
+            Ok(None) => false,
+            Err(e) => {
+                warn!("Couldn't get next epoch's leader schedule from RPC: {e}");
+                false
+            }
         };
-        
-        false // Failed to update
+
+        // Atomically replace the entire schedule cache
+        *schedule.write().unwrap() =
+            LeaderSchedule::new(epoch_info.epoch, slot_leaders, includes_next_epoch);
+        true
+    }
+
+    /// Adds an epoch's leader schedule, keyed by pubkey with slots relative to the epoch's
+    /// first slot, to the mapping of absolute slots to leaders
+    fn insert_slot_leaders(
+        slot_leaders: &mut HashMap<Slot, Pubkey>,
+        leader_schedule: &HashMap<String, Vec<usize>>,
+        epoch_start_slot: Slot,
+    ) {
+        for (pk_str, slots) in leader_schedule.iter() {
+            // Parse validator pubkey from string
+            if let Ok(pubkey) = Pubkey::from_str(pk_str) {
+                for slot in slots.iter() {
+                    slot_leaders.insert(*slot as u64 + epoch_start_slot, pubkey);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        sync::{Arc, RwLock},
+    };
+
+    use solana_sdk::pubkey::Pubkey;
+
+    use crate::schedule_cache::{LeaderSchedule, LeaderScheduleUpdatingHandle, LeaderSlots};
+
+    #[test]
+    fn test_leader_window() {
+        let (a, b, c) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let schedule: HashMap<_, _> = (0..4)
+            .map(|slot| (slot, a))
+            .chain((4..8).map(|slot| (slot, b)))
+            .chain((8..12).map(|slot| (slot, c)))
+            .collect();
+        let handle = LeaderScheduleUpdatingHandle::new(Arc::new(RwLock::new(LeaderSchedule::new(
+            0, schedule, false,
+        ))));
+
+        let window = handle.leader_window(5, 5);
+        assert_eq!(window.current_leader(), Some(b));
+        assert_eq!(
+            window.previous(),
+            Some(&LeaderSlots {
+                leader: a,
+                first_slot: 0,
+                last_slot: 3,
+            })
+        );
+        assert_eq!(
+            window.upcoming(),
+            &[
+                LeaderSlots {
+                    leader: b,
+                    first_slot: 5,
+                    last_slot: 7,
+                },
+                LeaderSlots {
+                    leader: c,
+                    first_slot: 8,
+                    last_slot: 9,
+                },
+            ]
+        );
+        assert_eq!(window.slots_until_leader(&b), Some(0));
+        assert_eq!(window.slots_until_leader(&c), Some(3));
+        assert_eq!(window.slots_until_leader(&a), None);
+    }
+
+    #[test]
+    fn test_is_scheduled_validator() {
+        let (current, next, unscheduled) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        // slots 0..4 are in the current epoch, 4..8 in the next
+        let schedule: HashMap<_, _> = (0..4)
+            .map(|slot| (slot, current))
+            .chain((4..8).map(|slot| (slot, next)))
+            .collect();
+        let handle = LeaderScheduleUpdatingHandle::new(Arc::new(RwLock::new(LeaderSchedule::new(
+            0, schedule, true,
+        ))));
+
+        assert!(handle.is_scheduled_validator(&current));
+        assert!(handle.is_scheduled_validator(&next));
+        assert!(!handle.is_scheduled_validator(&unscheduled));
+        assert_eq!(handle.leader_window(3, 2).upcoming().len(), 2);
     }
 }
@@ -0,0 +1,373 @@
+//! Optional TLS for the relayer gRPC server, with client certificate verification.
+//!
+//! Without TLS, JWT bearer tokens travel in plaintext. Given a certificate and private key, the
+//! relayer and auth services are served over TLS; given a client CA as well, clients also have
+//! to present a certificate signed by it (mTLS) on top of challenge/JWT auth, so private
+//! relayers can restrict connections to known validators.
+//!
+//! The [`ServerTlsUpdater`] reloads the files when they change. New connections use the
+//! reloaded certificates, established connections keep the ones they were accepted with.
+
+use std::{
+    fs::{self, File},
+    io::{self, BufReader},
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
+    thread::{self, sleep, Builder, JoinHandle},
+    time::{Duration, Instant, SystemTime},
+};
+
+use log::{debug, error, info, warn};
+use rustls_pemfile::Item;
+use solana_metrics::{datapoint_error, datapoint_info};
+use thiserror::Error;
+use tokio::{
+    net::{TcpListener, TcpStream},
+    sync::mpsc::{channel, Sender},
+    time::timeout,
+};
+use tokio_rustls::{
+    rustls::{
+        self, server::AllowAnyAuthenticatedClient, Certificate, PrivateKey, RootCertStore,
+        ServerConfig,
+    },
+    server::TlsStream,
+    TlsAcceptor,
+};
+use tokio_stream::wrappers::ReceiverStream;
+
+/// Connections that completed the handshake and are waiting for the server to pick them up
+const ACCEPTED_CONNECTIONS_CAPACITY: usize = 1_024;
+
+/// How long a client has to complete the TLS handshake
+const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Error, Debug)]
+pub enum ServerTlsError {
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("tls error: {0}")]
+    Rustls(#[from] rustls::Error),
+
+    #[error("no PEM encoded certificates in {0:?}")]
+    NoCertificates(PathBuf),
+
+    #[error("no PEM encoded private key in {0:?}")]
+    NoPrivateKey(PathBuf),
+
+    #[error("{0:?} contains certificates that can't be used as a client CA")]
+    InvalidClientCa(PathBuf),
+}
+
+pub type ServerTlsResult<T> = Result<T, ServerTlsError>;
+
+/// PEM files making up the server's TLS configuration
+#[derive(Debug, Clone)]
+pub struct ServerTlsPaths {
+    /// Server certificate chain, leaf first
+    pub cert_path: PathBuf,
+    pub key_path: PathBuf,
+    /// CA certificates client certificates must be signed by; clients don't need a certificate
+    /// when unset
+    pub client_ca_path: Option<PathBuf>,
+}
+
+impl ServerTlsPaths {
+    pub fn load(&self) -> ServerTlsResult<ServerConfig> {
+        let certs = read_certs(&self.cert_path)?;
+        let key = read_private_key(&self.key_path)?;
+
+        let builder = ServerConfig::builder().with_safe_defaults();
+        let builder = match &self.client_ca_path {
+            Some(client_ca_path) => {
+                let mut roots = RootCertStore::empty();
+                let certs: Vec<Vec<u8>> = read_certs(client_ca_path)?
+                    .into_iter()
+                    .map(|cert| cert.0)
+                    .collect();
+                let (_, num_invalid) = roots.add_parsable_certificates(&certs);
+                if num_invalid > 0 {
+                    return Err(ServerTlsError::InvalidClientCa(client_ca_path.clone()));
+                }
+                builder.with_client_cert_verifier(AllowAnyAuthenticatedClient::new(roots).boxed())
+            }
+            None => builder.with_no_client_auth(),
+        };
+        let mut config = builder.with_single_cert(certs, key)?;
+        // gRPC runs over HTTP/2
+        config.alpn_protocols = vec![b"h2".to_vec()];
+        Ok(config)
+    }
+
+    fn modified_times(&self) -> io::Result<Vec<SystemTime>> {
+        [
+            Some(&self.cert_path),
+            Some(&self.key_path),
+            self.client_ca_path.as_ref(),
+        ]
+        .into_iter()
+        .flatten()
+        .map(|path| fs::metadata(path)?.modified())
+        .collect()
+    }
+}
+
+fn read_certs(path: &Path) -> ServerTlsResult<Vec<Certificate>> {
+    let certs = rustls_pemfile::certs(&mut BufReader::new(File::open(path)?))?;
+    if certs.is_empty() {
+        return Err(ServerTlsError::NoCertificates(path.to_owned()));
+    }
+    Ok(certs.into_iter().map(Certificate).collect())
+}
+
+fn read_private_key(path: &Path) -> ServerTlsResult<PrivateKey> {
+    rustls_pemfile::read_all(&mut BufReader::new(File::open(path)?))?
+        .into_iter()
+        .find_map(|item| match item {
+            Item::RSAKey(key) | Item::PKCS8Key(key) | Item::ECKey(key) => Some(PrivateKey(key)),
+            _ => None,
+        })
+        .ok_or_else(|| ServerTlsError::NoPrivateKey(path.to_owned()))
+}
+
+/// Shared access handle to the current TLS configuration.
+#[derive(Clone)]
+pub struct ServerTlsHandle {
+    config: Arc<RwLock<Arc<ServerConfig>>>,
+}
+
+impl ServerTlsHandle {
+    pub fn new(config: ServerConfig) -> ServerTlsHandle {
+        ServerTlsHandle {
+            config: Arc::new(RwLock::new(Arc::new(config))),
+        }
+    }
+
+    /// Returns a snapshot of the current configuration
+    pub fn load(&self) -> Arc<ServerConfig> {
+        self.config.read().unwrap().clone()
+    }
+
+    /// Atomically replaces the configuration, returning the previous one
+    pub fn store(&self, config: ServerConfig) -> Arc<ServerConfig> {
+        std::mem::replace(&mut *self.config.write().unwrap(), Arc::new(config))
+    }
+
+    /// Accepts connections on `listener` and completes their TLS handshakes with the current
+    /// configuration, yielding the established connections for the gRPC server to serve.
+    /// Handshakes run concurrently, so a slow client doesn't hold up the others.
+    pub fn incoming(
+        &self,
+        listener: TcpListener,
+    ) -> ReceiverStream<io::Result<TlsStream<TcpStream>>> {
+        let (sender, receiver) = channel(ACCEPTED_CONNECTIONS_CAPACITY);
+        let handle = self.clone();
+        tokio::spawn(async move {
+            loop {
+                // stops once the server drops the stream
+                let accepted = tokio::select! {
+                    accepted = listener.accept() => accepted,
+                    _ = sender.closed() => break,
+                };
+                match accepted {
+                    Ok((stream, remote_addr)) => {
+                        let acceptor = TlsAcceptor::from(handle.load());
+                        tokio::spawn(Self::handshake(
+                            acceptor,
+                            stream,
+                            remote_addr,
+                            sender.clone(),
+                        ));
+                    }
+                    Err(e) => {
+                        // e.g. out of file descriptors, back off instead of spinning
+                        warn!("error accepting connection: {e}");
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                    }
+                }
+            }
+        });
+        ReceiverStream::new(receiver)
+    }
+
+    async fn handshake(
+        acceptor: TlsAcceptor,
+        stream: TcpStream,
+        remote_addr: SocketAddr,
+        sender: Sender<io::Result<TlsStream<TcpStream>>>,
+    ) {
+        if let Err(e) = stream.set_nodelay(true) {
+            debug!("error setting nodelay for {remote_addr}: {e}");
+        }
+        match timeout(TLS_HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+            Ok(Ok(tls_stream)) => {
+                let _ = sender.send(Ok(tls_stream)).await;
+            }
+            Ok(Err(e)) => debug!("tls handshake with {remote_addr} failed: {e}"),
+            Err(_) => debug!("tls handshake with {remote_addr} timed out"),
+        }
+    }
+}
+
+/// Watches the TLS files and reloads them into a [`ServerTlsHandle`] when they change.
+pub struct ServerTlsUpdater {
+    /// Polls the files for modifications
+    refresh_thread: JoinHandle<()>,
+}
+
+impl ServerTlsUpdater {
+    /// Starts a thread that reloads the TLS files on change. The handle should already hold
+    /// their configuration. Files that fail to load leave the previous configuration in place,
+    /// so a certificate and key replaced one at a time are picked up once both are in place.
+    ///
+    /// # Arguments
+    /// * `paths` - The certificate, key and client CA files
+    /// * `handle` - Shared handle that gets the reloaded configuration swapped in
+    /// * `refresh_interval` - How often to check the files for modifications
+    /// * `exit` - Shutdown signal for graceful termination
+    pub fn new(
+        paths: ServerTlsPaths,
+        handle: &ServerTlsHandle,
+        refresh_interval: Duration,
+        exit: &Arc<AtomicBool>,
+    ) -> ServerTlsUpdater {
+        let handle = handle.clone();
+        let exit = exit.clone();
+        let refresh_thread = Builder::new()
+            .name("server_tls_updater".to_string())
+            .spawn(move || {
+                let mut last_modified = paths.modified_times().ok();
+                let mut last_check = Instant::now();
+                while !exit.load(Ordering::Relaxed) {
+                    sleep(Duration::from_secs(1));
+                    if last_check.elapsed() < refresh_interval {
+                        continue;
+                    }
+                    last_check = Instant::now();
+
+                    let result = paths
+                        .modified_times()
+                        .map_err(ServerTlsError::from)
+                        .and_then(|modified| {
+                            if last_modified.as_ref() != Some(&modified) {
+                                handle.store(paths.load()?);
+                                last_modified = Some(modified);
+                                info!("reloaded tls certificates from {paths:?}");
+                                datapoint_info!("server_tls_updater-reload", ("count", 1, i64));
+                            }
+                            Ok(())
+                        });
+                    if let Err(e) = result {
+                        error!("error reloading tls certificates from {paths:?}: {e}");
+                        datapoint_error!(
+                            "server_tls_updater-error",
+                            ("error", e.to_string(), String)
+                        );
+                    }
+                }
+            })
+            .unwrap();
+
+        ServerTlsUpdater { refresh_thread }
+    }
+
+    pub fn join(self) -> thread::Result<()> {
+        self.refresh_thread.join()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use openssl::{
+        asn1::Asn1Time,
+        bn::BigNum,
+        ec::{EcGroup, EcKey},
+        hash::MessageDigest,
+        nid::Nid,
+        pkey::PKey,
+        x509::{X509NameBuilder, X509},
+    };
+
+    use crate::server_tls::{ServerTlsError, ServerTlsPaths};
+
+    /// Writes a self-signed P-256 certificate and its key, returning their paths
+    fn write_self_signed_cert(name: &str) -> (PathBuf, PathBuf) {
+        let key = PKey::from_ec_key(
+            EcKey::generate(&EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap()).unwrap(),
+        )
+        .unwrap();
+        let mut subject = X509NameBuilder::new().unwrap();
+        subject.append_entry_by_text("CN", "relayer").unwrap();
+        let subject = subject.build();
+
+        let mut cert = X509::builder().unwrap();
+        cert.set_version(2).unwrap();
+        cert.set_serial_number(&BigNum::from_u32(1).unwrap().to_asn1_integer().unwrap())
+            .unwrap();
+        cert.set_subject_name(&subject).unwrap();
+        cert.set_issuer_name(&subject).unwrap();
+        cert.set_pubkey(&key).unwrap();
+        cert.set_not_before(&Asn1Time::days_from_now(0).unwrap())
+            .unwrap();
+        cert.set_not_after(&Asn1Time::days_from_now(1).unwrap())
+            .unwrap();
+        cert.sign(&key, MessageDigest::sha256()).unwrap();
+
+        let dir = std::env::temp_dir();
+        let pid = std::process::id();
+        let cert_path = dir.join(format!("relayer-tls-{name}-{pid}.crt"));
+        let key_path = dir.join(format!("relayer-tls-{name}-{pid}.key"));
+        std::fs::write(&cert_path, cert.build().to_pem().unwrap()).unwrap();
+        std::fs::write(&key_path, key.private_key_to_pem_pkcs8().unwrap()).unwrap();
+        (cert_path, key_path)
+    }
+
+    #[test]
+    fn test_load_server_tls() {
+        let (cert_path, key_path) = write_self_signed_cert("server");
+        let (client_ca_path, client_key_path) = write_self_signed_cert("client-ca");
+
+        let paths = ServerTlsPaths {
+            cert_path: cert_path.clone(),
+            key_path: key_path.clone(),
+            client_ca_path: None,
+        };
+        let config = paths.load().unwrap();
+        assert_eq!(config.alpn_protocols, vec![b"h2".to_vec()]);
+
+        let mtls_paths = ServerTlsPaths {
+            client_ca_path: Some(client_ca_path.clone()),
+            ..paths.clone()
+        };
+        assert!(mtls_paths.load().is_ok());
+
+        // a certificate isn't a key
+        let swapped_paths = ServerTlsPaths {
+            key_path: cert_path.clone(),
+            ..paths
+        };
+        assert!(matches!(
+            swapped_paths.load(),
+            Err(ServerTlsError::NoPrivateKey(_))
+        ));
+        let no_ca_paths = ServerTlsPaths {
+            client_ca_path: Some(client_key_path.clone()),
+            ..mtls_paths
+        };
+        assert!(matches!(
+            no_ca_paths.load(),
+            Err(ServerTlsError::NoCertificates(_))
+        ));
+
+        for path in [cert_path, key_path, client_ca_path, client_key_path] {
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+}
@@ -0,0 +1,218 @@
+//! Table of validator sessions, from authentication to packet subscription.
+//!
+//! A session starts when a validator is issued tokens and lasts until its refresh token expires,
+//! or until it disconnects if it's still subscribed by then. There's one session per validator
+//! pubkey, so authenticating again replaces the IP and token expirations of the previous one.
+//! Every change is also recorded in the [`AuthEventLog`].
+
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::{Arc, RwLock},
+};
+
+use chrono::Utc;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::auth_events::{AuthEvent, AuthEventLog, SubscriptionCloseReason};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Session {
+    pub pubkey: Pubkey,
+    /// IP the validator last authenticated, refreshed or subscribed from
+    pub client_ip: Option<IpAddr>,
+    /// Unset for validators that authenticated before a restart
+    pub authenticated_at_unix_ts: Option<i64>,
+    pub access_token_expires_at_unix_ts: Option<i64>,
+    pub refresh_token_expires_at_unix_ts: Option<i64>,
+    pub last_refresh_at_unix_ts: Option<i64>,
+    /// When the current packet subscription was opened, unset if the validator isn't subscribed
+    pub subscribed_at_unix_ts: Option<i64>,
+}
+
+impl Session {
+    fn new(pubkey: Pubkey) -> Session {
+        Session {
+            pubkey,
+            client_ip: None,
+            authenticated_at_unix_ts: None,
+            access_token_expires_at_unix_ts: None,
+            refresh_token_expires_at_unix_ts: None,
+            last_refresh_at_unix_ts: None,
+            subscribed_at_unix_ts: None,
+        }
+    }
+
+    /// How long the validator has been subscribed, if it is
+    pub fn connected_secs(&self, now_unix_ts: i64) -> Option<i64> {
+        self.subscribed_at_unix_ts
+            .map(|subscribed_at| now_unix_ts.saturating_sub(subscribed_at))
+    }
+
+    fn is_expired(&self, now_unix_ts: i64) -> bool {
+        self.subscribed_at_unix_ts.is_none()
+            && self
+                .refresh_token_expires_at_unix_ts
+                .or(self.access_token_expires_at_unix_ts)
+                .map_or(true, |expires_at| expires_at <= now_unix_ts)
+    }
+}
+
+/// Shared table of validator sessions, updated by the auth service and the relayer
+#[derive(Clone, Default)]
+pub struct SessionTable {
+    sessions: Arc<RwLock<HashMap<Pubkey, Session>>>,
+    auth_events: AuthEventLog,
+}
+
+impl SessionTable {
+    pub fn new(auth_events: AuthEventLog) -> SessionTable {
+        SessionTable {
+            sessions: Arc::default(),
+            auth_events,
+        }
+    }
+
+    /// Starts a new session for a validator that answered its auth challenge
+    pub fn tokens_issued(
+        &self,
+        pubkey: &Pubkey,
+        client_ip: IpAddr,
+        access_token_expires_at_unix_ts: i64,
+        refresh_token_expires_at_unix_ts: i64,
+    ) {
+        self.auth_events.record(AuthEvent::TokensIssued {
+            pubkey: pubkey.to_string(),
+            client_ip,
+            access_token_expires_at_unix_ts,
+            refresh_token_expires_at_unix_ts,
+        });
+
+        let now = Utc::now().timestamp();
+        let mut l_sessions = self.sessions.write().unwrap();
+        l_sessions.retain(|_, session| !session.is_expired(now));
+        // a subscription opened with the previous session's tokens stays open
+        let subscribed_at_unix_ts = l_sessions
+            .get(pubkey)
+            .and_then(|session| session.subscribed_at_unix_ts);
+        l_sessions.insert(
+            *pubkey,
+            Session {
+                pubkey: *pubkey,
+                client_ip: Some(client_ip),
+                authenticated_at_unix_ts: Some(now),
+                access_token_expires_at_unix_ts: Some(access_token_expires_at_unix_ts),
+                refresh_token_expires_at_unix_ts: Some(refresh_token_expires_at_unix_ts),
+                last_refresh_at_unix_ts: None,
+                subscribed_at_unix_ts,
+            },
+        );
+    }
+
+    pub fn token_refreshed(
+        &self,
+        pubkey: &Pubkey,
+        client_ip: IpAddr,
+        access_token_expires_at_unix_ts: i64,
+        refresh_token_expires_at_unix_ts: i64,
+        refresh_token_rotated: bool,
+    ) {
+        self.auth_events.record(AuthEvent::TokenRefreshed {
+            pubkey: pubkey.to_string(),
+            client_ip,
+            access_token_expires_at_unix_ts,
+            refresh_token_rotated,
+        });
+
+        let mut l_sessions = self.sessions.write().unwrap();
+        let session = l_sessions
+            .entry(*pubkey)
+            .or_insert_with(|| Session::new(*pubkey));
+        session.client_ip = Some(client_ip);
+        session.access_token_expires_at_unix_ts = Some(access_token_expires_at_unix_ts);
+        session.refresh_token_expires_at_unix_ts = Some(refresh_token_expires_at_unix_ts);
+        session.last_refresh_at_unix_ts = Some(Utc::now().timestamp());
+    }
+
+    pub fn subscription_opened(&self, pubkey: &Pubkey, client_ip: Option<IpAddr>) {
+        self.auth_events.record(AuthEvent::SubscriptionOpened {
+            pubkey: pubkey.to_string(),
+            client_ip,
+        });
+
+        let mut l_sessions = self.sessions.write().unwrap();
+        let session = l_sessions
+            .entry(*pubkey)
+            .or_insert_with(|| Session::new(*pubkey));
+        if client_ip.is_some() {
+            session.client_ip = client_ip;
+        }
+        session.subscribed_at_unix_ts = Some(Utc::now().timestamp());
+    }
+
+    pub fn subscription_closed(&self, pubkey: &Pubkey, reason: SubscriptionCloseReason) {
+        let now = Utc::now().timestamp();
+        let mut l_sessions = self.sessions.write().unwrap();
+        let Some(session) = l_sessions.get_mut(pubkey) else {
+            return;
+        };
+        let connected_secs = session.connected_secs(now).unwrap_or_default();
+        session.subscribed_at_unix_ts = None;
+        if session.is_expired(now) {
+            l_sessions.remove(pubkey);
+        }
+        drop(l_sessions);
+
+        self.auth_events.record(AuthEvent::SubscriptionClosed {
+            pubkey: pubkey.to_string(),
+            reason,
+            connected_secs,
+        });
+    }
+
+    /// Returns the live sessions, dropping expired ones
+    pub fn sessions(&self) -> Vec<Session> {
+        let now = Utc::now().timestamp();
+        let mut l_sessions = self.sessions.write().unwrap();
+        l_sessions.retain(|_, session| !session.is_expired(now));
+        l_sessions.values().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr};
+
+    use chrono::Utc;
+    use solana_sdk::pubkey::Pubkey;
+
+    use crate::{auth_events::SubscriptionCloseReason, sessions::SessionTable};
+
+    #[test]
+    fn test_session_lifecycle() {
+        let sessions = SessionTable::default();
+        let (validator, expired) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let client_ip = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        let now = Utc::now().timestamp();
+
+        sessions.tokens_issued(&validator, client_ip, now + 60, now + 600);
+        sessions.tokens_issued(&expired, client_ip, now - 60, now - 1);
+        sessions.subscription_opened(&validator, None);
+        sessions.token_refreshed(&validator, client_ip, now + 120, now + 600, true);
+
+        // expired sessions are dropped unless the validator is still subscribed
+        let live = sessions.sessions();
+        assert_eq!(live.len(), 1);
+        assert_eq!(live[0].pubkey, validator);
+        assert_eq!(live[0].client_ip, Some(client_ip));
+        assert_eq!(live[0].access_token_expires_at_unix_ts, Some(now + 120));
+        assert!(live[0].last_refresh_at_unix_ts.is_some());
+        assert!(live[0].connected_secs(now + 5).unwrap() >= 5);
+
+        // authenticating again keeps the open subscription
+        sessions.tokens_issued(&validator, client_ip, now + 60, now + 600);
+        assert!(sessions.sessions()[0].subscribed_at_unix_ts.is_some());
+        sessions.subscription_closed(&validator, SubscriptionCloseReason::SendFailed);
+        assert_eq!(sessions.sessions()[0].connected_secs(now), None);
+    }
+}
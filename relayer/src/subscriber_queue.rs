@@ -0,0 +1,361 @@
+//! Bounded per-validator queue feeding a packet subscription's gRPC stream.
+//!
+//! Forwarding threads push packet batches and heartbeats without blocking, and tonic drains the
+//! queue into the validator's stream. A validator that reads slower than packets arrive fills
+//! its queue, and its [`Backpressure`] policy decides what gives: the newest batch is dropped,
+//! the oldest batch is dropped so the freshest transactions win, or the validator is
+//! disconnected once its queue has stayed full for too long.
+//!
+//! Messages are queued as encoded `SubscribePacketsResponse`s, see [`jito_protos::encoded`], so a
+//! batch sent to many validators is encoded once and its bytes are shared by their queues.
+//!
+//! The stream ends once every [`SubscriberSender`] is dropped, and sends fail with
+//! [`TrySendError::Closed`] once the validator's stream is dropped.
+
+use std::{
+    collections::VecDeque,
+    fmt,
+    pin::Pin,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll, Waker},
+    time::{Duration, Instant},
+};
+
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use tokio_stream::Stream;
+use tonic::Status;
+
+/// What happens to a packet batch sent to a validator whose queue is full
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+#[repr(u8)]
+pub enum Backpressure {
+    /// Drop the batch being sent
+    #[default]
+    DropNewest = 0,
+    /// Drop the oldest queued batch to make room. The relayer queues a packet batch's chunks
+    /// lowest fee first for these validators, so the cheapest chunks are pushed out first
+    DropOldest = 1,
+    /// Drop the batch being sent, and disconnect the validator once its queue has been full for
+    /// longer than the slow subscriber timeout
+    Disconnect = 2,
+}
+
+impl Backpressure {
+    pub(crate) fn from_u8(value: u8) -> Backpressure {
+        match value {
+            1 => Backpressure::DropOldest,
+            2 => Backpressure::Disconnect,
+            _ => Backpressure::DropNewest,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Backpressure::DropNewest => "drop_newest",
+            Backpressure::DropOldest => "drop_oldest",
+            Backpressure::Disconnect => "disconnect",
+        }
+    }
+}
+
+impl FromStr for Backpressure {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Backpressure, String> {
+        match s {
+            "drop_newest" => Ok(Backpressure::DropNewest),
+            "drop_oldest" => Ok(Backpressure::DropOldest),
+            "disconnect" => Ok(Backpressure::Disconnect),
+            _ => Err(format!(
+                "invalid backpressure policy {s:?}, expected drop_newest, drop_oldest or \
+                 disconnect"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Backpressure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum TrySendError {
+    /// The queue is full and the message was dropped
+    Full,
+    /// The validator's stream was dropped
+    Closed,
+    /// The queue has been full for longer than the slow subscriber timeout
+    TooSlow,
+}
+
+struct QueuedMessage {
+    /// Encoded `SubscribePacketsResponse`
+    response: Bytes,
+    /// Packets in the message, counted as dropped if it's pushed out of the queue
+    num_packets: u64,
+}
+
+struct QueueState {
+    messages: VecDeque<QueuedMessage>,
+    /// When a send first found the queue full, cleared once a send finds room
+    full_since: Option<Instant>,
+    /// Woken when a message is queued or the senders are dropped
+    waker: Option<Waker>,
+    senders_closed: bool,
+    receiver_closed: bool,
+}
+
+struct Shared {
+    state: Mutex<QueueState>,
+    capacity: usize,
+    num_senders: AtomicUsize,
+}
+
+/// Creates a queue holding up to `capacity` messages
+pub fn subscriber_queue(capacity: usize) -> (SubscriberSender, SubscriberReceiver) {
+    let shared = Arc::new(Shared {
+        state: Mutex::new(QueueState {
+            messages: VecDeque::with_capacity(capacity.min(1_024)),
+            full_since: None,
+            waker: None,
+            senders_closed: false,
+            receiver_closed: false,
+        }),
+        capacity,
+        num_senders: AtomicUsize::new(1),
+    });
+    (
+        SubscriberSender {
+            shared: shared.clone(),
+        },
+        SubscriberReceiver { shared },
+    )
+}
+
+pub struct SubscriberSender {
+    shared: Arc<Shared>,
+}
+
+impl SubscriberSender {
+    /// Queues the message without blocking, applying `backpressure` if the queue is full.
+    /// Returns the number of packets pushed out of the queue to make room.
+    ///
+    /// # Arguments
+    /// * `num_packets` - Packets in the message, for drop accounting
+    /// * `slow_subscriber_timeout` - How long the queue can stay full under
+    ///   [`Backpressure::Disconnect`] before sends fail with [`TrySendError::TooSlow`]
+    pub fn try_send(
+        &self,
+        response: Bytes,
+        num_packets: u64,
+        backpressure: Backpressure,
+        slow_subscriber_timeout: Duration,
+    ) -> Result<u64, TrySendError> {
+        let mut state = self.shared.state.lock().unwrap();
+        if state.receiver_closed {
+            return Err(TrySendError::Closed);
+        }
+
+        let mut num_dropped_packets = 0;
+        if state.messages.len() < self.shared.capacity {
+            state.full_since = None;
+        } else {
+            let full_since = *state.full_since.get_or_insert_with(Instant::now);
+            match backpressure {
+                Backpressure::DropNewest => return Err(TrySendError::Full),
+                Backpressure::DropOldest => {
+                    num_dropped_packets = state
+                        .messages
+                        .pop_front()
+                        .map_or(0, |message| message.num_packets);
+                }
+                Backpressure::Disconnect => {
+                    return Err(if full_since.elapsed() >= slow_subscriber_timeout {
+                        TrySendError::TooSlow
+                    } else {
+                        TrySendError::Full
+                    });
+                }
+            }
+        }
+
+        state.messages.push_back(QueuedMessage {
+            response,
+            num_packets,
+        });
+        let waker = state.waker.take();
+        drop(state);
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+        Ok(num_dropped_packets)
+    }
+
+    pub fn len(&self) -> usize {
+        self.shared.state.lock().unwrap().messages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.shared.capacity
+    }
+
+    /// Whether both senders feed the same validator stream
+    pub fn same_queue(&self, other: &SubscriberSender) -> bool {
+        Arc::ptr_eq(&self.shared, &other.shared)
+    }
+
+    /// Fraction of the queue in use, from 0 to 1
+    pub fn fill_ratio(&self) -> f64 {
+        self.len() as f64 / self.shared.capacity.max(1) as f64
+    }
+
+    /// How long sends have been finding the queue full, if the last one did
+    pub fn full_for(&self) -> Option<Duration> {
+        self.shared
+            .state
+            .lock()
+            .unwrap()
+            .full_since
+            .map(|full_since| full_since.elapsed())
+    }
+}
+
+impl Clone for SubscriberSender {
+    fn clone(&self) -> SubscriberSender {
+        self.shared.num_senders.fetch_add(1, Ordering::Relaxed);
+        SubscriberSender {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl Drop for SubscriberSender {
+    fn drop(&mut self) {
+        if self.shared.num_senders.fetch_sub(1, Ordering::AcqRel) != 1 {
+            return;
+        }
+        let mut state = self.shared.state.lock().unwrap();
+        state.senders_closed = true;
+        let waker = state.waker.take();
+        drop(state);
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+/// Stream of a validator's queued messages, served as its packet subscription
+pub struct SubscriberReceiver {
+    shared: Arc<Shared>,
+}
+
+impl Stream for SubscriberReceiver {
+    type Item = Result<Bytes, Status>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut state = self.shared.state.lock().unwrap();
+        if let Some(message) = state.messages.pop_front() {
+            return Poll::Ready(Some(Ok(message.response)));
+        }
+        if state.senders_closed {
+            return Poll::Ready(None);
+        }
+        state.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl Drop for SubscriberReceiver {
+    fn drop(&mut self) {
+        let mut state = self.shared.state.lock().unwrap();
+        state.receiver_closed = true;
+        state.messages.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bytes::Bytes;
+    use jito_protos::{
+        encoded::encode_message,
+        relayer::{subscribe_packets_response, SubscribePacketsResponse},
+        shared::Heartbeat,
+    };
+    use tokio_stream::StreamExt;
+
+    use crate::subscriber_queue::{subscriber_queue, Backpressure, TrySendError};
+
+    fn heartbeat(count: u64) -> Bytes {
+        encode_message(&SubscribePacketsResponse {
+            header: None,
+            msg: Some(subscribe_packets_response::Msg::Heartbeat(Heartbeat {
+                count,
+            })),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_subscriber_queue_backpressure() {
+        let (sender, mut receiver) = subscriber_queue(2);
+        let timeout = Duration::from_secs(60);
+
+        for count in 0..2 {
+            assert_eq!(
+                sender.try_send(heartbeat(count), 1, Backpressure::DropNewest, timeout),
+                Ok(0)
+            );
+        }
+        assert_eq!(
+            sender.try_send(heartbeat(2), 1, Backpressure::DropNewest, timeout),
+            Err(TrySendError::Full)
+        );
+        assert_eq!(sender.len(), 2);
+        assert!(sender.full_for().is_some());
+
+        // the oldest message makes room for the newest
+        assert_eq!(
+            sender.try_send(heartbeat(3), 1, Backpressure::DropOldest, timeout),
+            Ok(1)
+        );
+        assert_eq!(
+            sender.try_send(heartbeat(4), 1, Backpressure::Disconnect, Duration::ZERO),
+            Err(TrySendError::TooSlow)
+        );
+
+        assert_eq!(receiver.next().await.unwrap().unwrap(), heartbeat(1));
+        assert_eq!(receiver.next().await.unwrap().unwrap(), heartbeat(3));
+
+        // dropping every sender ends the stream
+        let sender_clone = sender.clone();
+        drop(sender);
+        assert_eq!(
+            sender_clone.try_send(heartbeat(5), 0, Backpressure::DropNewest, timeout),
+            Ok(0)
+        );
+        assert!(sender_clone.full_for().is_none());
+        drop(sender_clone);
+        assert_eq!(receiver.next().await.unwrap().unwrap(), heartbeat(5));
+        assert!(receiver.next().await.is_none());
+
+        let (sender, receiver) = subscriber_queue(1);
+        drop(receiver);
+        assert_eq!(
+            sender.try_send(heartbeat(0), 0, Backpressure::DropNewest, timeout),
+            Err(TrySendError::Closed)
+        );
+    }
+}
@@ -0,0 +1,134 @@
+//! [`ValidatorAuther`] implementations deciding which validators can authenticate.
+//!
+//! Rules are combined with [`AllOf`] and [`AnyOf`], e.g. "on the allowlist or staked above
+//! 10k SOL, and not on the denylist". Every rule follows changes without a restart: the leader
+//! schedule and stakes are refreshed from RPC, and pubkey list files are reloaded when they
+//! change by the watcher in [`jito_core::pubkey_list`], which the OFAC list uses too.
+
+use std::sync::{Arc, RwLock};
+
+use jito_core::pubkey_list::PubkeyListHandle;
+use solana_sdk::pubkey::Pubkey;
+use solana_streamer::streamer::StakedNodes;
+
+use crate::{auth_service::ValidatorAuther, schedule_cache::LeaderScheduleUpdatingHandle};
+
+/// Allows validators that are in the current or next epoch's leader schedule
+pub struct ScheduledLeaders(pub LeaderScheduleUpdatingHandle);
+
+impl ValidatorAuther for ScheduledLeaders {
+    fn is_authorized(&self, pubkey: &Pubkey) -> bool {
+        self.0.is_scheduled_validator(pubkey)
+    }
+}
+
+/// Allows validators with at least `min_stake_lamports` activated stake, using the stakes
+/// fetched for QUIC connection prioritization.
+pub struct MinimumStake {
+    staked_nodes: Arc<RwLock<StakedNodes>>,
+    min_stake_lamports: u64,
+}
+
+impl MinimumStake {
+    pub fn new(staked_nodes: Arc<RwLock<StakedNodes>>, min_stake_lamports: u64) -> MinimumStake {
+        MinimumStake {
+            staked_nodes,
+            min_stake_lamports,
+        }
+    }
+}
+
+impl ValidatorAuther for MinimumStake {
+    fn is_authorized(&self, pubkey: &Pubkey) -> bool {
+        self.staked_nodes
+            .read()
+            .unwrap()
+            .get_node_stake(pubkey)
+            .is_some_and(|stake| stake >= self.min_stake_lamports)
+    }
+}
+
+/// Allows only the validators on the list
+pub struct Allowlist(pub PubkeyListHandle);
+
+impl ValidatorAuther for Allowlist {
+    fn is_authorized(&self, pubkey: &Pubkey) -> bool {
+        self.0.load().contains(pubkey)
+    }
+}
+
+/// Allows every validator except the ones on the list
+pub struct Denylist(pub PubkeyListHandle);
+
+impl ValidatorAuther for Denylist {
+    fn is_authorized(&self, pubkey: &Pubkey) -> bool {
+        !self.0.load().contains(pubkey)
+    }
+}
+
+/// Allows validators allowed by every rule
+pub struct AllOf(pub Vec<Box<dyn ValidatorAuther>>);
+
+impl ValidatorAuther for AllOf {
+    fn is_authorized(&self, pubkey: &Pubkey) -> bool {
+        self.0.iter().all(|auther| auther.is_authorized(pubkey))
+    }
+}
+
+/// Allows validators allowed by at least one rule
+pub struct AnyOf(pub Vec<Box<dyn ValidatorAuther>>);
+
+impl ValidatorAuther for AnyOf {
+    fn is_authorized(&self, pubkey: &Pubkey) -> bool {
+        self.0.iter().any(|auther| auther.is_authorized(pubkey))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::{HashMap, HashSet},
+        sync::{Arc, RwLock},
+    };
+
+    use jito_core::pubkey_list::PubkeyListHandle;
+    use solana_sdk::pubkey::Pubkey;
+    use solana_streamer::streamer::StakedNodes;
+
+    use crate::{
+        auth_service::ValidatorAuther,
+        validator_authers::{AllOf, Allowlist, AnyOf, Denylist, MinimumStake},
+    };
+
+    #[test]
+    fn test_composed_validator_authers() {
+        let (allowed, staked, denied, unknown) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let staked_nodes = Arc::new(RwLock::new(StakedNodes::new(
+            Arc::new(HashMap::from([(staked, 100), (denied, 100), (unknown, 10)])),
+            HashMap::new(),
+        )));
+        let denylist = PubkeyListHandle::new(HashSet::from([denied]));
+
+        let auther = AllOf(vec![
+            Box::new(AnyOf(vec![
+                Box::new(Allowlist(PubkeyListHandle::new(HashSet::from([allowed])))),
+                Box::new(MinimumStake::new(staked_nodes, 50)),
+            ])),
+            Box::new(Denylist(denylist.clone())),
+        ]);
+        assert!(auther.is_authorized(&allowed));
+        assert!(auther.is_authorized(&staked));
+        assert!(!auther.is_authorized(&denied));
+        assert!(!auther.is_authorized(&unknown));
+
+        // lists are read on every check, so reloads apply immediately
+        denylist.store(HashSet::from([allowed]));
+        assert!(!auther.is_authorized(&allowed));
+        assert!(auther.is_authorized(&denied));
+    }
+}
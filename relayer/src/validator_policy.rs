@@ -0,0 +1,176 @@
+//! Per-validator overrides of the forwarding parameters.
+//!
+//! A relayer can serve validators with different needs, so each validator can override the
+//! global slot lookahead, batch size, forward-all mode, packet delay and backpressure policy.
+//! Policies are loaded from a YAML file mapping base58 validator pubkeys to the parameters they
+//! override:
+//!
+//! ```yaml
+//! "7Np41oeYqPefeNQEHSv1UDhYrehxin3NStELsSKCT4K2":
+//!   slot_lookahead: 10
+//!   validator_packet_batch_size: 8
+//!   forward_all: true
+//!   packet_delay_ms: 50
+//!   backpressure: drop_oldest
+//! ```
+
+use std::{collections::HashMap, fs, io, path::Path, str::FromStr};
+
+use serde::Deserialize;
+use solana_sdk::pubkey::Pubkey;
+use thiserror::Error;
+
+use crate::subscriber_queue::Backpressure;
+
+/// Forwarding parameters overridden for a single validator; unset fields use the global value
+#[derive(Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct ValidatorPolicy {
+    pub slot_lookahead: Option<u64>,
+    pub validator_packet_batch_size: Option<usize>,
+    pub forward_all: Option<bool>,
+    pub packet_delay_ms: Option<u32>,
+    pub backpressure: Option<Backpressure>,
+}
+
+#[derive(Error, Debug)]
+pub enum ValidatorPolicyError {
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("yaml error: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+
+    #[error("invalid validator policies:\n  - {}", .0.join("\n  - "))]
+    Invalid(Vec<String>),
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct ValidatorPolicies {
+    policies: HashMap<Pubkey, ValidatorPolicy>,
+}
+
+impl ValidatorPolicies {
+    pub fn new(policies: HashMap<Pubkey, ValidatorPolicy>) -> ValidatorPolicies {
+        ValidatorPolicies { policies }
+    }
+
+    /// Reads policies from a YAML file, reporting every invalid entry rather than the first
+    pub fn load(path: &Path) -> Result<ValidatorPolicies, ValidatorPolicyError> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn parse(contents: &str) -> Result<ValidatorPolicies, ValidatorPolicyError> {
+        let raw_policies: Option<HashMap<String, ValidatorPolicy>> =
+            serde_yaml::from_str(contents)?;
+
+        let mut policies = HashMap::new();
+        let mut problems = Vec::new();
+        for (pubkey, policy) in raw_policies.unwrap_or_default() {
+            let Ok(pubkey) = Pubkey::from_str(&pubkey) else {
+                problems.push(format!("invalid pubkey {pubkey:?}"));
+                continue;
+            };
+            if policy.validator_packet_batch_size == Some(0) {
+                problems.push(format!(
+                    "{pubkey}: validator_packet_batch_size must be greater than 0"
+                ));
+            }
+            if policy.slot_lookahead == Some(0) {
+                problems.push(format!("{pubkey}: slot_lookahead must be greater than 0"));
+            }
+            policies.insert(pubkey, policy);
+        }
+
+        if problems.is_empty() {
+            Ok(ValidatorPolicies { policies })
+        } else {
+            Err(ValidatorPolicyError::Invalid(problems))
+        }
+    }
+
+    pub fn get(&self, pubkey: &Pubkey) -> Option<&ValidatorPolicy> {
+        self.policies.get(pubkey)
+    }
+
+    pub fn len(&self) -> usize {
+        self.policies.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.policies.is_empty()
+    }
+
+    /// Largest slot lookahead overridden by any policy
+    pub fn max_slot_lookahead(&self) -> Option<u64> {
+        self.policies
+            .values()
+            .filter_map(|policy| policy.slot_lookahead)
+            .max()
+    }
+
+    /// Packet delays overridden by the policies
+    pub fn packet_delays_ms(&self) -> impl Iterator<Item = u32> + '_ {
+        self.policies
+            .values()
+            .filter_map(|policy| policy.packet_delay_ms)
+    }
+
+    /// Smallest packet delay overridden by any policy
+    pub fn min_packet_delay_ms(&self) -> Option<u32> {
+        self.packet_delays_ms().min()
+    }
+
+    /// Largest packet delay overridden by any policy
+    pub fn max_packet_delay_ms(&self) -> Option<u32> {
+        self.packet_delays_ms().max()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::pubkey::Pubkey;
+
+    use crate::{
+        subscriber_queue::Backpressure,
+        validator_policy::{ValidatorPolicies, ValidatorPolicy, ValidatorPolicyError},
+    };
+
+    #[test]
+    fn test_parse_validator_policies() {
+        let validator = Pubkey::new_unique();
+        let policies = ValidatorPolicies::parse(&format!(
+            "\"{validator}\":\n  slot_lookahead: 10\n  packet_delay_ms: 50\n  \
+             backpressure: drop_oldest\n"
+        ))
+        .unwrap();
+        assert_eq!(
+            policies.get(&validator),
+            Some(&ValidatorPolicy {
+                slot_lookahead: Some(10),
+                validator_packet_batch_size: None,
+                forward_all: None,
+                packet_delay_ms: Some(50),
+                backpressure: Some(Backpressure::DropOldest),
+            })
+        );
+        assert_eq!(policies.max_slot_lookahead(), Some(10));
+        assert_eq!(policies.min_packet_delay_ms(), Some(50));
+
+        assert!(ValidatorPolicies::parse("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_validator_policies_reports_all_problems() {
+        let validator = Pubkey::new_unique();
+        let err = ValidatorPolicies::parse(&format!(
+            "not-a-pubkey: {{}}\n\"{validator}\":\n  validator_packet_batch_size: 0\n  \
+             slot_lookahead: 0\n"
+        ))
+        .unwrap_err();
+        let ValidatorPolicyError::Invalid(problems) = err else {
+            panic!("unexpected error: {err}");
+        };
+        assert_eq!(problems.len(), 3);
+    }
+}
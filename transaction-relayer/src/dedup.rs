@@ -0,0 +1,198 @@
+//! Suppression of duplicate transactions before they're forwarded.
+//!
+//! Clients often send the same signed transaction over many QUIC connections, and every copy
+//! passes sigverify. The forwarder looks up each packet's first signature in a
+//! [`SignatureDeduper`] and discards the copies it has already seen on the paths the
+//! [`DedupMode`] applies to, so validators and the block engine get each transaction once.
+
+use std::{
+    collections::HashSet,
+    fmt,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use solana_core::banking_trace::BankingPacketBatch;
+use solana_perf::packet::Packet;
+
+/// Signatures remembered per bucket before it's rotated early, bounding memory under load
+const MAX_SIGNATURES_PER_BUCKET: usize = 1_000_000;
+
+/// Which forwarding paths duplicate transactions are dropped from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DedupMode {
+    Off,
+    Validators,
+    BlockEngine,
+    Both,
+}
+
+impl DedupMode {
+    pub fn dedups_validators(&self) -> bool {
+        matches!(self, DedupMode::Validators | DedupMode::Both)
+    }
+
+    pub fn dedups_block_engine(&self) -> bool {
+        matches!(self, DedupMode::BlockEngine | DedupMode::Both)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DedupMode::Off => "off",
+            DedupMode::Validators => "validators",
+            DedupMode::BlockEngine => "block_engine",
+            DedupMode::Both => "both",
+        }
+    }
+}
+
+impl FromStr for DedupMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<DedupMode, String> {
+        match s {
+            "off" => Ok(DedupMode::Off),
+            "validators" => Ok(DedupMode::Validators),
+            "block_engine" => Ok(DedupMode::BlockEngine),
+            "both" => Ok(DedupMode::Both),
+            _ => Err(format!(
+                "invalid dedup mode {s:?}, expected off, validators, block_engine or both"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for DedupMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+struct Buckets {
+    current: HashSet<u64>,
+    previous: HashSet<u64>,
+    current_started: Instant,
+}
+
+/// Signatures seen recently, kept in two time buckets: the current one takes new signatures and
+/// becomes the previous one once it's a window old, so a signature is remembered for one to two
+/// windows. Shared by the forwarder threads.
+pub struct SignatureDeduper {
+    buckets: Mutex<Buckets>,
+    window: Duration,
+}
+
+impl SignatureDeduper {
+    pub fn new(window: Duration) -> SignatureDeduper {
+        SignatureDeduper {
+            buckets: Mutex::new(Buckets {
+                current: HashSet::new(),
+                previous: HashSet::new(),
+                current_started: Instant::now(),
+            }),
+            window,
+        }
+    }
+
+    /// Records the signatures of the batch's packets, returning the batch with packets whose
+    /// signature was already seen marked as discarded, and how many there were. The batch is
+    /// only copied if it has duplicates.
+    pub fn dedup(&self, banking_packet_batch: &BankingPacketBatch) -> (BankingPacketBatch, u64) {
+        let mut duplicates = Vec::new();
+        {
+            let mut buckets = self.buckets.lock().unwrap();
+            if buckets.current_started.elapsed() >= self.window
+                || buckets.current.len() >= MAX_SIGNATURES_PER_BUCKET
+            {
+                buckets.previous = std::mem::take(&mut buckets.current);
+                buckets.current_started = Instant::now();
+            }
+
+            for (batch_index, batch) in banking_packet_batch.0.iter().enumerate() {
+                for (packet_index, packet) in batch.iter().enumerate() {
+                    if packet.meta().discard() {
+                        continue;
+                    }
+                    let Some(key) = signature_key(packet) else {
+                        continue;
+                    };
+                    if buckets.previous.contains(&key) || !buckets.current.insert(key) {
+                        duplicates.push((batch_index, packet_index));
+                    }
+                }
+            }
+        }
+        if duplicates.is_empty() {
+            return (banking_packet_batch.clone(), 0);
+        }
+
+        let mut packet_batches = banking_packet_batch.0.clone();
+        for (batch_index, packet_index) in &duplicates {
+            packet_batches[*batch_index][*packet_index]
+                .meta_mut()
+                .set_discard(true);
+        }
+        (
+            Arc::new((packet_batches, banking_packet_batch.1.clone())),
+            duplicates.len() as u64,
+        )
+    }
+}
+
+/// Key for the packet's first signature. A sigverified transaction starts with its signature
+/// count, a single byte as transactions can't hold 128 signatures, followed by the signatures.
+/// The first eight bytes of a signature are effectively random, so they identify it while
+/// keeping the buckets small.
+fn signature_key(packet: &Packet) -> Option<u64> {
+    let bytes = packet.data(1..9)?;
+    Some(u64::from_le_bytes(bytes.try_into().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use solana_perf::packet::{Packet, PacketBatch};
+    use solana_sdk::{hash::Hash, signature::Keypair, signer::Signer, system_transaction};
+
+    use crate::dedup::SignatureDeduper;
+
+    fn packet(keypair: &Keypair) -> Packet {
+        let tx = system_transaction::transfer(keypair, &keypair.pubkey(), 1, Hash::new_unique());
+        Packet::from_data(None, tx).unwrap()
+    }
+
+    #[test]
+    fn test_signature_deduper() {
+        let (a, b) = (Keypair::new(), Keypair::new());
+        let (packet_a, packet_b) = (packet(&a), packet(&b));
+        let deduper = SignatureDeduper::new(Duration::from_secs(60));
+
+        let batch = Arc::new((
+            vec![PacketBatch::new(vec![
+                packet_a.clone(),
+                packet_b.clone(),
+                packet_a.clone(),
+            ])],
+            None,
+        ));
+        let (deduped, num_duplicates) = deduper.dedup(&batch);
+        assert_eq!(num_duplicates, 1);
+        let discarded: Vec<bool> = deduped.0[0].iter().map(|p| p.meta().discard()).collect();
+        assert_eq!(discarded, vec![false, false, true]);
+        // the original batch is left as is for paths that aren't deduped
+        assert!(!batch.0[0][2].meta().discard());
+
+        // duplicates are caught across batches
+        let batch = Arc::new((vec![PacketBatch::new(vec![packet_b])], None));
+        assert_eq!(deduper.dedup(&batch).1, 1);
+
+        // signatures are forgotten after two windows
+        let deduper = SignatureDeduper::new(Duration::ZERO);
+        let batch = Arc::new((vec![PacketBatch::new(vec![packet_a])], None));
+        assert_eq!(deduper.dedup(&batch).1, 0);
+        assert_eq!(deduper.dedup(&batch).1, 1);
+        assert_eq!(deduper.dedup(&batch).1, 0);
+    }
+}
@@ -10,32 +10,50 @@ use std::{
 
 use crossbeam_channel::{Receiver, RecvTimeoutError, Sender};
 use jito_block_engine::block_engine::BlockEnginePackets;
-use jito_relayer::relayer::RelayerPacketBatches;
+use jito_relayer::{forwarding_config::ForwardingConfig, relayer::RelayerPacketBatches};
 use solana_core::banking_trace::BankingPacketBatch;
 use solana_metrics::datapoint_info;
 use tokio::sync::mpsc::error::TrySendError;
 
+use crate::dedup::{DedupMode, SignatureDeduper};
+
 pub const BLOCK_ENGINE_FORWARDER_QUEUE_CAPACITY: usize = 5_000;
 
+/// How often batches whose delay is up are released to the relayer. Everything released in a
+/// tick goes out as one batch, so the relayer orders all of its packets by fee together rather
+/// than one TPU batch at a time.
+const DELAY_TICK: Duration = Duration::from_millis(5);
+
 /// Forwards packets to the Block Engine handler thread.
-/// Delays transactions for packet_delay_ms before forwarding them to the validator.
+/// Delays transactions for the shortest configured packet delay before forwarding them to the
+/// relayer, which applies longer per-validator delays. The delay is re-read from
+/// `forwarding_config` on every iteration so it can change at runtime.
+/// Transactions whose signature was seen within the last `dedup_window` are dropped from the
+/// paths selected by `dedup_mode`.
+#[allow(clippy::too_many_arguments)]
 pub fn start_forward_and_delay_thread(
     verified_receiver: Receiver<BankingPacketBatch>,
     delay_packet_sender: Sender<RelayerPacketBatches>,
-    packet_delay_ms: u32,
+    forwarding_config: &Arc<ForwardingConfig>,
     block_engine_sender: tokio::sync::mpsc::Sender<BlockEnginePackets>,
     num_threads: u64,
     disable_mempool: bool,
+    dedup_mode: DedupMode,
+    dedup_window: Duration,
     exit: &Arc<AtomicBool>,
 ) -> Vec<JoinHandle<()>> {
     const SLEEP_DURATION: Duration = Duration::from_millis(5);
-    let packet_delay = Duration::from_millis(packet_delay_ms as u64);
+
+    // shared so copies of a transaction are caught whichever thread receives them
+    let deduper = Arc::new(SignatureDeduper::new(dedup_window));
 
     (0..num_threads)
         .map(|thread_id| {
             let verified_receiver = verified_receiver.clone();
             let delay_packet_sender = delay_packet_sender.clone();
             let block_engine_sender = block_engine_sender.clone();
+            let forwarding_config = forwarding_config.clone();
+            let deduper = deduper.clone();
 
             let exit = exit.clone();
             Builder::new()
@@ -51,8 +69,15 @@ pub fn start_forward_and_delay_thread(
                         block_engine_sender.capacity(),
                     );
                     let mut last_metrics_upload = Instant::now();
+                    let mut last_release = Instant::now();
 
                     while !exit.load(Ordering::Relaxed) {
+                        let packet_delay_ms = forwarding_config.packet_delay_ms();
+                        // hold packets for the shortest delay of any validator policy; the relayer
+                        // holds them further for validators with a longer delay
+                        let packet_delay =
+                            Duration::from_millis(forwarding_config.min_packet_delay_ms() as u64);
+
                         if last_metrics_upload.elapsed() >= metrics_interval {
                             forwarder_metrics.report(thread_id, packet_delay_ms);
 
@@ -76,11 +101,27 @@ pub fn start_forward_and_delay_thread(
                                 forwarder_metrics.num_batches_received += 1;
                                 forwarder_metrics.num_packets_received += num_packets;
 
+                                let deduped_packet_batch = if dedup_mode == DedupMode::Off {
+                                    banking_packet_batch.clone()
+                                } else {
+                                    let (deduped_packet_batch, num_duplicates) =
+                                        deduper.dedup(&banking_packet_batch);
+                                    forwarder_metrics.num_dedup_packets_checked += num_packets;
+                                    forwarder_metrics.num_duplicate_packets += num_duplicates;
+                                    deduped_packet_batch
+                                };
+
                                 // try_send because the block engine receiver only drains when it's connected
                                 // and we don't want to OOM on packet_receiver
                                 if !disable_mempool {
+                                    let block_engine_packet_batch =
+                                        if dedup_mode.dedups_block_engine() {
+                                            deduped_packet_batch.clone()
+                                        } else {
+                                            banking_packet_batch.clone()
+                                        };
                                     match block_engine_sender.try_send(BlockEnginePackets {
-                                        banking_packet_batch: banking_packet_batch.clone(),
+                                        banking_packet_batch: block_engine_packet_batch,
                                         stamp: system_time,
                                         expiration: packet_delay_ms,
                                     }) {
@@ -102,7 +143,13 @@ pub fn start_forward_and_delay_thread(
                                 }
                                 buffered_packet_batches.push_back(RelayerPacketBatches {
                                     stamp: instant,
-                                    banking_packet_batch,
+                                    banking_packet_batches: vec![
+                                        if dedup_mode.dedups_validators() {
+                                            deduped_packet_batch
+                                        } else {
+                                            banking_packet_batch
+                                        },
+                                    ],
                                 });
                             }
                             Err(RecvTimeoutError::Timeout) => {}
@@ -111,16 +158,31 @@ pub fn start_forward_and_delay_thread(
                             }
                         }
 
+                        forwarder_metrics.update_queue_lengths(
+                            buffered_packet_batches.len(),
+                            buffered_packet_batches.capacity(),
+                            verified_receiver.len(),
+                            BLOCK_ENGINE_FORWARDER_QUEUE_CAPACITY - block_engine_sender.capacity(),
+                        );
+
+                        if last_release.elapsed() < DELAY_TICK {
+                            continue;
+                        }
+                        last_release = Instant::now();
+
+                        let mut expired_packet_batches = Vec::new();
                         while let Some(packet_batches) = buffered_packet_batches.front() {
                             if packet_batches.stamp.elapsed() < packet_delay {
                                 break;
                             }
-                            let batch = buffered_packet_batches.pop_front().unwrap();
-
+                            expired_packet_batches
+                                .push(buffered_packet_batches.pop_front().unwrap());
+                        }
+                        if let Some(batch) = merge_packet_batches(expired_packet_batches) {
                             let num_packets = batch
-                                .banking_packet_batch
-                                .0
+                                .banking_packet_batches
                                 .iter()
+                                .flat_map(|banking_packet_batch| banking_packet_batch.0.iter())
                                 .map(|b| b.len() as u64)
                                 .sum::<u64>();
 
@@ -129,13 +191,6 @@ pub fn start_forward_and_delay_thread(
                                 .send(batch)
                                 .expect("exiting forwarding delayed packets");
                         }
-
-                        forwarder_metrics.update_queue_lengths(
-                            buffered_packet_batches.len(),
-                            buffered_packet_batches.capacity(),
-                            verified_receiver.len(),
-                            BLOCK_ENGINE_FORWARDER_QUEUE_CAPACITY - block_engine_sender.capacity(),
-                        );
                     }
                 })
                 .unwrap()
@@ -143,6 +198,20 @@ pub fn start_forward_and_delay_thread(
         .collect()
 }
 
+/// Merges the batches into one stamped with the newest batch's stamp, so no packet is
+/// forwarded before its delay is up. The banking packet batches are moved, never copied.
+fn merge_packet_batches(packet_batches: Vec<RelayerPacketBatches>) -> Option<RelayerPacketBatches> {
+    let stamp = packet_batches.last()?.stamp;
+    let banking_packet_batches = packet_batches
+        .into_iter()
+        .flat_map(|batch| batch.banking_packet_batches)
+        .collect();
+    Some(RelayerPacketBatches {
+        stamp,
+        banking_packet_batches,
+    })
+}
+
 struct ForwarderMetrics {
     pub num_batches_received: u64,
     pub num_packets_received: u64,
@@ -153,6 +222,10 @@ struct ForwarderMetrics {
 
     pub num_relayer_packets_forwarded: u64,
 
+    // packets looked up in the deduper and those it found duplicated
+    pub num_dedup_packets_checked: u64,
+    pub num_duplicate_packets: u64,
+
     // high water mark on queue lengths
     pub buffered_packet_batches_max_len: usize,
     pub buffered_packet_batches_capacity: usize,
@@ -175,6 +248,8 @@ impl ForwarderMetrics {
             num_be_packets_dropped: 0,
             num_be_sender_full: 0,
             num_relayer_packets_forwarded: 0,
+            num_dedup_packets_checked: 0,
+            num_duplicate_packets: 0,
             buffered_packet_batches_max_len: 0,
             buffered_packet_batches_capacity,
             verified_receiver_max_len: 0,
@@ -207,6 +282,11 @@ impl ForwarderMetrics {
     }
 
     pub fn report(&self, thread_id: u64, delay: u32) {
+        let dedup_hit_rate = if self.num_dedup_packets_checked == 0 {
+            0.0
+        } else {
+            self.num_duplicate_packets as f64 / self.num_dedup_packets_checked as f64
+        };
         datapoint_info!(
             "forwarder_metrics",
             ("thread_id", thread_id, i64),
@@ -227,6 +307,14 @@ impl ForwarderMetrics {
                 self.num_relayer_packets_forwarded,
                 i64
             ),
+            // Dedup metrics
+            (
+                "num_dedup_packets_checked",
+                self.num_dedup_packets_checked,
+                i64
+            ),
+            ("num_duplicate_packets", self.num_duplicate_packets, i64),
+            ("dedup_hit_rate", dedup_hit_rate, f64),
             // Channel stats
             (
                 "buffered_packet_batches_len",
@@ -257,3 +345,45 @@ impl ForwarderMetrics {
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::Arc,
+        time::{Duration, Instant},
+    };
+
+    use jito_relayer::relayer::RelayerPacketBatches;
+    use solana_perf::packet::{Packet, PacketBatch};
+
+    use crate::forwarder::merge_packet_batches;
+
+    fn packet_batches(stamp: Instant, num_packets: usize) -> RelayerPacketBatches {
+        RelayerPacketBatches {
+            stamp,
+            banking_packet_batches: vec![Arc::new((
+                vec![PacketBatch::new(vec![Packet::default(); num_packets])],
+                None,
+            ))],
+        }
+    }
+
+    #[test]
+    fn test_merge_packet_batches() {
+        assert!(merge_packet_batches(vec![]).is_none());
+
+        let older = Instant::now() - Duration::from_millis(10);
+        let newer = Instant::now();
+        let merged =
+            merge_packet_batches(vec![packet_batches(older, 2), packet_batches(newer, 3)]).unwrap();
+        // stamped with the newest batch so none is released early
+        assert_eq!(merged.stamp, newer);
+        let num_packets: usize = merged
+            .banking_packet_batches
+            .iter()
+            .flat_map(|banking_packet_batch| banking_packet_batch.0.iter())
+            .map(|b| b.len())
+            .sum();
+        assert_eq!(num_packets, 5);
+    }
+}
@@ -1 +1,3 @@
+pub mod config;
+pub mod dedup;
 pub mod forwarder;
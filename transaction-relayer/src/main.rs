@@ -1,55 +1,73 @@
 use std::{
     collections::HashSet,
-    fs,
+    env, fs,
     net::{IpAddr, Ipv4Addr, SocketAddr},
     ops::Range,
     path::PathBuf,
+    process,
     str::FromStr,
     sync::{
         atomic::{AtomicBool, Ordering},
         Arc,
     },
-    thread,
-    thread::JoinHandle,
-    time::{Duration, Instant},
+    time::Duration,
 };
 
 use agave_validator::admin_rpc_service::StakedNodesOverrides;
-use clap::Parser;
-use crossbeam_channel::tick;
+use clap::{CommandFactory, FromArgMatches, Parser};
 use dashmap::DashMap;
 use env_logger::Env;
+use ipnet::IpNet;
 use jito_block_engine::block_engine::{BlockEngineConfig, BlockEngineRelayerHandler};
 use jito_core::{
     graceful_panic,
+    lookup_table_cache::{LookupTableCacheUpdater, LookupTableSnapshotConfig},
+    lookup_table_fetcher::LookupTableFetcher,
+    ofac::{LookupTablePolicy, OfacFilter},
+    ofac_updater::{OfacAddressesHandle, OfacAddressesUpdater},
+    pubkey_list::{PubkeyListHandle, PubkeyListUpdater},
     tpu::{Tpu, TpuSockets},
 };
 use jito_protos::{
-    auth::auth_service_server::AuthServiceServer, relayer::relayer_server::RelayerServer,
+    admin::relayer_admin_server::RelayerAdminServer, auth::auth_service_server::AuthServiceServer,
+    encoded::relayer_server::RelayerServer,
 };
 use jito_relayer::{
-    auth_interceptor::AuthInterceptor,
+    admin_service::RelayerAdminImpl,
+    auth_events::{AuthEventLog, AuthEventLogger},
+    auth_interceptor::{AuthInterceptor, ClientIpBinding},
+    auth_rate_limiter::{AuthRateLimiter, RateLimit},
     auth_service::{AuthServiceImpl, ValidatorAuther},
+    forwarding_config::{ForwardingConfig, ForwardingParams},
     health_manager::HealthManager,
+    keyring::{Keyring, KeyringHandle, KeyringUpdater},
+    refresh_tokens::{RefreshTokenPersister, RefreshTokenStore},
     relayer::RelayerImpl,
-    schedule_cache::{LeaderScheduleCacheUpdater, LeaderScheduleUpdatingHandle},
+    schedule_cache::LeaderScheduleCacheUpdater,
+    server_tls::{ServerTlsHandle, ServerTlsPaths, ServerTlsUpdater},
+    sessions::SessionTable,
+    subscriber_queue::Backpressure,
+    validator_authers::{AllOf, Allowlist, AnyOf, Denylist, MinimumStake, ScheduledLeaders},
+    validator_policy::ValidatorPolicies,
 };
 use jito_relayer_web::{start_relayer_web_server, RelayerState};
 use jito_rpc::load_balancer::LoadBalancer;
-use jito_transaction_relayer::forwarder::start_forward_and_delay_thread;
-use jwt::{AlgorithmType, PKeyWithDigest};
-use log::{debug, error, info, warn};
-use openssl::{hash::MessageDigest, pkey::PKey};
-use solana_metrics::{datapoint_error, datapoint_info};
+use jito_transaction_relayer::{
+    config::{self, ConfigError},
+    dedup::DedupMode,
+    forwarder::start_forward_and_delay_thread,
+};
+use log::{error, info, warn};
+use solana_metrics::datapoint_info;
 use solana_net_utils::multi_bind_in_range;
-use solana_program::address_lookup_table::{state::AddressLookupTable, AddressLookupTableAccount};
+use solana_program::address_lookup_table::AddressLookupTableAccount;
 use solana_sdk::{
     pubkey::Pubkey,
     signature::{read_keypair_file, Signer},
 };
 use tikv_jemallocator::Jemalloc;
-use tokio::{runtime::Builder, signal, sync::mpsc::channel};
-use tonic::transport::Server;
+use tokio::{net::TcpListener, runtime::Builder, signal, sync::mpsc::channel};
+use tonic::transport::{server::TcpIncoming, Server};
 
 // no-op change to test ci
 
@@ -63,6 +81,18 @@ static GLOBAL: Jemalloc = Jemalloc;
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
 struct Args {
+    /// Path to a TOML or YAML config file setting any of these arguments.
+    /// Keys are the argument names in snake_case, e.g. `packet_delay_ms = 200`.
+    /// Precedence is command line > environment variable > config file > default.
+    /// Files with a `.toml` extension are parsed as TOML, anything else as YAML.
+    #[arg(long, env)]
+    config: Option<PathBuf>,
+
+    /// Print the effective configuration after merging the command line, environment,
+    /// and config file, then exit. Secrets and URL credentials are redacted.
+    #[arg(long, default_value_t = false)]
+    print_effective_config: bool,
+
     /// DEPRECATED: Legacy UDP TPU port (Transaction Processing Unit)
     /// UDP-based TPU was replaced with QUIC for better performance and reliability.
     /// This field remains for backward compatibility but is no longer used.
@@ -128,6 +158,26 @@ struct Args {
     #[arg(long, env, default_value_t = 11_226)]
     grpc_bind_port: u16,
 
+    /// PEM certificate chain to serve the relayer and auth gRPC services over TLS with.
+    /// Without TLS, JWT bearer tokens travel in plaintext. Requires grpc_tls_key_path.
+    #[arg(long, env, requires = "grpc_tls_key_path")]
+    grpc_tls_cert_path: Option<PathBuf>,
+
+    /// PEM private key of grpc_tls_cert_path.
+    #[arg(long, env, requires = "grpc_tls_cert_path")]
+    grpc_tls_key_path: Option<PathBuf>,
+
+    /// PEM CA certificates that client certificates must be signed by (mTLS).
+    /// Clients without such a certificate are refused before challenge/JWT auth, so private
+    /// relayers can only be reached by known validators. Requires grpc_tls_cert_path.
+    #[arg(long, env, requires = "grpc_tls_cert_path")]
+    grpc_tls_client_ca_path: Option<PathBuf>,
+
+    /// How often to check the gRPC TLS files for changes, in seconds.
+    /// New connections use the reloaded certificates without a restart.
+    #[arg(long, env, default_value_t = 10)]
+    grpc_tls_refresh_secs: u64,
+
     /// List of Solana RPC server HTTP URLs for blockchain queries (space-separated).
     /// These servers provide access to blockchain state, account data, and transaction submission.
     /// The LoadBalancer automatically routes requests to the server with the highest slot
@@ -222,30 +272,89 @@ struct Args {
     /// Restricts access to only specified validators for enhanced security.
     /// Use comma-separated list of base58-encoded pubkeys.
     ///
-    /// If not specified (null), all validators in the current leader schedule
-    /// are automatically permitted to authenticate. This is the typical configuration
-    /// for open relayers that serve the entire validator set.
+    /// If no allow rule (this, allowed_validators_path or min_validator_stake_lamports) is
+    /// specified, all validators in the current or next epoch's leader schedule are
+    /// automatically permitted to authenticate. This is the typical configuration for open
+    /// relayers that serve the entire validator set.
     ///
     /// Example: "pubkey1,pubkey2,pubkey3"
     #[arg(long, env, value_delimiter = ',')]
     allowed_validators: Option<Vec<Pubkey>>,
 
+    /// Path to a file of validator pubkeys allowed to authenticate, in the same formats as
+    /// ofac_addresses_path. Merged with allowed_validators and reloaded when it changes.
+    #[arg(long, env)]
+    allowed_validators_path: Option<PathBuf>,
+
+    /// Allow validators with at least this much activated stake, in lamports.
+    #[arg(long, env)]
+    min_validator_stake_lamports: Option<u64>,
+
+    /// Also allow validators in the current or next epoch's leader schedule when other allow
+    /// rules are set.
+    /// Scheduled leaders are always allowed when no allow rule is set.
+    #[arg(long, env, default_value_t = false)]
+    allow_scheduled_leaders: bool,
+
+    /// Require validators to pass every allow rule instead of any of them, e.g. to only allow
+    /// validators on the allowlist that also have min_validator_stake_lamports.
+    #[arg(long, env, default_value_t = false)]
+    require_all_validator_rules: bool,
+
+    /// Validator pubkeys that can't authenticate regardless of the allow rules
+    /// (comma-separated).
+    #[arg(long, env, value_delimiter = ',')]
+    denied_validators: Option<Vec<Pubkey>>,
+
+    /// Path to a file of validator pubkeys that can't authenticate, in the same formats as
+    /// ofac_addresses_path. Merged with denied_validators and reloaded when it changes.
+    #[arg(long, env)]
+    denied_validators_path: Option<PathBuf>,
+
+    /// How often to check allowed_validators_path and denied_validators_path for changes,
+    /// in seconds.
+    #[arg(long, env, default_value_t = 10)]
+    validator_lists_refresh_secs: u64,
+
     /// Path to PEM-encoded private key file for JWT token signing.
     /// This key is used by the authentication service to sign access tokens
     /// and refresh tokens issued to authenticated validators.
     ///
+    /// Tokens name the key in their `kid` header, which is this file's name without extension.
+    /// The key type selects the signing algorithm: RSA keys sign with RS256, P-256 keys with
+    /// ES256 and Ed25519 keys with EdDSA.
+    ///
     /// SECURITY: Must be kept secure with restricted file permissions (600).
     /// Compromise of this key allows unauthorized token generation.
-    #[arg(long, env)]
-    signing_key_pem_path: PathBuf,
+    #[arg(long, env, required_unless_present = "jwt_keyring_dir")]
+    signing_key_pem_path: Option<PathBuf>,
 
     /// Path to PEM-encoded public key file for JWT token verification.
     /// This key is used to verify the authenticity of tokens presented by validators.
     /// Multiple services can share this public key for distributed token verification.
     ///
     /// Must correspond to the private key specified in signing_key_pem_path.
-    #[arg(long, env)]
-    verifying_key_pem_path: PathBuf,
+    #[arg(long, env, required_unless_present = "jwt_keyring_dir")]
+    verifying_key_pem_path: Option<PathBuf>,
+
+    /// Directory of PEM key files used instead of signing_key_pem_path and
+    /// verifying_key_pem_path, allowing keys to be rotated without logging out validators.
+    /// Files are named `<kid>.pem`. The single private key is the active signing key, public
+    /// keys keep verifying tokens signed by earlier keys. The directory is reloaded when it
+    /// changes; keys removed from it keep verifying until the tokens they signed expire.
+    ///
+    /// To rotate: add the new private key, then replace the old private key file with its public
+    /// key, e.g. `openssl pkey -in old.pem -pubout -out old.pem.new && mv old.pem.new old.pem`.
+    #[arg(
+        long,
+        env,
+        conflicts_with_all = ["signing_key_pem_path", "verifying_key_pem_path"]
+    )]
+    jwt_keyring_dir: Option<PathBuf>,
+
+    /// How often to check jwt_keyring_dir for changes, in seconds.
+    #[arg(long, env, default_value_t = 10)]
+    jwt_keyring_refresh_secs: u64,
 
     /// Time-to-live for access tokens in seconds (default: 30 minutes).
     /// Access tokens are short-lived credentials that validators use for API calls.
@@ -261,6 +370,67 @@ struct Args {
     #[arg(long, env, default_value_t = 180_000)]
     refresh_token_ttl_secs: u64,
 
+    /// Path to a JSON file persisting refresh token families and revocations.
+    /// When unset they're only kept in memory, so revocations are forgotten on restart and, with
+    /// rotation enabled, refresh tokens issued before a restart are rejected and validators
+    /// authenticate with a new challenge.
+    #[arg(long, env)]
+    refresh_token_store_path: Option<PathBuf>,
+
+    /// Rotate refresh tokens on every refresh.
+    /// Each refresh returns a new refresh token in the `x-refresh-token` response metadata and
+    /// retires the old one; reusing a retired token revokes every refresh token from the same
+    /// authentication. Only enable once clients read the metadata, as the others have to
+    /// authenticate again after their refresh token is rotated out. Applies to refresh tokens
+    /// issued after the relayer starts.
+    ///
+    /// While enabled, refresh tokens the relayer has no record of are rejected: those issued
+    /// before refresh tokens were tracked, and those issued before a restart without
+    /// --refresh-token-store-path. Their validators have to authenticate again.
+    #[arg(long, env, default_value_t = false)]
+    enable_refresh_token_rotation: bool,
+
+    /// Requests per second each client IP can make to each auth service RPC, 0 to disable.
+    /// Issuing tokens is CPU intensive, so this keeps a single host from starving the others.
+    /// Requests over the limit are rejected with `resource_exhausted`.
+    #[arg(long, env, default_value_t = 1.0)]
+    auth_rate_limit_per_ip: f64,
+
+    /// Requests per second each validator pubkey can make to each auth service RPC,
+    /// 0 to disable.
+    #[arg(long, env, default_value_t = 0.5)]
+    auth_rate_limit_per_pubkey: f64,
+
+    /// Requests a client IP or pubkey can make to an auth service RPC in a burst before
+    /// auth_rate_limit_per_ip and auth_rate_limit_per_pubkey apply.
+    #[arg(long, env, default_value_t = 10)]
+    auth_rate_limit_burst: u32,
+
+    /// Reject access tokens used from an IP address other than the one they were issued to,
+    /// so a leaked token can't be used from elsewhere. Clients that change address have to
+    /// authenticate again.
+    #[arg(long, env, default_value_t = false)]
+    enforce_client_ip_binding: bool,
+
+    /// Address ranges within which a token issued to one address is accepted from any other
+    /// (space-separated CIDRs), for validators behind NAT pools. Only used with
+    /// enforce_client_ip_binding.
+    ///
+    /// Example: "203.0.113.0/28 2001:db8::/64"
+    #[arg(
+        long,
+        env,
+        value_delimiter = ' ',
+        requires = "enforce_client_ip_binding"
+    )]
+    client_ip_binding_allowed_cidrs: Vec<IpNet>,
+
+    /// File to append the authentication audit log to as JSON lines, or `-` for stdout.
+    /// Records issued challenges and tokens, refreshes, rejected auth requests with a reason
+    /// code, and packet subscriptions opening and closing. Disabled when unset.
+    #[arg(long, env)]
+    auth_event_log: Option<PathBuf>,
+
     /// Time-to-live for authentication challenges in seconds (default: 30 minutes).
     /// Challenges are cryptographic puzzles sent to validators during initial auth.
     /// Must be long enough for validators to process but short enough to prevent replay attacks.
@@ -275,6 +445,12 @@ struct Args {
     #[arg(long, env, default_value_t = 180)]
     challenge_expiration_sleep_interval_secs: u64,
 
+    /// Maximum number of outstanding authentication challenges per client IP address.
+    /// Each validator identity authenticating from an address holds one challenge, so this
+    /// bounds how many validators behind one NAT address can authenticate at the same time.
+    #[arg(long, env, default_value_t = 16)]
+    max_auth_challenges_per_ip: usize,
+
     /// Slot miss threshold for marking the system as unhealthy (seconds).
     /// If no slot updates are received within this timeframe, the health
     /// manager marks the system as unhealthy, which affects metrics and
@@ -306,20 +482,36 @@ struct Args {
     #[arg(long, env, default_value_t = 300)]
     aoi_cache_ttl_secs: u64,
 
-    /// Interval for refreshing Solana address lookup tables (seconds).
+    /// Interval for full reconciliation scans of Solana address lookup tables (seconds).
     /// Address lookup tables compress transaction sizes by storing frequently used addresses.
-    /// Regular refresh ensures the relayer has current lookup table data for transaction processing.
+    /// The lookup table cache is kept current incrementally by a program subscription, so
+    /// these scans are only a safety net against missed updates and can be infrequent. While the
+    /// subscription is down, e.g. on RPC providers without programSubscribe, the cache is
+    /// scanned at least every minute instead.
     /// Only active when enable_lookup_table_refresh is true.
-    #[arg(long, env, default_value_t = 600)]
+    #[arg(long, env, default_value_t = 3_600)]
     lookup_table_refresh_secs: u64,
 
-    /// Enable automatic refresh of address lookup table data from RPC servers.
-    /// When enabled, periodically fetches all address lookup tables to keep local cache current.
-    /// Improves transaction processing efficiency but increases RPC load.
+    /// Enable the address lookup table cache.
+    /// When enabled, the cache is seeded with a full scan of all address lookup tables, then
+    /// kept current by a websocket subscription to the address lookup table program, so
+    /// newly created and extended tables are visible within a slot.
     /// Recommended for high-throughput relayers handling many compressed transactions.
     #[arg(long, env, default_value_t = false)]
     enable_lookup_table_refresh: bool,
 
+    /// Path of the address lookup table cache snapshot.
+    /// When set, the cache is loaded from this file at boot so forwarding has lookup table
+    /// data immediately after a restart, while the first full scan reconciles it in the
+    /// background. The snapshot is rewritten periodically and on shutdown.
+    /// Only active when enable_lookup_table_refresh is true.
+    #[arg(long, env)]
+    lookup_table_snapshot_path: Option<PathBuf>,
+
+    /// Interval between address lookup table cache snapshots (seconds).
+    #[arg(long, env, default_value_t = 60)]
+    lookup_table_snapshot_interval_secs: u64,
+
     /// List of addresses subject to OFAC sanctions (space-separated pubkeys).
     /// Transactions involving any of these addresses will be automatically dropped
     /// for regulatory compliance. This includes transactions that:
@@ -331,6 +523,49 @@ struct Args {
     #[arg(long, env, value_delimiter = ' ', value_parser = Pubkey::from_str)]
     ofac_addresses: Option<Vec<Pubkey>>,
 
+    /// Path to a file containing OFAC-sanctioned addresses.
+    /// Accepts either plain text (one base58 pubkey per line, `#` for comments)
+    /// or a JSON array of base58 pubkeys. Addresses from --ofac-addresses are always included.
+    ///
+    /// The file is watched for changes and reloaded without a restart, so compliance
+    /// teams can update the sanctions list on a running relayer.
+    #[arg(long, env)]
+    ofac_addresses_path: Option<PathBuf>,
+
+    /// How often to check the OFAC addresses file for modifications (seconds).
+    /// Only used when --ofac-addresses-path is set.
+    #[arg(long, env, default_value_t = 10)]
+    ofac_addresses_refresh_secs: u64,
+
+    /// Log every transaction dropped by the OFAC filter, including its signature,
+    /// the match reason, and the sanctioned key. Lines are logged under the `ofac_audit` target
+    /// so they can be routed separately for compliance records.
+    #[arg(long, env, default_value_t = false)]
+    ofac_audit_log: bool,
+
+    /// How the OFAC filter treats transactions that reference an address lookup table
+    /// missing from the lookup table cache:
+    /// - fail-open: forward them, only checking the addresses that could be resolved
+    /// - fail-closed: drop them
+    /// - drop-and-fetch: drop them and fetch the missing table from RPC in the background, so
+    ///   later transactions using the table are checked against it. The first transactions
+    ///   through a table the cache hasn't seen are always dropped
+    ///
+    /// COMPLIANCE: fail-open lets transactions using freshly created lookup tables bypass
+    /// the sanctions list until the next lookup table refresh.
+    #[arg(long, env, default_value_t = LookupTablePolicy::FailOpen)]
+    ofac_lookup_table_policy: LookupTablePolicy,
+
+    /// Maximum number of missing lookup tables fetched from RPC per second.
+    /// Only used with --ofac-lookup-table-policy drop-and-fetch.
+    #[arg(long, env, default_value_t = 50)]
+    ofac_lookup_table_max_fetches_per_sec: u64,
+
+    /// How long to wait before retrying a lookup table that failed to fetch (seconds).
+    /// Only used with --ofac-lookup-table-policy drop-and-fetch.
+    #[arg(long, env, default_value_t = 60)]
+    ofac_lookup_table_fetch_retry_secs: u64,
+
     /// Bind address for the diagnostic web server.
     /// Exposes health metrics, system status, and operational information via HTTP endpoints.
     /// Used for monitoring, alerting, and operational visibility.
@@ -338,6 +573,28 @@ struct Args {
     #[arg(long, env, default_value_t = SocketAddr::from_str("127.0.0.1:11227").unwrap())]
     webserver_bind_addr: SocketAddr,
 
+    /// Bearer token for the admin endpoints on the diagnostic web server.
+    /// The admin endpoints change forwarding parameters (packet_delay_ms,
+    /// validator_packet_batch_size, forward_all, slot_lookahead, previous_leader_grace_ms,
+    /// backpressure, slow_subscriber_timeout_ms) on a running relayer: `GET /admin/forwarding`
+    /// returns them, `POST /admin/forwarding` with a JSON body of the parameters to change
+    /// updates them. `GET /admin/sessions` lists each validator's IP, token expirations, last
+    /// refresh and how long it's been subscribed. The admin endpoints are disabled when unset.
+    ///
+    /// SECURITY: Treat as a secret; anyone holding it can change forwarding behavior.
+    #[arg(long, env, hide_env_values = true)]
+    admin_token: Option<String>,
+
+    /// Bind address for the admin gRPC service.
+    /// The admin service lists validator subscriptions, disconnects or temporarily bans
+    /// validators, triggers leader schedule refreshes, and reports health inputs.
+    /// Disabled when unset.
+    ///
+    /// SECURITY: The admin service is unauthenticated, so it must be a loopback or private
+    /// address.
+    #[arg(long, env)]
+    admin_grpc_bind_addr: Option<SocketAddr>,
+
     /// Maximum concurrent QUIC connections from unstaked validators.
     /// Unstaked validators have lower priority and resource allocation.
     /// Lower limit prevents unstaked validators from overwhelming the relayer
@@ -359,6 +616,12 @@ struct Args {
     #[arg(long, env, default_value_t = 4)]
     validator_packet_batch_size: usize,
 
+    /// Number of threads forwarding packets to subscribed validators, each serving a share of
+    /// the subscriptions. Raise it when many validators subscribe with forward_all and a
+    /// single thread can't keep up with sending every batch to all of them.
+    #[arg(long, env, default_value_t = 1)]
+    num_forwarding_threads: usize,
+
     /// Disable forwarding transactions to the mempool/gossip network.
     /// When true, transactions are only forwarded directly to current leaders
     /// and not broadcast to the wider network. This can improve performance
@@ -366,6 +629,18 @@ struct Args {
     #[arg(long, env, default_value_t = false)]
     disable_mempool: bool,
 
+    /// Which forwarding paths drop transactions whose signature was already seen.
+    /// Clients often send the same transaction over many connections; `validators` drops the
+    /// copies forwarded to validators, `block_engine` those forwarded to the block engine,
+    /// `both` drops them from both paths and `off` forwards every copy.
+    #[arg(long, env, default_value_t = DedupMode::Both)]
+    dedup_mode: DedupMode,
+
+    /// How long a transaction's signature is remembered for dedup, between one and two windows.
+    /// Should cover the time clients keep resending a transaction.
+    #[arg(long, env, default_value_t = 2_000)]
+    dedup_window_ms: u64,
+
     /// Forward transactions to ALL connected validators regardless of leader schedule.
     /// When true, ignores leader schedule and broadcasts to all validators.
     ///
@@ -407,6 +682,217 @@ struct Args {
     /// Default 5 slots (~2 seconds) balances predictability with responsiveness.
     #[arg(long, env, default_value_t = 5)]
     slot_lookahead: u64,
+
+    /// How long the previous leader keeps receiving packets after the next leader's slots begin.
+    /// Covers packets that race the slot boundary while the previous leader is still finishing
+    /// its last block. Set to 0 to stop forwarding to a leader as soon as its slots end.
+    #[arg(long, env, default_value_t = 200)]
+    previous_leader_grace_ms: u32,
+
+    /// What happens to packets sent to a validator whose queue is full.
+    /// `drop_newest` drops the packets being sent, `drop_oldest` drops the oldest queued packets
+    /// so the validator keeps receiving the freshest ones, and `disconnect` drops the packets
+    /// being sent and disconnects the validator once its queue has been full for longer than
+    /// slow_subscriber_timeout_ms.
+    #[arg(long, env, default_value_t = Backpressure::DropNewest)]
+    backpressure: Backpressure,
+
+    /// How long a validator's queue can stay full before it's disconnected.
+    /// Only applies to validators with the `disconnect` backpressure policy.
+    #[arg(long, env, default_value_t = 5_000)]
+    slow_subscriber_timeout_ms: u32,
+
+    /// Path to YAML file of per-validator forwarding policies.
+    /// Each entry overrides slot_lookahead, validator_packet_batch_size, forward_all,
+    /// packet_delay_ms and backpressure for one validator; parameters it doesn't set use the
+    /// global values.
+    ///
+    /// File format (YAML):
+    /// ```yaml
+    /// "validator_pubkey_1":
+    ///   slot_lookahead: 10
+    ///   packet_delay_ms: 50
+    /// "validator_pubkey_2":
+    ///   forward_all: true
+    ///   backpressure: drop_oldest
+    /// ```
+    #[arg(long, env)]
+    validator_policies_path: Option<PathBuf>,
+}
+
+impl Args {
+    /// Checks the arguments for problems that would otherwise fail startup later on,
+    /// returning all of them so they can be fixed at once.
+    fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        if self.num_tpu_quic_servers == u16::MAX {
+            problems.push("num_tpu_quic_servers must be less than 65535".to_string());
+        }
+        if self.num_tpu_fwd_quic_servers == u16::MAX {
+            problems.push("num_tpu_fwd_quic_servers must be less than 65535".to_string());
+        }
+        let tpu_ports_end = self.tpu_quic_port.checked_add(self.num_tpu_quic_servers);
+        let tpu_fwd_ports_end = self
+            .tpu_quic_fwd_port
+            .checked_add(self.num_tpu_fwd_quic_servers);
+        match (tpu_ports_end, tpu_fwd_ports_end) {
+            (Some(tpu_ports_end), Some(tpu_fwd_ports_end)) => {
+                if self.tpu_quic_port < tpu_fwd_ports_end && self.tpu_quic_fwd_port < tpu_ports_end
+                {
+                    problems.push(format!(
+                        "TPU ports [{}, {tpu_ports_end}) overlap TPU forward ports [{}, {})",
+                        self.tpu_quic_port, self.tpu_quic_fwd_port, tpu_fwd_ports_end
+                    ));
+                }
+            }
+            (None, _) => problems
+                .push("tpu_quic_port + num_tpu_quic_servers must not exceed 65535".to_string()),
+            (_, None) => problems.push(
+                "tpu_quic_fwd_port + num_tpu_fwd_quic_servers must not exceed 65535".to_string(),
+            ),
+        }
+
+        if self.rpc_servers.len() != self.websocket_servers.len() {
+            problems.push(format!(
+                "number of rpc servers ({}) must match number of websocket servers ({})",
+                self.rpc_servers.len(),
+                self.websocket_servers.len()
+            ));
+        }
+
+        // IPv4-only restriction for security: IPv6 addresses are cheap to generate
+        // in large quantities, which could be used to overwhelm the authentication
+        // challenge queue and create a denial-of-service attack vector.
+        if !self.grpc_bind_ip.is_ipv4() {
+            problems.push(format!(
+                "grpc_bind_ip must be an IPv4 address, got {}",
+                self.grpc_bind_ip
+            ));
+        }
+        if let Some(public_ip) = self.public_ip {
+            if !public_ip.is_ipv4() {
+                problems.push(format!(
+                    "public_ip must be an IPv4 address, got {public_ip}"
+                ));
+            }
+            if public_ip.is_loopback() {
+                problems.push("public_ip can't be the loopback interface".to_string());
+            }
+        }
+
+        if let Some(admin_grpc_bind_addr) = self.admin_grpc_bind_addr {
+            let is_private = match admin_grpc_bind_addr.ip() {
+                IpAddr::V4(ip) => ip.is_loopback() || ip.is_private(),
+                IpAddr::V6(ip) => ip.is_loopback(),
+            };
+            if !is_private {
+                problems.push(format!(
+                    "admin_grpc_bind_addr must be a loopback or private address, got {}",
+                    admin_grpc_bind_addr.ip()
+                ));
+            }
+        }
+
+        for (name, path) in [
+            ("keypair_path", Some(&self.keypair_path)),
+            ("signing_key_pem_path", self.signing_key_pem_path.as_ref()),
+            (
+                "verifying_key_pem_path",
+                self.verifying_key_pem_path.as_ref(),
+            ),
+            (
+                "staked_nodes_overrides",
+                self.staked_nodes_overrides.as_ref(),
+            ),
+            ("ofac_addresses_path", self.ofac_addresses_path.as_ref()),
+            (
+                "allowed_validators_path",
+                self.allowed_validators_path.as_ref(),
+            ),
+            (
+                "denied_validators_path",
+                self.denied_validators_path.as_ref(),
+            ),
+            (
+                "validator_policies_path",
+                self.validator_policies_path.as_ref(),
+            ),
+            ("grpc_tls_cert_path", self.grpc_tls_cert_path.as_ref()),
+            ("grpc_tls_key_path", self.grpc_tls_key_path.as_ref()),
+            (
+                "grpc_tls_client_ca_path",
+                self.grpc_tls_client_ca_path.as_ref(),
+            ),
+        ] {
+            if let Some(path) = path {
+                if !path.is_file() {
+                    problems.push(format!("{name} {path:?} is not a readable file"));
+                }
+            }
+        }
+
+        if let Some(jwt_keyring_dir) = &self.jwt_keyring_dir {
+            if !jwt_keyring_dir.is_dir() {
+                problems.push(format!(
+                    "jwt_keyring_dir {jwt_keyring_dir:?} is not a readable directory"
+                ));
+            }
+        }
+
+        for (name, value) in [
+            ("auth_rate_limit_per_ip", self.auth_rate_limit_per_ip),
+            (
+                "auth_rate_limit_per_pubkey",
+                self.auth_rate_limit_per_pubkey,
+            ),
+        ] {
+            if !value.is_finite() || value < 0.0 {
+                problems.push(format!(
+                    "{name} must be 0 or a positive number, got {value}"
+                ));
+            }
+        }
+
+        for (name, value) in [
+            (
+                "validator_packet_batch_size",
+                self.validator_packet_batch_size as u64,
+            ),
+            ("slot_lookahead", self.slot_lookahead),
+            ("num_forwarding_threads", self.num_forwarding_threads as u64),
+            ("dedup_window_ms", self.dedup_window_ms),
+            (
+                "challenge_expiration_sleep_interval_secs",
+                self.challenge_expiration_sleep_interval_secs,
+            ),
+            (
+                "ofac_addresses_refresh_secs",
+                self.ofac_addresses_refresh_secs,
+            ),
+            (
+                "lookup_table_snapshot_interval_secs",
+                self.lookup_table_snapshot_interval_secs,
+            ),
+            ("jwt_keyring_refresh_secs", self.jwt_keyring_refresh_secs),
+            (
+                "validator_lists_refresh_secs",
+                self.validator_lists_refresh_secs,
+            ),
+            ("grpc_tls_refresh_secs", self.grpc_tls_refresh_secs),
+            ("auth_rate_limit_burst", self.auth_rate_limit_burst as u64),
+            (
+                "max_auth_challenges_per_ip",
+                self.max_auth_challenges_per_ip as u64,
+            ),
+        ] {
+            if value == 0 {
+                problems.push(format!("{name} must be greater than 0"));
+            }
+        }
+
+        problems
+    }
 }
 
 /// Container for all QUIC socket bindings used by the TPU system.
@@ -526,9 +1012,31 @@ fn main() {
         .format_timestamp_millis()
         .init();
 
-    // Parse command-line arguments and environment variables
-    let args: Args = Args::parse();
-    info!("args: {:?}", args);
+    // Parse command-line arguments, environment variables, and the optional config file
+    let command = Args::command();
+    let matches = match config::get_layered_matches(command.clone(), env::args_os().collect()) {
+        Ok(matches) => matches,
+        Err(ConfigError::Clap(e)) => e.exit(),
+        Err(e) => {
+            error!("{e}");
+            process::exit(1);
+        }
+    };
+    let args = Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    let effective_config = config::effective_config(&command, &matches);
+    if args.print_effective_config {
+        print!("{effective_config}");
+        return;
+    }
+    info!("effective config:\n{effective_config}");
+
+    let problems = args.validate();
+    if !problems.is_empty() {
+        for problem in &problems {
+            error!("invalid configuration: {problem}");
+        }
+        process::exit(1);
+    }
 
     // Issue deprecation warnings for legacy arguments
     if args.cluster.is_some() {
@@ -555,7 +1063,8 @@ fn main() {
 
     info!("public ip: {:?}", public_ip);
 
-    // Validate that the public IP is suitable for network operations
+    // Validate that the public IP is suitable for network operations, which matters when it
+    // was discovered through the entrypoint rather than checked as an argument
     assert!(
         public_ip.is_ipv4(),
         "Your public IP address needs to be IPv4 but is currently listed as {}. \
@@ -568,11 +1077,6 @@ fn main() {
         "Your public IP can't be the loopback interface"
     );
 
-    // IPv4-only restriction for security: IPv6 addresses are cheap to generate
-    // in large quantities, which could be used to overwhelm the authentication
-    // challenge queue and create a denial-of-service attack vector.
-    assert!(args.grpc_bind_ip.is_ipv4(), "must bind to IPv4 address");
-
     let sockets = get_sockets(&args);
     let tpu_quic_ports: Vec<u16> = sockets
         .tpu_sockets
@@ -626,35 +1130,69 @@ fn main() {
 
     let exit = graceful_panic(None);
 
-    assert_eq!(
-        args.rpc_servers.len(),
-        args.websocket_servers.len(),
-        "number of rpc servers must match number of websocket servers"
-    );
-
     let servers: Vec<(String, String)> = args
         .rpc_servers
         .into_iter()
         .zip(args.websocket_servers)
         .collect();
 
-    let ofac_addresses: HashSet<Pubkey> = args
+    let static_ofac_addresses: HashSet<Pubkey> = args
         .ofac_addresses
         .map(|a| a.into_iter().collect())
         .unwrap_or_default();
-    info!("ofac addresses: {:?}", ofac_addresses);
+    info!("ofac addresses: {:?}", static_ofac_addresses);
+
+    let ofac_addresses = OfacAddressesHandle::new(static_ofac_addresses.clone());
+    let ofac_addresses_updater = args.ofac_addresses_path.map(|path| {
+        OfacAddressesUpdater::new(
+            path,
+            static_ofac_addresses,
+            &ofac_addresses,
+            Duration::from_secs(args.ofac_addresses_refresh_secs),
+            &exit,
+        )
+    });
 
     let (rpc_load_balancer, slot_receiver) = LoadBalancer::new(&servers, &exit);
     let rpc_load_balancer = Arc::new(rpc_load_balancer);
 
+    info!(
+        "ofac lookup table policy: {}",
+        args.ofac_lookup_table_policy
+    );
     // Lookup table refresher
     let address_lookup_table_cache: Arc<DashMap<Pubkey, AddressLookupTableAccount>> =
         Arc::new(DashMap::new());
-    let lookup_table_refresher = if args.enable_lookup_table_refresh {
-        Some(start_lookup_table_refresher(
+
+    let lookup_table_fetcher = (args.ofac_lookup_table_policy == LookupTablePolicy::DropAndFetch)
+        .then(|| {
+            LookupTableFetcher::new(
+                &rpc_load_balancer,
+                &address_lookup_table_cache,
+                args.ofac_lookup_table_max_fetches_per_sec,
+                Duration::from_secs(args.ofac_lookup_table_fetch_retry_secs),
+                &exit,
+            )
+        });
+    let ofac_filter = OfacFilter::new(
+        ofac_addresses,
+        args.ofac_audit_log,
+        args.ofac_lookup_table_policy,
+        lookup_table_fetcher
+            .as_ref()
+            .map(LookupTableFetcher::handle),
+    );
+    let lookup_table_cache_updater = if args.enable_lookup_table_refresh {
+        Some(LookupTableCacheUpdater::new(
             &rpc_load_balancer,
             &address_lookup_table_cache,
             Duration::from_secs(args.lookup_table_refresh_secs),
+            args.lookup_table_snapshot_path
+                .clone()
+                .map(|path| LookupTableSnapshotConfig {
+                    path,
+                    interval: Duration::from_secs(args.lookup_table_snapshot_interval_secs),
+                }),
             &exit,
         ))
     } else {
@@ -698,13 +1236,45 @@ fn main() {
     let (block_engine_sender, block_engine_receiver) =
         channel(jito_transaction_relayer::forwarder::BLOCK_ENGINE_FORWARDER_QUEUE_CAPACITY);
 
+    // forwarding parameters shared with the forwarding threads and changeable through the admin api
+    let validator_policies = match &args.validator_policies_path {
+        Some(path) => match ValidatorPolicies::load(path) {
+            Ok(validator_policies) => {
+                info!(
+                    "loaded {} validator forwarding policies from {path:?}",
+                    validator_policies.len()
+                );
+                validator_policies
+            }
+            Err(e) => {
+                error!("error loading validator policies from {path:?}: {e}");
+                process::exit(1);
+            }
+        },
+        None => ValidatorPolicies::default(),
+    };
+    let forwarding_config = Arc::new(ForwardingConfig::new(
+        ForwardingParams {
+            packet_delay_ms: args.packet_delay_ms,
+            validator_packet_batch_size: args.validator_packet_batch_size,
+            forward_all: args.forward_all,
+            slot_lookahead: args.slot_lookahead,
+            previous_leader_grace_ms: args.previous_leader_grace_ms,
+            backpressure: args.backpressure,
+            slow_subscriber_timeout_ms: args.slow_subscriber_timeout_ms,
+        },
+        validator_policies,
+    ));
+
     let forward_and_delay_threads = start_forward_and_delay_thread(
         verified_receiver,
         delay_packet_sender,
-        args.packet_delay_ms,
+        &forwarding_config,
         block_engine_sender,
         1,
         args.disable_mempool,
+        args.dedup_mode,
+        Duration::from_millis(args.dedup_window_ms),
         &exit,
     );
 
@@ -729,7 +1299,7 @@ fn main() {
         args.aoi_cache_ttl_secs,
         address_lookup_table_cache.clone(),
         &is_connected_to_block_engine,
-        ofac_addresses.clone(),
+        ofac_filter.clone(),
     );
 
     // receiver tracked as relayer_metrics.slot_receiver_len
@@ -743,6 +1313,17 @@ fn main() {
         exit.clone(),
     );
 
+    let auth_event_logger = args.auth_event_log.as_ref().map(|path| {
+        AuthEventLogger::new(path.clone(), &exit).unwrap_or_else(|e| {
+            error!("error opening auth event log {path:?}: {e}");
+            process::exit(1);
+        })
+    });
+    let auth_events = auth_event_logger
+        .as_ref()
+        .map_or_else(AuthEventLog::disabled, AuthEventLogger::handle);
+    let sessions = SessionTable::new(auth_events.clone());
+
     let server_addr = SocketAddr::new(args.grpc_bind_ip, args.grpc_bind_port);
     let relayer_svc = RelayerImpl::new(
         downstream_slot_receiver,
@@ -753,44 +1334,176 @@ fn main() {
         tpu_quic_fwd_ports,
         health_manager.handle(),
         exit.clone(),
-        ofac_addresses,
+        ofac_filter,
         address_lookup_table_cache,
-        args.validator_packet_batch_size,
-        args.forward_all,
-        args.slot_lookahead,
+        forwarding_config.clone(),
+        sessions.clone(),
+        args.num_forwarding_threads,
     );
 
-    let priv_key = fs::read(&args.signing_key_pem_path).unwrap_or_else(|_| {
-        panic!(
-            "Failed to read signing key file: {:?}",
-            &args.verifying_key_pem_path
-        )
-    });
-    let signing_key = PKeyWithDigest {
-        digest: MessageDigest::sha256(),
-        key: PKey::private_key_from_pem(&priv_key).unwrap(),
+    let keyring = match (
+        &args.jwt_keyring_dir,
+        &args.signing_key_pem_path,
+        &args.verifying_key_pem_path,
+    ) {
+        (Some(dir), _, _) => Keyring::load_dir(dir),
+        (None, Some(signing_key_path), Some(verifying_key_path)) => {
+            Keyring::from_pem_files(signing_key_path, verifying_key_path)
+        }
+        // enforced by clap
+        _ => unreachable!("jwt_keyring_dir or both key paths are required"),
     };
-
-    let key = fs::read(&args.verifying_key_pem_path).unwrap_or_else(|_| {
-        panic!(
-            "Failed to read verifying key file: {:?}",
-            &args.verifying_key_pem_path
+    let keyring = match keyring {
+        Ok(keyring) => {
+            info!(
+                "jwt signing key: {} ({})",
+                keyring.signing_kid(),
+                keyring.signing_algorithm()
+            );
+            KeyringHandle::new(keyring)
+        }
+        Err(e) => {
+            error!("error loading jwt keys: {e}");
+            process::exit(1);
+        }
+    };
+    let keyring_updater = args.jwt_keyring_dir.clone().map(|dir| {
+        KeyringUpdater::new(
+            dir,
+            &keyring,
+            Duration::from_secs(args.jwt_keyring_refresh_secs),
+            // retired keys are kept until every token they signed has expired
+            Duration::from_secs(std::cmp::max(
+                args.access_token_ttl_secs,
+                args.refresh_token_ttl_secs,
+            )),
+            &exit,
         )
     });
-    let verifying_key = Arc::new(PKeyWithDigest {
-        digest: MessageDigest::sha256(),
-        key: PKey::public_key_from_pem(&key).unwrap(),
-    });
 
-    let validator_store = match args.allowed_validators {
-        Some(pubkeys) => ValidatorStore::UserDefined(HashSet::from_iter(pubkeys)),
-        None => ValidatorStore::LeaderSchedule(leader_cache.handle()),
+    let client_ip_binding = args
+        .enforce_client_ip_binding
+        .then(|| ClientIpBinding::new(args.client_ip_binding_allowed_cidrs.clone()));
+
+    // validators have to pass the allow rules and not be on the denylist
+    let validator_lists_refresh_interval = Duration::from_secs(args.validator_lists_refresh_secs);
+    let mut allow_rules: Vec<Box<dyn ValidatorAuther>> = Vec::new();
+    let allowlist_updater =
+        if args.allowed_validators.is_some() || args.allowed_validators_path.is_some() {
+            let (allowlist, allowlist_updater) = load_validator_list(
+                "allowlist",
+                args.allowed_validators.clone(),
+                args.allowed_validators_path.clone(),
+                validator_lists_refresh_interval,
+                &exit,
+            );
+            allow_rules.push(Box::new(Allowlist(allowlist)));
+            allowlist_updater
+        } else {
+            None
+        };
+    if let Some(min_stake_lamports) = args.min_validator_stake_lamports {
+        allow_rules.push(Box::new(MinimumStake::new(
+            tpu.staked_nodes(),
+            min_stake_lamports,
+        )));
+    }
+    if allow_rules.is_empty() || args.allow_scheduled_leaders {
+        allow_rules.push(Box::new(ScheduledLeaders(leader_cache.handle())));
+    }
+    info!(
+        "validators must pass {} of {} allow rules",
+        if args.require_all_validator_rules {
+            "all"
+        } else {
+            "any"
+        },
+        allow_rules.len()
+    );
+    let allow_rules: Box<dyn ValidatorAuther> = if args.require_all_validator_rules {
+        Box::new(AllOf(allow_rules))
+    } else {
+        Box::new(AnyOf(allow_rules))
     };
+    let (denylist, denylist_updater) = load_validator_list(
+        "denylist",
+        args.denied_validators.clone(),
+        args.denied_validators_path.clone(),
+        validator_lists_refresh_interval,
+        &exit,
+    );
+    let validator_auther = AllOf(vec![allow_rules, Box::new(Denylist(denylist))]);
+
+    let refresh_tokens = match RefreshTokenStore::new(args.refresh_token_store_path.as_deref()) {
+        Ok(refresh_tokens) => refresh_tokens,
+        Err(e) => {
+            error!(
+                "error loading refresh tokens from {:?}: {e}",
+                args.refresh_token_store_path
+            );
+            process::exit(1);
+        }
+    };
+    let refresh_token_persister = args
+        .refresh_token_store_path
+        .clone()
+        .map(|path| RefreshTokenPersister::new(&refresh_tokens, path, &exit));
+
+    let relayer_admin_svc = args.admin_grpc_bind_addr.map(|addr| {
+        let svc = RelayerAdminImpl::new(
+            relayer_svc.handle(),
+            leader_cache.handle(),
+            &rpc_load_balancer,
+            health_manager.inputs(),
+            &is_connected_to_block_engine,
+            refresh_tokens.clone(),
+        );
+        (addr, svc)
+    });
+
+    let grpc_tls_paths = args
+        .grpc_tls_cert_path
+        .clone()
+        .zip(args.grpc_tls_key_path.clone())
+        .map(|(cert_path, key_path)| ServerTlsPaths {
+            cert_path,
+            key_path,
+            client_ca_path: args.grpc_tls_client_ca_path.clone(),
+        });
+    let (server_tls, server_tls_updater) = grpc_tls_paths
+        .map(|paths| {
+            let server_tls = match paths.load() {
+                Ok(config) => ServerTlsHandle::new(config),
+                Err(e) => {
+                    error!("error loading grpc tls certificates from {paths:?}: {e}");
+                    process::exit(1);
+                }
+            };
+            info!(
+                "serving grpc over tls, client certificates {}",
+                if paths.client_ca_path.is_some() {
+                    "required"
+                } else {
+                    "not required"
+                }
+            );
+            let server_tls_updater = ServerTlsUpdater::new(
+                paths,
+                &server_tls,
+                Duration::from_secs(args.grpc_tls_refresh_secs),
+                &exit,
+            );
+            (server_tls, server_tls_updater)
+        })
+        .unzip();
 
     let relayer_state = Arc::new(RelayerState::new(
         health_manager.handle(),
         &is_connected_to_block_engine,
         relayer_svc.handle(),
+        &forwarding_config,
+        sessions.clone(),
+        args.admin_token,
     ));
 
     let rt = Builder::new_multi_thread().enable_all().build().unwrap();
@@ -806,29 +1519,65 @@ fn main() {
 
     rt.block_on(async {
         let auth_svc = AuthServiceImpl::new(
-            ValidatorAutherImpl {
-                store: validator_store,
-            },
-            signing_key,
-            verifying_key.clone(),
+            validator_auther,
+            keyring.clone(),
             Duration::from_secs(args.access_token_ttl_secs),
             Duration::from_secs(args.refresh_token_ttl_secs),
             Duration::from_secs(args.challenge_ttl_secs),
             Duration::from_secs(args.challenge_expiration_sleep_interval_secs),
+            args.max_auth_challenges_per_ip,
+            refresh_tokens,
+            args.enable_refresh_token_rotation,
+            AuthRateLimiter::new(
+                auth_rate_limit(args.auth_rate_limit_per_ip, args.auth_rate_limit_burst),
+                auth_rate_limit(args.auth_rate_limit_per_pubkey, args.auth_rate_limit_burst),
+            ),
+            auth_events,
+            sessions,
+            relayer_svc.handle(),
             &exit,
             health_manager.handle(),
         );
 
+        if let Some((admin_addr, relayer_admin_svc)) = relayer_admin_svc {
+            info!("starting relayer admin service at: {:?}", admin_addr);
+            // bind up front so a bad admin address fails startup instead of going unnoticed
+            let admin_incoming =
+                TcpIncoming::new(admin_addr, false, None).expect("bind relayer admin service");
+            let admin_server = Server::builder()
+                .add_service(RelayerAdminServer::new(relayer_admin_svc))
+                .serve_with_incoming_shutdown(admin_incoming, shutdown_signal(exit.clone()));
+            tokio::spawn(async move {
+                if let Err(e) = admin_server.await {
+                    error!("relayer admin service error: {e}");
+                }
+            });
+        }
+
         info!("starting relayer at: {:?}", server_addr);
-        Server::builder()
+        let router = Server::builder()
             .add_service(RelayerServer::with_interceptor(
                 relayer_svc,
-                AuthInterceptor::new(verifying_key.clone(), AlgorithmType::Rs256),
+                AuthInterceptor::new(keyring, client_ip_binding),
             ))
-            .add_service(AuthServiceServer::new(auth_svc))
-            .serve_with_shutdown(server_addr, shutdown_signal(exit.clone()))
-            .await
-            .expect("serve relayer");
+            .add_service(AuthServiceServer::new(auth_svc));
+        match server_tls {
+            Some(server_tls) => {
+                let listener = TcpListener::bind(server_addr).await.expect("bind relayer");
+                router
+                    .serve_with_incoming_shutdown(
+                        server_tls.incoming(listener),
+                        shutdown_signal(exit.clone()),
+                    )
+                    .await
+            }
+            None => {
+                router
+                    .serve_with_shutdown(server_addr, shutdown_signal(exit.clone()))
+                    .await
+            }
+        }
+        .expect("serve relayer");
     });
 
     exit.store(true, Ordering::Relaxed);
@@ -839,8 +1588,29 @@ fn main() {
     for t in forward_and_delay_threads {
         t.join().unwrap();
     }
-    if let Some(lookup_table_refresher) = lookup_table_refresher {
-        lookup_table_refresher.join().unwrap();
+    if let Some(lookup_table_cache_updater) = lookup_table_cache_updater {
+        lookup_table_cache_updater.join().unwrap();
+    }
+    if let Some(lookup_table_fetcher) = lookup_table_fetcher {
+        lookup_table_fetcher.join().unwrap();
+    }
+    if let Some(ofac_addresses_updater) = ofac_addresses_updater {
+        ofac_addresses_updater.join().unwrap();
+    }
+    if let Some(keyring_updater) = keyring_updater {
+        keyring_updater.join().unwrap();
+    }
+    for validator_list_updater in [allowlist_updater, denylist_updater].into_iter().flatten() {
+        validator_list_updater.join().unwrap();
+    }
+    if let Some(server_tls_updater) = server_tls_updater {
+        server_tls_updater.join().unwrap();
+    }
+    if let Some(auth_event_logger) = auth_event_logger {
+        auth_event_logger.join().unwrap();
+    }
+    if let Some(refresh_token_persister) = refresh_token_persister {
+        refresh_token_persister.join().unwrap();
     }
     block_engine_forwarder.join();
 }
@@ -871,118 +1641,42 @@ pub async fn shutdown_signal(exit: Arc<AtomicBool>) {
     warn!("signal received, starting graceful shutdown");
 }
 
-enum ValidatorStore {
-    LeaderSchedule(LeaderScheduleUpdatingHandle),
-    UserDefined(HashSet<Pubkey>),
-}
-
-struct ValidatorAutherImpl {
-    store: ValidatorStore,
-}
-
-impl ValidatorAuther for ValidatorAutherImpl {
-    fn is_authorized(&self, pubkey: &Pubkey) -> bool {
-        match &self.store {
-            ValidatorStore::LeaderSchedule(cache) => cache.is_scheduled_validator(pubkey),
-            ValidatorStore::UserDefined(pubkeys) => pubkeys.contains(pubkey),
-        }
-    }
-}
-
-fn start_lookup_table_refresher(
-    rpc_load_balancer: &Arc<LoadBalancer>,
-    lookup_table: &Arc<DashMap<Pubkey, AddressLookupTableAccount>>,
-    refresh_duration: Duration,
+/// Loads a validator pubkey list from the command line and an optional file, starting an
+/// updater that reloads the file when it changes. Exits if the file can't be loaded.
+fn load_validator_list(
+    name: &'static str,
+    pubkeys: Option<Vec<Pubkey>>,
+    path: Option<PathBuf>,
+    refresh_interval: Duration,
     exit: &Arc<AtomicBool>,
-) -> JoinHandle<()> {
-    let rpc_load_balancer = rpc_load_balancer.clone();
-    let exit = exit.clone();
-    let lookup_table = lookup_table.clone();
-
-    thread::Builder::new()
-        .name("lookup_table_refresher".to_string())
-        .spawn(move || {
-            // seed lookup table
-            if let Err(e) = refresh_address_lookup_table(&rpc_load_balancer, &lookup_table) {
-                error!("error refreshing address lookup table: {e:?}");
-            }
-
-            let tick_receiver = tick(Duration::from_secs(1));
-            let mut last_refresh = Instant::now();
-
-            while !exit.load(Ordering::Relaxed) {
-                let _ = tick_receiver.recv();
-                if last_refresh.elapsed() < refresh_duration {
-                    continue;
-                }
-
-                let now = Instant::now();
-                let refresh_result =
-                    refresh_address_lookup_table(&rpc_load_balancer, &lookup_table);
-                let updated_elapsed = now.elapsed().as_micros();
-                match refresh_result {
-                    Ok(_) => {
-                        datapoint_info!(
-                            "lookup_table_refresher-ok",
-                            ("count", 1, i64),
-                            ("lookup_table_size", lookup_table.len(), i64),
-                            ("updated_elapsed_us", updated_elapsed, i64),
-                        );
-                    }
-                    Err(e) => {
-                        datapoint_error!(
-                            "lookup_table_refresher-error",
-                            ("count", 1, i64),
-                            ("lookup_table_size", lookup_table.len(), i64),
-                            ("updated_elapsed_us", updated_elapsed, i64),
-                            ("error", e.to_string(), String),
-                        );
-                    }
-                }
-                last_refresh = Instant::now();
-            }
-        })
-        .unwrap()
-}
-
-fn refresh_address_lookup_table(
-    rpc_load_balancer: &Arc<LoadBalancer>,
-    lookup_table: &DashMap<Pubkey, AddressLookupTableAccount>,
-) -> solana_client::client_error::Result<()> {
-    let rpc_client = rpc_load_balancer.rpc_client();
-
-    let address_lookup_table =
-        Pubkey::from_str("AddressLookupTab1e1111111111111111111111111").unwrap();
-    let start = Instant::now();
-    let accounts = rpc_client.get_program_accounts(&address_lookup_table)?;
-    info!(
-        "Fetched {} lookup tables from RPC in {:?}",
-        accounts.len(),
-        start.elapsed()
-    );
+) -> (PubkeyListHandle, Option<PubkeyListUpdater>) {
+    let static_pubkeys: HashSet<Pubkey> = pubkeys.unwrap_or_default().into_iter().collect();
+    let handle = PubkeyListHandle::new(static_pubkeys.clone());
+    let Some(path) = path else {
+        info!("validator {name}: {static_pubkeys:?}");
+        return (handle, None);
+    };
 
-    let mut new_pubkeys = HashSet::new();
-    for (pubkey, account_data) in accounts {
-        match AddressLookupTable::deserialize(&account_data.data) {
-            Err(e) => {
-                error!("error deserializing AddressLookupTable pubkey: {pubkey}, error: {e}");
-            }
-            Ok(table) => {
-                debug!("lookup table loaded pubkey: {pubkey:?}, table: {table:?}");
-                new_pubkeys.insert(pubkey);
-                lookup_table.insert(
-                    pubkey,
-                    AddressLookupTableAccount {
-                        key: pubkey,
-                        addresses: table.addresses.to_vec(),
-                    },
-                );
-            }
+    match PubkeyListUpdater::new(
+        name,
+        path.clone(),
+        static_pubkeys,
+        &handle,
+        refresh_interval,
+        exit,
+    ) {
+        Ok(updater) => (handle, Some(updater)),
+        Err(e) => {
+            error!("error loading validator {name} from {path:?}: {e}");
+            process::exit(1);
         }
     }
+}
 
-    // remove all the closed lookup tables
-    lookup_table.retain(|pubkey, _| new_pubkeys.contains(pubkey));
-
-    Ok(())
+/// An auth service rate limit, or None if a rate of 0 disables it
+fn auth_rate_limit(requests_per_sec: f64, burst: u32) -> Option<RateLimit> {
+    (requests_per_sec > 0.0).then_some(RateLimit {
+        requests_per_sec,
+        burst,
+    })
 }
@@ -1,18 +1,25 @@
 use std::{
-    net::SocketAddr,
+    net::{IpAddr, SocketAddr},
     sync::{
         atomic::{AtomicBool, Ordering},
         Arc, RwLock,
     },
-    time::Duration,
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
 use axum::{
-    error_handling::HandleErrorLayer, http::StatusCode, routing::get, BoxError, Extension, Json,
-    Router,
+    error_handling::HandleErrorLayer,
+    http::{header::AUTHORIZATION, HeaderMap, StatusCode},
+    routing::get,
+    BoxError, Extension, Json, Router,
 };
-use jito_relayer::{health_manager::HealthState, relayer::RelayerHandle};
-use log::debug;
+use jito_relayer::{
+    forwarding_config::{ForwardingConfig, ForwardingParams, ForwardingParamsUpdate},
+    health_manager::HealthState,
+    relayer::RelayerHandle,
+    sessions::SessionTable,
+};
+use log::{debug, warn};
 use serde::Serialize;
 use tower::{buffer::BufferLayer, limit::RateLimitLayer, ServiceBuilder};
 
@@ -21,6 +28,10 @@ pub struct RelayerState {
     slot_health: Arc<RwLock<HealthState>>,
     is_connected_to_block_engine: Arc<AtomicBool>,
     relayer_handle: RelayerHandle,
+    forwarding_config: Arc<ForwardingConfig>,
+    sessions: SessionTable,
+    /// Bearer token required by the admin endpoints; they're disabled when unset
+    admin_token: Option<String>,
 }
 
 impl RelayerState {
@@ -28,13 +39,42 @@ impl RelayerState {
         slot_health: Arc<RwLock<HealthState>>,
         is_connected_to_block_engine: &Arc<AtomicBool>,
         relayer_handle: RelayerHandle,
+        forwarding_config: &Arc<ForwardingConfig>,
+        sessions: SessionTable,
+        admin_token: Option<String>,
     ) -> RelayerState {
         RelayerState {
             slot_health,
             is_connected_to_block_engine: is_connected_to_block_engine.clone(),
             relayer_handle,
+            forwarding_config: forwarding_config.clone(),
+            sessions,
+            admin_token,
         }
     }
+
+    /// Checks the request carries `Authorization: Bearer <admin token>`
+    fn authorize_admin(&self, headers: &HeaderMap) -> Result<(), (StatusCode, String)> {
+        let Some(admin_token) = &self.admin_token else {
+            return Err((StatusCode::NOT_FOUND, "admin api disabled".to_string()));
+        };
+        let authorized = headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .is_some_and(|token| constant_time_eq(token.as_bytes(), admin_token.as_bytes()));
+        if authorized {
+            Ok(())
+        } else {
+            warn!("rejected unauthorized admin request");
+            Err((StatusCode::UNAUTHORIZED, "unauthorized".to_string()))
+        }
+    }
+}
+
+/// Compares without short-circuiting so response timing doesn't leak the token
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
 }
 
 #[derive(Serialize, Debug)]
@@ -44,6 +84,19 @@ pub struct RelayerStatus {
     validators_connected: Vec<String>,
 }
 
+/// A validator's session, with timestamps in unix seconds
+#[derive(Serialize, Debug)]
+pub struct SessionStatus {
+    pubkey: String,
+    client_ip: Option<IpAddr>,
+    authenticated_at: Option<i64>,
+    access_token_expires_at: Option<i64>,
+    refresh_token_expires_at: Option<i64>,
+    last_refresh_at: Option<i64>,
+    /// How long the validator has been subscribed to packets, unset if it isn't
+    connected_secs: Option<i64>,
+}
+
 /// Returns an axum router with endpoints to get status of relayer
 pub fn build_relayer_router(
     state: Arc<RelayerState>,
@@ -89,10 +142,65 @@ pub fn build_relayer_router(
         Json(status)
     }
 
+    /// Returns the current forwarding parameters
+    async fn get_forwarding_params(
+        Extension(state): Extension<Arc<RelayerState>>,
+        headers: HeaderMap,
+    ) -> Result<Json<ForwardingParams>, (StatusCode, String)> {
+        state.authorize_admin(&headers)?;
+        Ok(Json(state.forwarding_config.params()))
+    }
+
+    /// Updates the forwarding parameters present in the request body and returns the result
+    async fn update_forwarding_params(
+        Extension(state): Extension<Arc<RelayerState>>,
+        headers: HeaderMap,
+        Json(update): Json<ForwardingParamsUpdate>,
+    ) -> Result<Json<ForwardingParams>, (StatusCode, String)> {
+        state.authorize_admin(&headers)?;
+        state
+            .forwarding_config
+            .update(&update, "web_admin")
+            .map(Json)
+            .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))
+    }
+
+    /// Returns every validator session, from authentication through packet subscription
+    async fn get_sessions(
+        Extension(state): Extension<Arc<RelayerState>>,
+        headers: HeaderMap,
+    ) -> Result<Json<Vec<SessionStatus>>, (StatusCode, String)> {
+        state.authorize_admin(&headers)?;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64;
+        let sessions = state
+            .sessions
+            .sessions()
+            .into_iter()
+            .map(|session| SessionStatus {
+                pubkey: session.pubkey.to_string(),
+                client_ip: session.client_ip,
+                authenticated_at: session.authenticated_at_unix_ts,
+                access_token_expires_at: session.access_token_expires_at_unix_ts,
+                refresh_token_expires_at: session.refresh_token_expires_at_unix_ts,
+                last_refresh_at: session.last_refresh_at_unix_ts,
+                connected_secs: session.connected_secs(now),
+            })
+            .collect();
+        Ok(Json(sessions))
+    }
+
     Router::new()
         .route("/", get(homepage))
         .route("/health", get(get_health))
         .route("/status", get(get_status))
+        .route(
+            "/admin/forwarding",
+            get(get_forwarding_params).post(update_forwarding_params),
+        )
+        .route("/admin/sessions", get(get_sessions))
         .layer(
             ServiceBuilder::new()
                 .layer(HandleErrorLayer::new(|err: BoxError| async move {